@@ -0,0 +1,769 @@
+use crate::exporter::{favorite_recipes, read_local_image, validate_export_path};
+use crate::health::{
+    load_health_profile, parse_versioned_profile, save_health_profile, versioned_profile,
+    HealthProfile,
+};
+use crate::images::{
+    image_extension, image_url as store_url, is_image_name, is_portable_image_url, sha256_hex,
+    store_image,
+};
+use crate::profiles::ProfileState;
+use crate::recipe::{load_all_recipes, require_recipe, StoredRecipe};
+use crate::DatabaseState;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Cursor, Read, Seek, Write};
+use std::path::Path;
+use tauri::State;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const BUNDLE_FORMAT: &str = "chefmind-bundle";
+const BUNDLE_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const RECIPES_ENTRY: &str = "recipes.json";
+const HEALTH_PROFILE_ENTRY: &str = "health_profile.json";
+const MAX_RECIPES_ENTRY_BYTES: u64 = 50 * 1024 * 1024;
+const MAX_IMAGE_ENTRY_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_BUNDLE_RECIPES: usize = 5_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format: String,
+    pub version: u32,
+    pub app_version: String,
+    pub exported_at: String,
+    pub recipe_count: usize,
+    pub image_count: usize,
+    /// Whether the bundle carries the exporting profile's health profile.
+    #[serde(default)]
+    pub health_profile: bool,
+}
+
+/// One recipe row as stored in `recipes.json`. JSON columns are embedded as values so
+/// the content hash does not depend on how the source install formatted them.
+/// Only recipe content is carried; keyring entries and provider settings never are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleRecipe {
+    pub content_hash: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub ingredients: serde_json::Value,
+    pub instructions: serde_json::Value,
+    pub cooking_time: Option<i64>,
+    pub difficulty: Option<String>,
+    pub servings: Option<i64>,
+    pub category: Option<String>,
+    pub tags: serde_json::Value,
+    pub nutrition_info: serde_json::Value,
+    pub cooking_methods: serde_json::Value,
+    pub ai_provider: Option<String>,
+    pub ai_model: Option<String>,
+    /// Remote or data URI image, kept as-is.
+    pub image_url: Option<String>,
+    /// Bundle-relative path of an embedded local image, e.g. `images/<sha256>.jpg`.
+    pub image: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BundleExportResult {
+    pub path: String,
+    pub recipe_count: usize,
+    pub image_count: usize,
+    /// Recipes whose local image file could not be read and was left out.
+    pub missing_images: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BundleImportResult {
+    pub imported_recipe_ids: Vec<i64>,
+    pub skipped_duplicates: usize,
+    /// Existing recipes that skipped duplicates matched. Their favorite flag and notes
+    /// are applied to these when importing into favorites.
+    pub existing_recipe_ids: Vec<i64>,
+    pub image_count: usize,
+    pub health_profile_restored: bool,
+}
+
+fn column_value(raw: Option<&str>) -> serde_json::Value {
+    match raw {
+        Some(text) => serde_json::from_str(text)
+            .unwrap_or_else(|_| serde_json::Value::String(text.to_string())),
+        None => serde_json::Value::Null,
+    }
+}
+
+fn column_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(text) => Some(text.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+impl BundleRecipe {
+    fn from_stored(recipe: &StoredRecipe) -> Self {
+        let mut bundled = Self {
+            content_hash: String::new(),
+            title: recipe.title.trim().to_string(),
+            description: recipe.description.clone(),
+            ingredients: column_value(Some(&recipe.ingredients)),
+            instructions: column_value(Some(&recipe.instructions)),
+            cooking_time: recipe.cooking_time,
+            difficulty: recipe.difficulty.clone(),
+            servings: recipe.servings,
+            category: recipe.category.clone(),
+            tags: column_value(recipe.tags.as_deref()),
+            nutrition_info: column_value(recipe.nutrition_info.as_deref()),
+            cooking_methods: column_value(recipe.cooking_methods.as_deref()),
+            ai_provider: recipe.ai_provider.clone(),
+            ai_model: recipe.ai_model.clone(),
+            image_url: None,
+            image: None,
+            notes: None,
+        };
+        bundled.content_hash = bundled.compute_hash();
+        bundled
+    }
+
+    /// Hashes the cooking content only, so the same recipe matches across installs
+    /// regardless of image location, provenance or notes.
+    fn compute_hash(&self) -> String {
+        let content = serde_json::json!([
+            self.title.trim(),
+            self.description,
+            self.ingredients,
+            self.instructions,
+            self.cooking_time,
+            self.difficulty,
+            self.servings,
+            self.category,
+            self.tags,
+            self.nutrition_info,
+            self.cooking_methods,
+        ]);
+        sha256_hex(content.to_string().as_bytes())
+    }
+}
+
+fn favorite_notes(conn: &Connection, session_id: &str) -> Result<HashMap<i64, String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT recipe_id, notes FROM favorites
+             WHERE session_id = ?1 AND notes IS NOT NULL AND TRIM(notes) != ''
+             ORDER BY created_at",
+        )
+        .map_err(|e| format!("Failed to load favorite notes: {}", e))?;
+    let rows = stmt
+        .query_map([session_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load favorite notes: {}", e))?;
+    let mut notes = HashMap::new();
+    for (recipe_id, note) in rows {
+        notes.entry(recipe_id).or_insert(note);
+    }
+    Ok(notes)
+}
+
+/// Favorites a recipe for the session. A favorite that already exists keeps its own
+/// notes and only takes the bundled ones when it has none.
+fn add_favorite(
+    conn: &Connection,
+    session_id: &str,
+    recipe_id: i64,
+    notes: Option<&str>,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO favorites (session_id, recipe_id, recipe_title, recipe_image, notes)
+         SELECT ?1, id, title, image_url, ?3 FROM recipes WHERE id = ?2
+         ON CONFLICT(session_id, recipe_id) DO UPDATE SET
+             notes = COALESCE(NULLIF(TRIM(favorites.notes), ''), excluded.notes),
+             updated_at = CURRENT_TIMESTAMP",
+        params![session_id, recipe_id, notes],
+    )
+    .map_err(|e| format!("Failed to import favorite: {}", e))?;
+    Ok(())
+}
+
+pub(crate) fn write_bundle<W: Write + Seek>(
+    writer: W,
+    conn: &Connection,
+    images_dir: &Path,
+    recipes: &[StoredRecipe],
+    notes: &HashMap<i64, String>,
+    health_profile: Option<HealthProfile>,
+) -> Result<BundleExportResult, String> {
+    let zip_error = |e: zip::result::ZipError| format!("Failed to write bundle: {}", e);
+    let io_error = |e: std::io::Error| format!("Failed to write bundle: {}", e);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(writer);
+    let mut written_images = HashSet::new();
+    let mut missing_images = 0;
+
+    let mut rows = Vec::with_capacity(recipes.len());
+    for recipe in recipes {
+        let mut bundled = BundleRecipe::from_stored(recipe);
+        bundled.notes = notes.get(&recipe.id).cloned();
+        if let Some(url) = recipe
+            .image_url
+            .as_deref()
+            .filter(|url| !url.trim().is_empty())
+        {
+            if is_portable_image_url(url) {
+                bundled.image_url = Some(url.to_string());
+            } else if let Some((mime, bytes)) = read_local_image(images_dir, url) {
+                let name = format!("images/{}.{}", sha256_hex(&bytes), image_extension(mime));
+                if written_images.insert(name.clone()) {
+                    // Images are already compressed; storing them avoids wasted work.
+                    zip.start_file(
+                        name.as_str(),
+                        options.compression_method(CompressionMethod::Stored),
+                    )
+                    .map_err(zip_error)?;
+                    zip.write_all(&bytes).map_err(io_error)?;
+                }
+                bundled.image = Some(name);
+            } else {
+                missing_images += 1;
+            }
+        }
+        rows.push(bundled);
+    }
+
+    let exported_at: String = conn
+        .query_row("SELECT CURRENT_TIMESTAMP", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at,
+        recipe_count: rows.len(),
+        image_count: written_images.len(),
+        health_profile: health_profile.is_some(),
+    };
+    let mut entries = vec![
+        (MANIFEST_ENTRY, serde_json::to_vec_pretty(&manifest)),
+        (RECIPES_ENTRY, serde_json::to_vec_pretty(&rows)),
+    ];
+    if let Some(profile) = health_profile {
+        entries.push((
+            HEALTH_PROFILE_ENTRY,
+            serde_json::to_vec_pretty(&versioned_profile(profile)?),
+        ));
+    }
+    for (name, value) in entries {
+        let bytes = value.map_err(|e| format!("Failed to write bundle: {}", e))?;
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(&bytes).map_err(io_error)?;
+    }
+    zip.finish().map_err(zip_error)?;
+
+    Ok(BundleExportResult {
+        path: String::new(),
+        recipe_count: rows.len(),
+        image_count: written_images.len(),
+        missing_images,
+    })
+}
+
+fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    limit: u64,
+) -> Result<Vec<u8>, String> {
+    let entry = archive
+        .by_name(name)
+        .map_err(|_| format!("Bundle is missing {name}"))?;
+    // The declared size can lie, so cap what is actually decompressed.
+    let mut bytes = Vec::new();
+    entry
+        .take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read bundle: {}", e))?;
+    if bytes.len() as u64 > limit {
+        return Err(format!("Bundle entry {name} is too large"));
+    }
+    Ok(bytes)
+}
+
+pub(crate) fn read_bundle<R: Read + Seek>(
+    reader: R,
+    conn: &mut Connection,
+    images_dir: &Path,
+    session_id: Option<&str>,
+    profile_session_id: Option<&str>,
+) -> Result<BundleImportResult, String> {
+    let mut archive =
+        ZipArchive::new(reader).map_err(|_| "File is not a valid .chefmind bundle".to_string())?;
+    let manifest: BundleManifest =
+        serde_json::from_slice(&read_entry(&mut archive, MANIFEST_ENTRY, 64 * 1024)?)
+            .map_err(|_| "Bundle manifest is invalid".to_string())?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err("File is not a valid .chefmind bundle".to_string());
+    }
+    if manifest.version > BUNDLE_VERSION {
+        return Err("Bundle was created by a newer version of ChefMind".to_string());
+    }
+    let rows: Vec<BundleRecipe> = serde_json::from_slice(&read_entry(
+        &mut archive,
+        RECIPES_ENTRY,
+        MAX_RECIPES_ENTRY_BYTES,
+    )?)
+    .map_err(|_| "Bundle recipes are invalid".to_string())?;
+    if rows.len() > MAX_BUNDLE_RECIPES {
+        return Err("Bundle contains too many recipes".to_string());
+    }
+
+    // Parsed up front so a bad profile fails the import before anything is written.
+    let health_profile = match profile_session_id {
+        Some(_) if manifest.health_profile => {
+            let stored =
+                serde_json::from_slice(&read_entry(&mut archive, HEALTH_PROFILE_ENTRY, 64 * 1024)?)
+                    .map_err(|_| "Bundle health profile is invalid".to_string())?;
+            Some(parse_versioned_profile(stored)?)
+        }
+        _ => None,
+    };
+
+    let mut known_recipes: HashMap<String, i64> = load_all_recipes(conn)?
+        .iter()
+        .map(|recipe| (BundleRecipe::from_stored(recipe).content_hash, recipe.id))
+        .collect();
+    let mut extracted_images = HashMap::new();
+    let mut imported_recipe_ids = Vec::new();
+    let mut skipped_duplicates = 0;
+    let mut existing_recipe_ids = Vec::new();
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    for row in rows {
+        // Recompute rather than trust the stored hash, which a hand-edited bundle may not update.
+        let content_hash = row.compute_hash();
+        if row.title.trim().is_empty() {
+            skipped_duplicates += 1;
+            continue;
+        }
+        if let Some(&recipe_id) = known_recipes.get(&content_hash) {
+            skipped_duplicates += 1;
+            if let Some(session_id) = session_id {
+                add_favorite(&tx, session_id, recipe_id, row.notes.as_deref())?;
+            }
+            if !imported_recipe_ids.contains(&recipe_id)
+                && !existing_recipe_ids.contains(&recipe_id)
+            {
+                existing_recipe_ids.push(recipe_id);
+            }
+            continue;
+        }
+
+        let mut image_url = row
+            .image_url
+            .clone()
+            .filter(|url| is_portable_image_url(url));
+        if let Some(name) = row
+            .image
+            .as_deref()
+            .and_then(|path| path.strip_prefix("images/"))
+        {
+            if !is_image_name(name) {
+                return Err("Bundle contains an invalid image path".to_string());
+            }
+            if !extracted_images.contains_key(name) {
+                let stored = if images_dir.join(name).exists() {
+                    store_url(name)
+                } else {
+                    let bytes = read_entry(
+                        &mut archive,
+                        &format!("images/{name}"),
+                        MAX_IMAGE_ENTRY_BYTES,
+                    )?;
+                    if !name.starts_with(&sha256_hex(&bytes)) {
+                        return Err("Bundle image does not match its checksum".to_string());
+                    }
+                    let extension = name
+                        .split_once('.')
+                        .map_or("jpg", |(_, extension)| extension);
+                    store_image(images_dir, &bytes, extension)?
+                };
+                extracted_images.insert(name.to_string(), stored);
+            }
+            image_url = extracted_images.get(name).cloned();
+        }
+
+        tx.execute(
+            "INSERT INTO recipes (title, description, ingredients, instructions, cooking_time, difficulty,
+                 servings, category, tags, nutrition_info, image_url, cooking_methods, ai_provider, ai_model)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                row.title.trim(),
+                row.description,
+                column_text(&row.ingredients).unwrap_or_else(|| "[]".to_string()),
+                column_text(&row.instructions).unwrap_or_else(|| "[]".to_string()),
+                row.cooking_time,
+                row.difficulty,
+                row.servings.unwrap_or(4),
+                row.category,
+                column_text(&row.tags),
+                column_text(&row.nutrition_info),
+                image_url,
+                column_text(&row.cooking_methods),
+                row.ai_provider,
+                row.ai_model,
+            ],
+        )
+        .map_err(|e| format!("Failed to import recipe: {}", e))?;
+        let recipe_id = tx.last_insert_rowid();
+        known_recipes.insert(content_hash, recipe_id);
+
+        if let Some(session_id) = session_id {
+            add_favorite(&tx, session_id, recipe_id, row.notes.as_deref())?;
+        }
+        imported_recipe_ids.push(recipe_id);
+    }
+    let health_profile_restored = match (profile_session_id, health_profile) {
+        (Some(profile_session_id), Some(profile)) => {
+            save_health_profile(&tx, profile_session_id, profile)?;
+            true
+        }
+        _ => false,
+    };
+    tx.commit()
+        .map_err(|e| format!("Failed to import bundle: {}", e))?;
+
+    Ok(BundleImportResult {
+        imported_recipe_ids,
+        skipped_duplicates,
+        existing_recipe_ids,
+        image_count: extracted_images.len(),
+        health_profile_restored,
+    })
+}
+
+/// Writes a `.chefmind` bundle of the given recipes, or of every favorite of the
+/// active profile. Its favorite notes are included only when `include_notes` is set,
+/// and its health profile only when `include_health_profile` is set.
+#[tauri::command]
+pub(crate) fn bundle_export(
+    path: String,
+    recipe_ids: Option<Vec<i64>>,
+    favorites: Option<bool>,
+    include_notes: Option<bool>,
+    include_health_profile: Option<bool>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<BundleExportResult, String> {
+    let target = validate_export_path(&path)?;
+    let conn = db.open_connection()?;
+    let recipes = match (recipe_ids, favorites.unwrap_or(false)) {
+        (Some(ids), _) if !ids.is_empty() && ids.len() <= MAX_BUNDLE_RECIPES => ids
+            .into_iter()
+            .map(|id| require_recipe(&conn, id))
+            .collect::<Result<Vec<_>, _>>()?,
+        (None, true) => favorite_recipes(&conn, &profiles.session_id()?)?,
+        _ => return Err("Provide either recipe ids or favorites to export".to_string()),
+    };
+    if recipes.is_empty() {
+        return Err("There are no recipes to export".to_string());
+    }
+    let notes = if include_notes.unwrap_or(false) {
+        favorite_notes(&conn, &profiles.session_id()?)?
+    } else {
+        HashMap::new()
+    };
+    let health_profile = if include_health_profile.unwrap_or(false) {
+        load_health_profile(&conn, &profiles.session_id()?)?
+    } else {
+        None
+    };
+
+    let mut buffer = Cursor::new(Vec::new());
+    let result = write_bundle(
+        &mut buffer,
+        &conn,
+        &db.images_dir(),
+        &recipes,
+        &notes,
+        health_profile,
+    )?;
+    fs::write(target, buffer.into_inner()).map_err(|e| format!("Failed to write bundle: {}", e))?;
+    Ok(BundleExportResult { path, ..result })
+}
+
+/// Imports a `.chefmind` bundle, skipping recipes whose content already exists. With
+/// `add_to_favorites` the imported recipes, and the existing ones that duplicates
+/// matched, are added to the active profile's favorites along with any notes. With `restore_health_profile` a bundled health profile
+/// replaces the active profile's one.
+#[tauri::command]
+pub(crate) fn bundle_import(
+    path: String,
+    add_to_favorites: Option<bool>,
+    restore_health_profile: Option<bool>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<BundleImportResult, String> {
+    let file = fs::File::open(&path).map_err(|e| format!("Failed to read bundle: {}", e))?;
+    let session_id = add_to_favorites
+        .unwrap_or(false)
+        .then(|| profiles.session_id())
+        .transpose()?;
+    let profile_session_id = restore_health_profile
+        .unwrap_or(false)
+        .then(|| profiles.session_id())
+        .transpose()?;
+    let mut conn = db.open_connection()?;
+    read_bundle(
+        file,
+        &mut conn,
+        &db.images_dir(),
+        session_id.as_deref(),
+        profile_session_id.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::images::resolve_image_path;
+    use crate::initialize_schema;
+    use crate::recipe::{insert_recipe, load_recipe, RecipeDraft};
+
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("chefmind-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create scratch dir");
+        dir
+    }
+
+    fn database() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        conn
+    }
+
+    fn draft(title: &str, image_url: Option<String>) -> RecipeDraft {
+        RecipeDraft {
+            title: title.to_string(),
+            ingredients: vec!["番茄 2个".to_string()],
+            instructions: vec!["翻炒".to_string()],
+            image_url,
+            ..RecipeDraft::default()
+        }
+    }
+
+    #[test]
+    fn round_trips_recipes_images_and_notes() {
+        let dir = scratch_dir("bundle-round-trip");
+        let photo = store_image(&dir, b"\x89PNG fake image bytes", "png").expect("store photo");
+        let outside = dir.join("secret.png");
+        fs::write(&outside, b"\x89PNG not in the image store").expect("write outside file");
+
+        let source = database();
+        let with_image = insert_recipe(&source, &draft("番茄炒蛋", Some(photo))).expect("insert");
+        let remote = insert_recipe(
+            &source,
+            &draft("红烧肉", Some("https://example.com/pork.jpg".to_string())),
+        )
+        .expect("insert");
+        let local_path = insert_recipe(
+            &source,
+            &draft("清蒸鱼", Some(outside.to_string_lossy().to_string())),
+        )
+        .expect("insert");
+        source
+            .execute(
+                "INSERT INTO favorites (session_id, recipe_id, notes) VALUES ('alice', ?1, '少放糖')",
+                [with_image],
+            )
+            .expect("insert favorite");
+
+        let recipes = vec![
+            require_recipe(&source, with_image).expect("load"),
+            require_recipe(&source, remote).expect("load"),
+            require_recipe(&source, local_path).expect("load"),
+        ];
+        let notes = favorite_notes(&source, "alice").expect("notes");
+        let mut buffer = Cursor::new(Vec::new());
+        let exported =
+            write_bundle(&mut buffer, &source, &dir, &recipes, &notes, None).expect("export");
+        assert_eq!((exported.recipe_count, exported.image_count), (3, 1));
+        assert_eq!(exported.missing_images, 1);
+
+        let mut archive = ZipArchive::new(Cursor::new(buffer.get_ref().clone())).expect("zip");
+        let names: Vec<_> = archive.file_names().map(str::to_string).collect();
+        assert!(names.iter().all(|name| name == MANIFEST_ENTRY
+            || name == RECIPES_ENTRY
+            || name.starts_with("images/")));
+        let recipes_json =
+            String::from_utf8(read_entry(&mut archive, RECIPES_ENTRY, 1 << 20).expect("read"))
+                .expect("utf-8");
+        assert!(!recipes_json.contains(&dir.to_string_lossy().to_string()));
+
+        let mut target = database();
+        let images_dir = dir.join("target-images");
+        let imported = read_bundle(
+            Cursor::new(buffer.into_inner()),
+            &mut target,
+            &images_dir,
+            Some("bob"),
+            None,
+        )
+        .expect("import");
+        assert_eq!(imported.imported_recipe_ids.len(), 3);
+        assert_eq!(imported.image_count, 1);
+
+        let copy = load_recipe(&target, imported.imported_recipe_ids[0])
+            .expect("load")
+            .expect("recipe exists");
+        let image_url = copy.image_url.expect("image url");
+        let image_path = resolve_image_path(&images_dir, &image_url).expect("store url");
+        assert_eq!(
+            fs::read(&image_path).expect("read image"),
+            b"\x89PNG fake image bytes"
+        );
+        let note: String = target
+            .query_row(
+                "SELECT notes FROM favorites WHERE session_id = 'bob' AND recipe_id = ?1",
+                [imported.imported_recipe_ids[0]],
+                |row| row.get(0),
+            )
+            .expect("imported note");
+        assert_eq!(note, "少放糖");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn skips_recipes_that_already_exist() {
+        let dir = scratch_dir("bundle-dedup");
+        let conn = database();
+        let recipe_id = insert_recipe(&conn, &draft("番茄炒蛋", None)).expect("insert");
+        let recipes = vec![require_recipe(&conn, recipe_id).expect("load")];
+        let mut buffer = Cursor::new(Vec::new());
+        write_bundle(&mut buffer, &conn, &dir, &recipes, &HashMap::new(), None).expect("export");
+        let bytes = buffer.into_inner();
+
+        let mut target = database();
+        let first = read_bundle(Cursor::new(bytes.clone()), &mut target, &dir, None, None)
+            .expect("first import");
+        assert_eq!(first.imported_recipe_ids.len(), 1);
+        let second =
+            read_bundle(Cursor::new(bytes), &mut target, &dir, None, None).expect("second import");
+        assert!(second.imported_recipe_ids.is_empty());
+        assert_eq!(second.skipped_duplicates, 1);
+
+        assert!(read_bundle(
+            Cursor::new(b"not a zip".to_vec()),
+            &mut target,
+            &dir,
+            None,
+            None
+        )
+        .is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn round_trips_the_health_profile_when_asked() {
+        let dir = scratch_dir("bundle-health-profile");
+        let source = database();
+        let profile: HealthProfile = serde_json::from_value(serde_json::json!({
+            "name": "Alice",
+            "age": 34,
+            "gender": "female",
+            "height": 168,
+            "weight": 58,
+            "activityLevel": "moderate",
+            "healthGoals": ["增肌"],
+            "allergies": ["花生"]
+        }))
+        .expect("profile");
+        save_health_profile(&source, "alice", profile.clone()).expect("save profile");
+
+        let recipe_id = insert_recipe(&source, &draft("番茄炒蛋", None)).expect("insert");
+        let recipes = vec![require_recipe(&source, recipe_id).expect("load")];
+        let mut buffer = Cursor::new(Vec::new());
+        write_bundle(
+            &mut buffer,
+            &source,
+            &dir,
+            &recipes,
+            &HashMap::new(),
+            load_health_profile(&source, "alice").expect("load profile"),
+        )
+        .expect("export");
+        let bytes = buffer.into_inner();
+
+        let mut target = database();
+        let skipped =
+            read_bundle(Cursor::new(bytes.clone()), &mut target, &dir, None, None).expect("import");
+        assert!(!skipped.health_profile_restored);
+        assert_eq!(load_health_profile(&target, "bob").expect("load"), None);
+
+        let restored = read_bundle(Cursor::new(bytes), &mut target, &dir, None, Some("bob"))
+            .expect("import with profile");
+        assert!(restored.health_profile_restored);
+        assert_eq!(restored.skipped_duplicates, 1);
+        assert_eq!(
+            load_health_profile(&target, "bob").expect("load"),
+            Some(profile)
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn applies_favorites_and_notes_of_duplicates_to_the_existing_recipe() {
+        let dir = scratch_dir("bundle-duplicate-notes");
+        let source = database();
+        let recipe_id = insert_recipe(&source, &draft("番茄炒蛋", None)).expect("insert");
+        let recipes = vec![require_recipe(&source, recipe_id).expect("load")];
+        let notes = HashMap::from([(recipe_id, "少放糖".to_string())]);
+        let mut buffer = Cursor::new(Vec::new());
+        write_bundle(&mut buffer, &source, &dir, &recipes, &notes, None).expect("export");
+        let bytes = buffer.into_inner();
+
+        let mut target = database();
+        let existing = insert_recipe(&target, &draft("番茄炒蛋", None)).expect("insert");
+        let imported = read_bundle(
+            Cursor::new(bytes.clone()),
+            &mut target,
+            &dir,
+            Some("bob"),
+            None,
+        )
+        .expect("import");
+        assert!(imported.imported_recipe_ids.is_empty());
+        assert_eq!(imported.skipped_duplicates, 1);
+        assert_eq!(imported.existing_recipe_ids, vec![existing]);
+        let note: String = target
+            .query_row(
+                "SELECT notes FROM favorites WHERE session_id = 'bob' AND recipe_id = ?1",
+                [existing],
+                |row| row.get(0),
+            )
+            .expect("favorite on the existing recipe");
+        assert_eq!(note, "少放糖");
+
+        target
+            .execute(
+                "UPDATE favorites SET notes = '多放葱' WHERE session_id = 'bob'",
+                [],
+            )
+            .expect("edit note");
+        read_bundle(Cursor::new(bytes), &mut target, &dir, Some("bob"), None).expect("reimport");
+        let note: String = target
+            .query_row(
+                "SELECT notes FROM favorites WHERE session_id = 'bob'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("single favorite");
+        assert_eq!(note, "多放葱");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,464 @@
+use crate::health::validate_session_id;
+use crate::images::{remove_if_orphaned, store_upload, GC_GRACE_PERIOD};
+use crate::profiles::{ensure_owned, ProfileState};
+use crate::recipe::require_recipe;
+use crate::{validate_date, DatabaseState};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::State;
+
+const MAX_TEXT_CHARS: usize = 2_000;
+const MAX_RESULTS: usize = 200;
+const COOK_LOG_COLUMNS: &str = "l.id, l.recipe_id, r.title, l.session_id, l.cooked_on, l.rating, l.notes, l.modifications, l.photo_url, l.created_at";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CookLogInput {
+    pub recipe_id: i64,
+    /// Defaults to today in local time.
+    pub cooked_on: Option<String>,
+    pub rating: Option<i64>,
+    pub notes: Option<String>,
+    pub modifications: Option<String>,
+    pub photo: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CookLogEntry {
+    pub id: i64,
+    pub recipe_id: i64,
+    pub recipe_title: String,
+    pub session_id: String,
+    pub cooked_on: String,
+    pub rating: Option<i64>,
+    pub notes: Option<String>,
+    pub modifications: Option<String>,
+    pub photo_url: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LastCooked {
+    pub recipe_id: i64,
+    pub last_cooked_on: String,
+    pub times_cooked: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeRating {
+    pub average_rating: f64,
+    pub rating_count: i64,
+}
+
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<CookLogEntry> {
+    Ok(CookLogEntry {
+        id: row.get(0)?,
+        recipe_id: row.get(1)?,
+        recipe_title: row.get(2)?,
+        session_id: row.get(3)?,
+        cooked_on: row.get(4)?,
+        rating: row.get(5)?,
+        notes: row.get(6)?,
+        modifications: row.get(7)?,
+        photo_url: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+fn trimmed(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn validate_entry(conn: &Connection, entry: &CookLogInput) -> Result<(), String> {
+    if let Some(rating) = entry.rating {
+        if !(1..=5).contains(&rating) {
+            return Err("Rating must be between 1 and 5".to_string());
+        }
+    }
+    for text in [&entry.notes, &entry.modifications].into_iter().flatten() {
+        if text.chars().count() > MAX_TEXT_CHARS {
+            return Err("Notes must be at most 2000 characters".to_string());
+        }
+    }
+    if let Some(cooked_on) = &entry.cooked_on {
+        validate_date(conn, cooked_on)?;
+        let in_future: bool = conn
+            .query_row("SELECT ?1 > date('now', 'localtime')", [cooked_on], |row| {
+                row.get(0)
+            })
+            .map_err(|e| format!("Failed to validate date: {}", e))?;
+        if in_future {
+            return Err("Cook date must not be in the future".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Recomputes `recipes.average_rating` and `rating_count` from the rated cook log
+/// entries of every session.
+pub(crate) fn refresh_rating(conn: &Connection, recipe_id: i64) -> Result<RecipeRating, String> {
+    conn.query_row(
+        "UPDATE recipes SET
+             rating_count = (SELECT COUNT(rating) FROM cook_log WHERE recipe_id = ?1),
+             average_rating = COALESCE((SELECT AVG(rating) FROM cook_log WHERE recipe_id = ?1), 0)
+         WHERE id = ?1
+         RETURNING average_rating, rating_count",
+        [recipe_id],
+        |row| {
+            Ok(RecipeRating {
+                average_rating: row.get(0)?,
+                rating_count: row.get(1)?,
+            })
+        },
+    )
+    .map_err(|e| format!("Failed to update recipe rating: {}", e))
+}
+
+fn require_entry(conn: &Connection, entry_id: i64) -> Result<CookLogEntry, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {COOK_LOG_COLUMNS} FROM cook_log l JOIN recipes r ON r.id = l.recipe_id
+             WHERE l.id = ?1"
+        ),
+        [entry_id],
+        entry_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load cook log entry: {}", e))?
+    .ok_or_else(|| "Cook log entry not found".to_string())
+}
+
+pub(crate) fn add_entry(
+    conn: &mut Connection,
+    images_dir: &Path,
+    session_id: &str,
+    entry: &CookLogInput,
+) -> Result<CookLogEntry, String> {
+    validate_session_id(session_id)?;
+    validate_entry(conn, entry)?;
+    require_recipe(conn, entry.recipe_id)?;
+    // The photo is written first; if the insert then fails, image GC reclaims it.
+    let photo_url = entry
+        .photo
+        .as_deref()
+        .map(|bytes| store_upload(images_dir, bytes).map(|stored| stored.url))
+        .transpose()?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute(
+        "INSERT INTO cook_log (recipe_id, session_id, cooked_on, rating, notes, modifications, photo_url)
+         VALUES (?1, ?2, COALESCE(?3, date('now', 'localtime')), ?4, ?5, ?6, ?7)",
+        params![
+            entry.recipe_id,
+            session_id,
+            entry.cooked_on,
+            entry.rating,
+            trimmed(&entry.notes),
+            trimmed(&entry.modifications),
+            photo_url,
+        ],
+    )
+    .map_err(|e| format!("Failed to add cook log entry: {}", e))?;
+    let entry_id = tx.last_insert_rowid();
+    refresh_rating(&tx, entry.recipe_id)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to add cook log entry: {}", e))?;
+    require_entry(conn, entry_id)
+}
+
+pub(crate) fn remove_entry(
+    conn: &mut Connection,
+    images_dir: &Path,
+    entry_id: i64,
+) -> Result<RecipeRating, String> {
+    let entry = require_entry(conn, entry_id)?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute("DELETE FROM cook_log WHERE id = ?1", [entry_id])
+        .map_err(|e| format!("Failed to remove cook log entry: {}", e))?;
+    let rating = refresh_rating(&tx, entry.recipe_id)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to remove cook log entry: {}", e))?;
+    remove_if_orphaned(conn, images_dir, entry.photo_url, GC_GRACE_PERIOD)?;
+    Ok(rating)
+}
+
+/// Lists a session's entries, newest cook first, optionally for one recipe only.
+pub(crate) fn list_entries(
+    conn: &Connection,
+    session_id: &str,
+    recipe_id: Option<i64>,
+    limit: usize,
+) -> Result<Vec<CookLogEntry>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {COOK_LOG_COLUMNS} FROM cook_log l JOIN recipes r ON r.id = l.recipe_id
+             WHERE l.session_id = ?1 AND (?2 IS NULL OR l.recipe_id = ?2)
+             ORDER BY l.cooked_on DESC, l.id DESC
+             LIMIT ?3"
+        ))
+        .map_err(|e| format!("Failed to load cook log: {}", e))?;
+    stmt.query_map(params![session_id, recipe_id, limit as i64], entry_from_row)
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load cook log: {}", e))
+}
+
+/// When each recipe was last cooked in this session; an empty `recipe_ids` covers
+/// every recipe the session has cooked, most recent first.
+pub(crate) fn last_cooked(
+    conn: &Connection,
+    session_id: &str,
+    recipe_ids: &[i64],
+) -> Result<Vec<LastCooked>, String> {
+    let filter = (!recipe_ids.is_empty())
+        .then(|| serde_json::to_string(recipe_ids))
+        .transpose()
+        .map_err(|e| format!("Failed to load cook history: {}", e))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT recipe_id, MAX(cooked_on) AS last_cooked_on, COUNT(*) FROM cook_log
+             WHERE session_id = ?1
+               AND (?2 IS NULL OR recipe_id IN (SELECT value FROM json_each(?2)))
+             GROUP BY recipe_id
+             ORDER BY last_cooked_on DESC, recipe_id",
+        )
+        .map_err(|e| format!("Failed to load cook history: {}", e))?;
+    stmt.query_map(params![session_id, filter], |row| {
+        Ok(LastCooked {
+            recipe_id: row.get(0)?,
+            last_cooked_on: row.get(1)?,
+            times_cooked: row.get(2)?,
+        })
+    })
+    .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+    .map_err(|e| format!("Failed to load cook history: {}", e))
+}
+
+#[tauri::command]
+pub(crate) fn cook_log_add(
+    entry: CookLogInput,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<CookLogEntry, String> {
+    let mut conn = db.open_connection()?;
+    add_entry(&mut conn, &db.images_dir(), &profiles.session_id()?, &entry)
+}
+
+#[tauri::command]
+pub(crate) fn cook_log_list(
+    recipe_id: Option<i64>,
+    limit: Option<usize>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<CookLogEntry>, String> {
+    let conn = db.open_connection()?;
+    list_entries(
+        &conn,
+        &profiles.session_id()?,
+        recipe_id,
+        limit.unwrap_or(50).clamp(1, MAX_RESULTS),
+    )
+}
+
+#[tauri::command]
+pub(crate) fn cook_log_remove(
+    entry_id: i64,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<RecipeRating, String> {
+    let mut conn = db.open_connection()?;
+    ensure_owned(
+        &conn,
+        "SELECT session_id FROM cook_log WHERE id = ?1",
+        entry_id,
+        &profiles.session_id()?,
+        "Cook log entry not found",
+    )?;
+    remove_entry(&mut conn, &db.images_dir(), entry_id)
+}
+
+#[tauri::command]
+pub(crate) fn cook_log_last_cooked(
+    recipe_ids: Option<Vec<i64>>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<LastCooked>, String> {
+    let conn = db.open_connection()?;
+    last_cooked(
+        &conn,
+        &profiles.session_id()?,
+        recipe_ids.as_deref().unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+
+    fn insert_recipe(conn: &Connection, title: &str) -> i64 {
+        conn.execute(
+            "INSERT INTO recipes (title, ingredients, instructions) VALUES (?1, '[]', '[]')",
+            [title],
+        )
+        .unwrap();
+        conn.last_insert_rowid()
+    }
+
+    fn cooked(recipe_id: i64, cooked_on: &str, rating: Option<i64>) -> CookLogInput {
+        CookLogInput {
+            recipe_id,
+            cooked_on: Some(cooked_on.to_string()),
+            rating,
+            notes: Some("  少放了糖 ".to_string()),
+            modifications: None,
+            photo: None,
+        }
+    }
+
+    fn stored_rating(conn: &Connection, recipe_id: i64) -> (f64, i64) {
+        conn.query_row(
+            "SELECT average_rating, rating_count FROM recipes WHERE id = ?1",
+            [recipe_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn recomputes_ratings_from_the_log() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        let images_dir = std::env::temp_dir().join("chefmind-cook-log-unused");
+        let pork = insert_recipe(&conn, "红烧肉");
+
+        let first = add_entry(
+            &mut conn,
+            &images_dir,
+            "s1",
+            &cooked(pork, "2026-01-03", Some(4)),
+        )
+        .unwrap();
+        assert_eq!(first.notes.as_deref(), Some("少放了糖"));
+        assert_eq!(first.recipe_title, "红烧肉");
+        add_entry(
+            &mut conn,
+            &images_dir,
+            "s2",
+            &cooked(pork, "2026-02-10", Some(5)),
+        )
+        .unwrap();
+        add_entry(
+            &mut conn,
+            &images_dir,
+            "s1",
+            &cooked(pork, "2026-03-01", None),
+        )
+        .unwrap();
+        assert_eq!(stored_rating(&conn, pork), (4.5, 2));
+
+        let rating = remove_entry(&mut conn, &images_dir, first.id).unwrap();
+        assert_eq!(
+            rating,
+            RecipeRating {
+                average_rating: 5.0,
+                rating_count: 1
+            }
+        );
+        assert_eq!(stored_rating(&conn, pork), (5.0, 1));
+
+        for (entry, error) in [
+            (
+                cooked(pork, "2026-01-03", Some(6)),
+                "Rating must be between 1 and 5",
+            ),
+            (
+                cooked(pork, "3026-01-03", None),
+                "Cook date must not be in the future",
+            ),
+            (
+                cooked(pork, "2026/01/03", None),
+                "Date must use the YYYY-MM-DD format",
+            ),
+            (cooked(999, "2026-01-03", None), "Recipe not found"),
+        ] {
+            assert_eq!(
+                add_entry(&mut conn, &images_dir, "s1", &entry).unwrap_err(),
+                error
+            );
+        }
+        let revisions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM recipe_revisions", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(revisions, 1, "rating updates must not create revisions");
+    }
+
+    #[test]
+    fn lists_entries_and_last_cooked_dates_per_session() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        let images_dir = std::env::temp_dir().join("chefmind-cook-log-unused");
+        let soup = insert_recipe(&conn, "番茄蛋汤");
+        let noodles = insert_recipe(&conn, "炸酱面");
+        for (session, recipe, date) in [
+            ("s1", soup, "2026-01-05"),
+            ("s1", noodles, "2026-01-09"),
+            ("s1", soup, "2026-02-01"),
+            ("s2", noodles, "2026-03-01"),
+        ] {
+            add_entry(&mut conn, &images_dir, session, &cooked(recipe, date, None)).unwrap();
+        }
+        let today = add_entry(
+            &mut conn,
+            &images_dir,
+            "s2",
+            &CookLogInput {
+                cooked_on: None,
+                ..cooked(soup, "", None)
+            },
+        )
+        .unwrap();
+        let expected_today: String = conn
+            .query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(today.cooked_on, expected_today);
+
+        let dates: Vec<_> = list_entries(&conn, "s1", None, 10)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.cooked_on)
+            .collect();
+        assert_eq!(dates, ["2026-02-01", "2026-01-09", "2026-01-05"]);
+        assert_eq!(
+            list_entries(&conn, "s1", Some(noodles), 10).unwrap().len(),
+            1
+        );
+
+        assert_eq!(
+            last_cooked(&conn, "s1", &[]).unwrap(),
+            [
+                LastCooked {
+                    recipe_id: soup,
+                    last_cooked_on: "2026-02-01".to_string(),
+                    times_cooked: 2
+                },
+                LastCooked {
+                    recipe_id: noodles,
+                    last_cooked_on: "2026-01-09".to_string(),
+                    times_cooked: 1
+                },
+            ]
+        );
+        let only_noodles = last_cooked(&conn, "s2", &[noodles]).unwrap();
+        assert_eq!(only_noodles.len(), 1);
+        assert_eq!(only_noodles[0].last_cooked_on, "2026-03-01");
+    }
+}
@@ -0,0 +1,949 @@
+use crate::recipe::{load_recipe, require_recipe, StoredRecipe};
+use crate::{tray, DatabaseState};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+pub(crate) const MAX_TIMER_SECONDS: u64 = 24 * 60 * 60;
+const MAX_ACTIVE_TIMERS: usize = 16;
+pub(crate) const TIMER_TICK_EVENT: &str = "timer-tick";
+pub(crate) const TIMER_FINISHED_EVENT: &str = "timer-finished";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CookingStatus {
+    Active,
+    Paused,
+    Completed,
+    Abandoned,
+}
+
+impl CookingStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Paused => "paused",
+            Self::Completed => "completed",
+            Self::Abandoned => "abandoned",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(Self::Active),
+            "paused" => Some(Self::Paused),
+            "completed" => Some(Self::Completed),
+            "abandoned" => Some(Self::Abandoned),
+            _ => None,
+        }
+    }
+}
+
+/// `Held` marks a timer stopped by pausing the whole session, so resuming the
+/// session restarts exactly those timers and leaves user-paused ones alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerStatus {
+    Running,
+    Paused,
+    Held,
+    Finished,
+    Dismissed,
+}
+
+impl TimerStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Paused => "paused",
+            Self::Held => "held",
+            Self::Finished => "finished",
+            Self::Dismissed => "dismissed",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "running" => Some(Self::Running),
+            "paused" => Some(Self::Paused),
+            "held" => Some(Self::Held),
+            "finished" => Some(Self::Finished),
+            "dismissed" => Some(Self::Dismissed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CookingTimerSnapshot {
+    pub id: i64,
+    pub step_index: Option<usize>,
+    pub label: String,
+    pub duration_ms: u64,
+    pub remaining_ms: u64,
+    pub status: TimerStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CookingSessionSnapshot {
+    pub id: i64,
+    pub recipe_id: i64,
+    pub recipe_title: String,
+    pub step_index: usize,
+    pub step_count: usize,
+    pub current_step: Option<String>,
+    pub status: CookingStatus,
+    pub timers: Vec<CookingTimerSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimerTick {
+    pub session_id: i64,
+    pub timers: Vec<CookingTimerSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimerFinished {
+    pub session_id: i64,
+    pub recipe_id: i64,
+    pub recipe_title: String,
+    pub timer: CookingTimerSnapshot,
+}
+
+/// A point in time on both clocks: the monotonic one drives countdowns, the wall
+/// clock is only used to carry deadlines across a restart.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Now {
+    pub instant: Instant,
+    pub unix_ms: i64,
+}
+
+impl Now {
+    pub(crate) fn current() -> Self {
+        Self {
+            instant: Instant::now(),
+            unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as i64),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CookingTimer {
+    pub id: i64,
+    pub step_index: Option<usize>,
+    pub label: String,
+    pub duration: Duration,
+    pub status: TimerStatus,
+    /// Time left while the timer is not running.
+    remaining: Duration,
+    /// Monotonic deadline while the timer is running.
+    deadline: Option<Instant>,
+}
+
+impl CookingTimer {
+    pub(crate) fn remaining_at(&self, now: Instant) -> Duration {
+        match self.deadline {
+            Some(deadline) if self.status == TimerStatus::Running => {
+                deadline.saturating_duration_since(now)
+            }
+            _ => self.remaining,
+        }
+    }
+
+    pub(crate) fn snapshot(&self, now: Instant) -> CookingTimerSnapshot {
+        CookingTimerSnapshot {
+            id: self.id,
+            step_index: self.step_index,
+            label: self.label.clone(),
+            duration_ms: self.duration.as_millis() as u64,
+            remaining_ms: self.remaining_at(now).as_millis() as u64,
+            status: self.status,
+        }
+    }
+
+    fn stop(&mut self, now: Instant, status: TimerStatus) {
+        self.remaining = self.remaining_at(now);
+        self.deadline = None;
+        self.status = status;
+    }
+
+    fn run(&mut self, now: Instant, remaining: Duration) {
+        self.remaining = remaining;
+        self.deadline = Some(now + remaining);
+        self.status = TimerStatus::Running;
+    }
+
+    fn is_live(&self) -> bool {
+        matches!(
+            self.status,
+            TimerStatus::Running | TimerStatus::Paused | TimerStatus::Held
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CookingSession {
+    pub id: i64,
+    pub recipe_id: i64,
+    pub recipe_title: String,
+    pub steps: Vec<String>,
+    pub step_index: usize,
+    pub status: CookingStatus,
+    pub timers: Vec<CookingTimer>,
+}
+
+impl CookingSession {
+    pub(crate) fn snapshot(&self, now: Instant) -> CookingSessionSnapshot {
+        CookingSessionSnapshot {
+            id: self.id,
+            recipe_id: self.recipe_id,
+            recipe_title: self.recipe_title.clone(),
+            step_index: self.step_index,
+            step_count: self.steps.len(),
+            current_step: self.steps.get(self.step_index).cloned(),
+            status: self.status,
+            timers: self
+                .timers
+                .iter()
+                .filter(|timer| timer.status != TimerStatus::Dismissed)
+                .map(|timer| timer.snapshot(now))
+                .collect(),
+        }
+    }
+
+    fn require_active(&self) -> Result<(), String> {
+        match self.status {
+            CookingStatus::Active => Ok(()),
+            CookingStatus::Paused => Err("Cooking session is paused".to_string()),
+            _ => Err("Cooking session has ended".to_string()),
+        }
+    }
+
+    pub(crate) fn timer_mut(&mut self, timer_id: i64) -> Result<&mut CookingTimer, String> {
+        self.timers
+            .iter_mut()
+            .find(|timer| timer.id == timer_id && timer.status != TimerStatus::Dismissed)
+            .ok_or_else(|| "Timer not found".to_string())
+    }
+
+    pub(crate) fn go_to_step(&mut self, step_index: usize) -> Result<(), String> {
+        self.require_active()?;
+        if step_index >= self.steps.len().max(1) {
+            return Err("Step index is out of range".to_string());
+        }
+        self.step_index = step_index;
+        Ok(())
+    }
+
+    pub(crate) fn pause(&mut self, now: Instant) -> Result<(), String> {
+        self.require_active()?;
+        for timer in &mut self.timers {
+            if timer.status == TimerStatus::Running {
+                timer.stop(now, TimerStatus::Held);
+            }
+        }
+        self.status = CookingStatus::Paused;
+        Ok(())
+    }
+
+    pub(crate) fn resume(&mut self, now: Instant) -> Result<(), String> {
+        if self.status != CookingStatus::Paused {
+            return Err("Cooking session is not paused".to_string());
+        }
+        for timer in &mut self.timers {
+            if timer.status == TimerStatus::Held {
+                let remaining = timer.remaining;
+                timer.run(now, remaining);
+            }
+        }
+        self.status = CookingStatus::Active;
+        Ok(())
+    }
+
+    /// Ends the session as completed or abandoned and dismisses its timers.
+    pub(crate) fn end(&mut self, now: Instant, status: CookingStatus) {
+        for timer in &mut self.timers {
+            if timer.status != TimerStatus::Dismissed {
+                timer.stop(now, TimerStatus::Dismissed);
+            }
+        }
+        self.status = status;
+    }
+
+    pub(crate) fn pause_timer(&mut self, timer_id: i64, now: Instant) -> Result<(), String> {
+        self.require_active()?;
+        let timer = self.timer_mut(timer_id)?;
+        if timer.status != TimerStatus::Running {
+            return Err("Timer is not running".to_string());
+        }
+        timer.stop(now, TimerStatus::Paused);
+        Ok(())
+    }
+
+    pub(crate) fn resume_timer(&mut self, timer_id: i64, now: Instant) -> Result<(), String> {
+        self.require_active()?;
+        let timer = self.timer_mut(timer_id)?;
+        if timer.status != TimerStatus::Paused {
+            return Err("Timer is not paused".to_string());
+        }
+        let remaining = timer.remaining;
+        timer.run(now, remaining);
+        Ok(())
+    }
+
+    pub(crate) fn dismiss_timer(&mut self, timer_id: i64, now: Instant) -> Result<(), String> {
+        self.timer_mut(timer_id)?.stop(now, TimerStatus::Dismissed);
+        Ok(())
+    }
+
+    /// Restarts a finished timer for `delay`, e.g. "five more minutes".
+    pub(crate) fn snooze_timer(
+        &mut self,
+        timer_id: i64,
+        delay: Duration,
+        now: Instant,
+    ) -> Result<(), String> {
+        self.require_active()?;
+        if delay.is_zero() || delay.as_secs() > MAX_TIMER_SECONDS {
+            return Err("Snooze must be between 1 second and 24 hours".to_string());
+        }
+        let timer = self.timer_mut(timer_id)?;
+        if timer.status != TimerStatus::Finished {
+            return Err("Only finished timers can be snoozed".to_string());
+        }
+        timer.run(now, delay);
+        Ok(())
+    }
+
+    /// Moves to the step a timer belongs to; timers without a step leave it unchanged.
+    pub(crate) fn focus_timer_step(&mut self, timer_id: i64) -> Result<usize, String> {
+        if let Some(step_index) = self.timer_mut(timer_id)?.step_index {
+            self.go_to_step(step_index)?;
+        }
+        Ok(self.step_index)
+    }
+
+    /// Marks running timers that reached zero as finished and reports the rest as
+    /// a tick. Nothing is emitted for a session without running timers.
+    pub(crate) fn tick(&mut self, now: Instant) -> (Option<TimerTick>, Vec<TimerFinished>) {
+        let mut finished = Vec::new();
+        for timer in &mut self.timers {
+            if timer.status == TimerStatus::Running && timer.remaining_at(now).is_zero() {
+                timer.stop(now, TimerStatus::Finished);
+                finished.push(TimerFinished {
+                    session_id: self.id,
+                    recipe_id: self.recipe_id,
+                    recipe_title: self.recipe_title.clone(),
+                    timer: timer.snapshot(now),
+                });
+            }
+        }
+        let running: Vec<CookingTimerSnapshot> = self
+            .timers
+            .iter()
+            .filter(|timer| timer.status == TimerStatus::Running)
+            .map(|timer| timer.snapshot(now))
+            .collect();
+        let tick = (!running.is_empty()).then_some(TimerTick {
+            session_id: self.id,
+            timers: running,
+        });
+        (tick, finished)
+    }
+}
+
+/// The one cooking session that may be in progress, shared by commands and the ticker.
+#[derive(Default)]
+pub struct CookingState {
+    session: Mutex<Option<CookingSession>>,
+}
+
+impl CookingState {
+    pub(crate) fn lock(&self) -> std::sync::MutexGuard<'_, Option<CookingSession>> {
+        self.session
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+pub(crate) fn start_session(
+    conn: &Connection,
+    recipe: &StoredRecipe,
+) -> Result<CookingSession, String> {
+    let steps = recipe.step_list();
+    conn.execute(
+        "INSERT INTO cooking_sessions (recipe_id, step_index, status) VALUES (?1, 0, 'active')",
+        [recipe.id],
+    )
+    .map_err(|e| format!("Failed to start cooking session: {}", e))?;
+    Ok(CookingSession {
+        id: conn.last_insert_rowid(),
+        recipe_id: recipe.id,
+        recipe_title: recipe.title.clone(),
+        steps,
+        step_index: 0,
+        status: CookingStatus::Active,
+        timers: Vec::new(),
+    })
+}
+
+pub(crate) fn add_timer(
+    conn: &Connection,
+    session: &mut CookingSession,
+    label: &str,
+    duration_seconds: u64,
+    step_index: Option<usize>,
+    now: Now,
+) -> Result<i64, String> {
+    session.require_active()?;
+    let label = label.trim();
+    if label.is_empty() || label.chars().count() > 100 {
+        return Err("Timer label is invalid".to_string());
+    }
+    if duration_seconds == 0 || duration_seconds > MAX_TIMER_SECONDS {
+        return Err("Timer duration must be between 1 second and 24 hours".to_string());
+    }
+    if step_index.is_some_and(|index| index >= session.steps.len()) {
+        return Err("Step index is out of range".to_string());
+    }
+    if session
+        .timers
+        .iter()
+        .filter(|timer| timer.is_live())
+        .count()
+        >= MAX_ACTIVE_TIMERS
+    {
+        return Err("Too many timers are running".to_string());
+    }
+    let duration = Duration::from_secs(duration_seconds);
+    conn.execute(
+        "INSERT INTO cooking_timers (cooking_session_id, step_index, label, duration_ms, remaining_ms, deadline_unix_ms, status)
+         VALUES (?1, ?2, ?3, ?4, ?4, ?5, 'running')",
+        params![
+            session.id,
+            step_index.map(|index| index as i64),
+            label,
+            duration.as_millis() as i64,
+            now.unix_ms + duration.as_millis() as i64,
+        ],
+    )
+    .map_err(|e| format!("Failed to start timer: {}", e))?;
+    let mut timer = CookingTimer {
+        id: conn.last_insert_rowid(),
+        step_index,
+        label: label.to_string(),
+        duration,
+        status: TimerStatus::Running,
+        remaining: duration,
+        deadline: None,
+    };
+    timer.run(now.instant, duration);
+    session.timers.push(timer);
+    Ok(session.timers[session.timers.len() - 1].id)
+}
+
+/// Writes the session and every timer; running timers are stored as a wall-clock
+/// deadline so they keep counting down while the app is closed.
+pub(crate) fn save_session(
+    conn: &mut Connection,
+    session: &CookingSession,
+    now: Now,
+) -> Result<(), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    write_session(&tx, session, now)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to save cooking session: {}", e))
+}
+
+fn write_session(conn: &Connection, session: &CookingSession, now: Now) -> Result<(), String> {
+    conn.execute(
+        "UPDATE cooking_sessions
+         SET step_index = ?1, status = ?2, updated_at = CURRENT_TIMESTAMP,
+             finished_at = CASE WHEN ?2 IN ('completed', 'abandoned') THEN CURRENT_TIMESTAMP END
+         WHERE id = ?3",
+        params![
+            session.step_index as i64,
+            session.status.as_str(),
+            session.id
+        ],
+    )
+    .map_err(|e| format!("Failed to save cooking session: {}", e))?;
+    for timer in &session.timers {
+        let remaining = timer.remaining_at(now.instant).as_millis() as i64;
+        let deadline = (timer.status == TimerStatus::Running).then_some(now.unix_ms + remaining);
+        conn.execute(
+            "UPDATE cooking_timers
+             SET remaining_ms = ?1, deadline_unix_ms = ?2, status = ?3, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?4",
+            params![remaining, deadline, timer.status.as_str(), timer.id],
+        )
+        .map_err(|e| format!("Failed to save cooking session: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Restores the newest unfinished session. Running timers are re-anchored on the
+/// monotonic clock from their stored deadline; ones that expired while the app was
+/// closed come back at zero and finish on the next tick.
+pub(crate) fn load_active_session(
+    conn: &Connection,
+    now: Now,
+) -> Result<Option<CookingSession>, String> {
+    let row: Option<(i64, i64, i64, String)> = conn
+        .query_row(
+            "SELECT id, recipe_id, step_index, status FROM cooking_sessions
+             WHERE status IN ('active', 'paused')
+             ORDER BY id DESC LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load cooking session: {}", e))?;
+    let Some((id, recipe_id, step_index, status)) = row else {
+        return Ok(None);
+    };
+    let Some(recipe) =
+        load_recipe(conn, recipe_id).map_err(|e| format!("Failed to load recipe: {}", e))?
+    else {
+        return Ok(None);
+    };
+    let steps = recipe.step_list();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, step_index, label, duration_ms, remaining_ms, deadline_unix_ms, status
+             FROM cooking_timers
+             WHERE cooking_session_id = ?1 AND status != 'dismissed'
+             ORDER BY id",
+        )
+        .map_err(|e| format!("Failed to load cooking timers: {}", e))?;
+    let timers = stmt
+        .query_map([id], |row| {
+            let status =
+                TimerStatus::parse(&row.get::<_, String>(6)?).unwrap_or(TimerStatus::Paused);
+            let deadline_unix_ms: Option<i64> = row.get(5)?;
+            let stored_remaining = row.get::<_, i64>(4)?.max(0) as u64;
+            let remaining = match (status, deadline_unix_ms) {
+                (TimerStatus::Running, Some(deadline)) => (deadline - now.unix_ms).max(0) as u64,
+                _ => stored_remaining,
+            };
+            let mut timer = CookingTimer {
+                id: row.get(0)?,
+                step_index: row
+                    .get::<_, Option<i64>>(1)?
+                    .map(|index| index.max(0) as usize),
+                label: row.get(2)?,
+                duration: Duration::from_millis(row.get::<_, i64>(3)?.max(0) as u64),
+                status,
+                remaining: Duration::from_millis(remaining),
+                deadline: None,
+            };
+            if status == TimerStatus::Running {
+                timer.run(now.instant, Duration::from_millis(remaining));
+            }
+            Ok(timer)
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load cooking timers: {}", e))?;
+
+    Ok(Some(CookingSession {
+        id,
+        recipe_id,
+        recipe_title: recipe.title,
+        step_index: (step_index.max(0) as usize).min(steps.len().saturating_sub(1)),
+        steps,
+        status: CookingStatus::parse(&status).unwrap_or(CookingStatus::Paused),
+        timers,
+    }))
+}
+
+/// Loads the persisted session into `CookingState` and starts the once-a-second
+/// ticker thread that emits `timer-tick` and `timer-finished`.
+pub(crate) fn start(app: &AppHandle) {
+    let restored = app
+        .state::<DatabaseState>()
+        .open_connection()
+        .and_then(|conn| load_active_session(&conn, Now::current()));
+    match restored {
+        Ok(session) => *app.state::<CookingState>().lock() = session,
+        Err(e) => eprintln!("Failed to restore cooking session: {}", e),
+    }
+
+    let handle = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK_INTERVAL);
+        let now = Now::current();
+        let (tick, finished, snapshot) = {
+            let cooking = handle.state::<CookingState>();
+            let mut guard = cooking.lock();
+            match guard.as_mut() {
+                Some(session) => {
+                    let (tick, finished) = session.tick(now.instant);
+                    if !finished.is_empty() {
+                        let saved = handle
+                            .state::<DatabaseState>()
+                            .open_connection()
+                            .and_then(|mut conn| save_session(&mut conn, session, now));
+                        if let Err(e) = saved {
+                            eprintln!("Failed to save finished timers: {}", e);
+                        }
+                    }
+                    (tick, finished, Some(session.snapshot(now.instant)))
+                }
+                None => (None, Vec::new(), None),
+            }
+        };
+        if let Some(tick) = tick {
+            if let Err(e) = handle.emit(TIMER_TICK_EVENT, tick) {
+                eprintln!("Failed to emit timer tick: {}", e);
+            }
+        }
+        for event in finished {
+            tray::notify_finished(&handle, &event);
+            if let Err(e) = handle.emit(TIMER_FINISHED_EVENT, event) {
+                eprintln!("Failed to emit timer finished: {}", e);
+            }
+        }
+        tray::refresh(&handle, snapshot.as_ref());
+    });
+}
+
+/// Applies `change` to the in-progress session, persists it and returns the new state.
+pub(crate) fn update_session(
+    cooking: &CookingState,
+    db: &DatabaseState,
+    change: impl FnOnce(&Connection, &mut CookingSession, Now) -> Result<(), String>,
+) -> Result<CookingSessionSnapshot, String> {
+    let mut guard = cooking.lock();
+    let session = guard
+        .as_mut()
+        .ok_or_else(|| "No cooking session is in progress".to_string())?;
+    let mut conn = db.open_connection()?;
+    let now = Now::current();
+    let mut updated = session.clone();
+    // Rows the change wrote before failing are rolled back with the in-memory copy.
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    change(&tx, &mut updated, now)?;
+    write_session(&tx, &updated, now)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to save cooking session: {}", e))?;
+    let snapshot = updated.snapshot(now.instant);
+    *guard = matches!(
+        updated.status,
+        CookingStatus::Active | CookingStatus::Paused
+    )
+    .then_some(updated);
+    Ok(snapshot)
+}
+
+/// Starts cooking a recipe; a session already in progress is abandoned.
+#[tauri::command]
+pub(crate) fn cooking_start(
+    recipe_id: i64,
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    let mut guard = cooking.lock();
+    let mut conn = db.open_connection()?;
+    let recipe = require_recipe(&conn, recipe_id)?;
+    let now = Now::current();
+    if let Some(previous) = guard.as_mut() {
+        previous.end(now.instant, CookingStatus::Abandoned);
+        save_session(&mut conn, previous, now)?;
+    }
+    *guard = None;
+    let session = start_session(&conn, &recipe)?;
+    let snapshot = session.snapshot(now.instant);
+    *guard = Some(session);
+    Ok(snapshot)
+}
+
+#[tauri::command]
+pub(crate) fn cooking_current(
+    cooking: State<CookingState>,
+) -> Result<Option<CookingSessionSnapshot>, String> {
+    let now = Instant::now();
+    Ok(cooking.lock().as_ref().map(|session| session.snapshot(now)))
+}
+
+#[tauri::command]
+pub(crate) fn cooking_go_to_step(
+    step_index: usize,
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, _| {
+        session.go_to_step(step_index)
+    })
+}
+
+#[tauri::command]
+pub(crate) fn cooking_next_step(
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, _| {
+        session.go_to_step(session.step_index + 1)
+    })
+}
+
+#[tauri::command]
+pub(crate) fn cooking_previous_step(
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, _| {
+        let previous = session
+            .step_index
+            .checked_sub(1)
+            .ok_or_else(|| "Already at the first step".to_string())?;
+        session.go_to_step(previous)
+    })
+}
+
+#[tauri::command]
+pub(crate) fn cooking_pause(
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, now| session.pause(now.instant))
+}
+
+#[tauri::command]
+pub(crate) fn cooking_resume(
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, now| session.resume(now.instant))
+}
+
+#[tauri::command]
+pub(crate) fn cooking_finish(
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, now| {
+        session.end(now.instant, CookingStatus::Completed);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub(crate) fn cooking_abandon(
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, now| {
+        session.end(now.instant, CookingStatus::Abandoned);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub(crate) fn timer_start(
+    label: String,
+    duration_seconds: u64,
+    step_index: Option<usize>,
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |conn, session, now| {
+        add_timer(conn, session, &label, duration_seconds, step_index, now).map(|_| ())
+    })
+}
+
+#[tauri::command]
+pub(crate) fn timer_pause(
+    timer_id: i64,
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, now| {
+        session.pause_timer(timer_id, now.instant)
+    })
+}
+
+#[tauri::command]
+pub(crate) fn timer_resume(
+    timer_id: i64,
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, now| {
+        session.resume_timer(timer_id, now.instant)
+    })
+}
+
+#[tauri::command]
+pub(crate) fn timer_snooze(
+    timer_id: i64,
+    minutes: Option<u64>,
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    let delay = minutes
+        .unwrap_or(tray::SNOOZE_MINUTES)
+        .checked_mul(60)
+        .map(Duration::from_secs)
+        .ok_or_else(|| "Snooze must be between 1 second and 24 hours".to_string())?;
+    update_session(&cooking, &db, |_, session, now| {
+        session.snooze_timer(timer_id, delay, now.instant)
+    })
+}
+
+#[tauri::command]
+pub(crate) fn timer_dismiss(
+    timer_id: i64,
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    update_session(&cooking, &db, |_, session, now| {
+        session.dismiss_timer(timer_id, now.instant)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+
+    fn cooking_recipe(conn: &Connection) -> StoredRecipe {
+        conn.execute(
+            "INSERT INTO recipes (title, ingredients, instructions)
+             VALUES ('红烧肉', '[]', '[\"焯水\", \"炒糖色\", \"小火焖煮40分钟\"]')",
+            [],
+        )
+        .unwrap();
+        require_recipe(conn, conn.last_insert_rowid()).unwrap()
+    }
+
+    fn after(now: Now, seconds: u64) -> Now {
+        Now {
+            instant: now.instant + Duration::from_secs(seconds),
+            unix_ms: now.unix_ms + seconds as i64 * 1_000,
+        }
+    }
+
+    #[test]
+    fn runs_steps_and_timers_through_the_state_machine() {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        let recipe = cooking_recipe(&conn);
+        let start = Now::current();
+        let mut session = start_session(&conn, &recipe).unwrap();
+        assert_eq!(session.steps.len(), 3);
+
+        session.go_to_step(2).unwrap();
+        assert!(session.go_to_step(3).is_err());
+        let stew = add_timer(&conn, &mut session, "焖煮", 40 * 60, Some(2), start).unwrap();
+        let rest = add_timer(&conn, &mut session, "醒肉", 60, None, start).unwrap();
+        assert!(add_timer(
+            &conn,
+            &mut session,
+            "太长",
+            MAX_TIMER_SECONDS + 1,
+            None,
+            start
+        )
+        .is_err());
+
+        session.pause_timer(rest, after(start, 20).instant).unwrap();
+        session.pause(after(start, 30).instant).unwrap();
+        assert_eq!(
+            session.go_to_step(0).unwrap_err(),
+            "Cooking session is paused"
+        );
+        session.resume(after(start, 600).instant).unwrap();
+
+        let later = after(start, 600 + 40 * 60 - 30);
+        let (tick, finished) = session.tick(later.instant);
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].timer.id, stew);
+        assert!(tick.is_none(), "the paused timer must not tick");
+        let rest_timer = session.timer_mut(rest).unwrap();
+        assert_eq!(rest_timer.status, TimerStatus::Paused);
+        assert_eq!(
+            rest_timer.remaining_at(later.instant),
+            Duration::from_secs(40)
+        );
+
+        session.resume_timer(rest, later.instant).unwrap();
+        let (tick, finished) = session.tick(after(later, 10).instant);
+        assert!(finished.is_empty());
+        assert_eq!(tick.unwrap().timers[0].remaining_ms, 30_000);
+
+        assert_eq!(session.focus_timer_step(stew).unwrap(), 2);
+        assert!(session
+            .snooze_timer(rest, Duration::from_secs(300), later.instant)
+            .is_err());
+        session
+            .snooze_timer(stew, Duration::from_secs(300), later.instant)
+            .unwrap();
+        assert_eq!(
+            session
+                .timer_mut(stew)
+                .unwrap()
+                .remaining_at(after(later, 60).instant),
+            Duration::from_secs(240)
+        );
+
+        session.end(later.instant, CookingStatus::Completed);
+        assert!(session.snapshot(later.instant).timers.is_empty());
+        assert!(session.go_to_step(0).is_err());
+    }
+
+    #[test]
+    fn restores_running_timers_from_wall_clock_deadlines() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        let recipe = cooking_recipe(&conn);
+        let start = Now::current();
+        let mut session = start_session(&conn, &recipe).unwrap();
+        session.go_to_step(1).unwrap();
+        let short = add_timer(&conn, &mut session, "炒糖色", 90, Some(1), start).unwrap();
+        let long = add_timer(&conn, &mut session, "焖煮", 2_400, Some(2), start).unwrap();
+        let held = add_timer(&conn, &mut session, "腌制", 600, None, start).unwrap();
+        session
+            .pause_timer(held, after(start, 100).instant)
+            .unwrap();
+        save_session(&mut conn, &session, after(start, 100)).unwrap();
+
+        // The app was closed for five minutes.
+        let reopened = Now {
+            instant: Instant::now(),
+            unix_ms: start.unix_ms + 400_000,
+        };
+        let mut restored = load_active_session(&conn, reopened).unwrap().unwrap();
+        assert_eq!(restored.id, session.id);
+        assert_eq!(restored.step_index, 1);
+        let snapshot = restored.snapshot(reopened.instant);
+        let remaining: Vec<_> = snapshot
+            .timers
+            .iter()
+            .map(|timer| (timer.id, timer.remaining_ms, timer.status))
+            .collect();
+        assert_eq!(
+            remaining,
+            [
+                (short, 0, TimerStatus::Running),
+                (long, 2_000_000, TimerStatus::Running),
+                (held, 500_000, TimerStatus::Paused),
+            ]
+        );
+        let (_, finished) = restored.tick(reopened.instant);
+        assert_eq!(finished.len(), 1);
+
+        restored.end(reopened.instant, CookingStatus::Abandoned);
+        save_session(&mut conn, &restored, reopened).unwrap();
+        assert!(load_active_session(&conn, reopened).unwrap().is_none());
+    }
+}
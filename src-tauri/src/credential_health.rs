@@ -0,0 +1,309 @@
+use crate::provider_chain::http_status;
+use crate::{read_credential, validate_provider_id, CREDENTIAL_SERVICE, PROVIDER_IDS};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+pub(crate) const CREDENTIAL_FAILING_EVENT: &str = "credential-failing";
+/// The first check waits for startup to settle; later ones run a few times a day.
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Keys older than this are flagged so the settings page can suggest rotating them.
+const ROTATION_AGE_SECS: u64 = 90 * 24 * 60 * 60;
+const FINGERPRINT_CHARS: usize = 4;
+/// Rejected, forbidden or out of quota: the only answers that say something about
+/// the key itself. Being offline, rate limits, timeouts and server errors do not.
+const KEY_FAILURE_STATUSES: [u16; 3] = [401, 402, 403];
+
+/// Non-secret bookkeeping kept in its own keyring entry beside the credential, so
+/// reading it never touches the API key. Times are seconds since the Unix epoch.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CredentialMetadata {
+    pub created_at: u64,
+    pub last_success_at: Option<u64>,
+    pub last_failure_at: Option<u64>,
+    pub last_failure_reason: Option<String>,
+    pub fingerprint: String,
+}
+
+impl CredentialMetadata {
+    fn is_failing(&self) -> bool {
+        match (self.last_failure_at, self.last_success_at) {
+            (Some(failure), Some(success)) => failure >= success,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Records one request outcome and returns true when a working key starts failing.
+    fn record(&mut self, outcome: &Result<(), String>, now: u64) -> bool {
+        let was_failing = self.is_failing();
+        match outcome {
+            Ok(()) => self.last_success_at = Some(now),
+            Err(reason) => {
+                self.last_failure_at = Some(now);
+                self.last_failure_reason = Some(reason.clone());
+            }
+        }
+        !was_failing && self.is_failing()
+    }
+
+    fn rotation_due(&self, now: u64) -> bool {
+        now.saturating_sub(self.created_at) >= ROTATION_AGE_SECS
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CredentialStatus {
+    pub provider_id: String,
+    pub configured: bool,
+    /// Why the stored credential cannot be used, when it cannot.
+    pub problem: Option<String>,
+    pub created_at: Option<u64>,
+    pub last_success_at: Option<u64>,
+    pub last_failure_at: Option<u64>,
+    pub last_failure_reason: Option<String>,
+    pub fingerprint: Option<String>,
+    pub failing: bool,
+    pub rotation_due: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CredentialFailingEvent {
+    pub provider_id: String,
+    pub fingerprint: String,
+    pub reason: String,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn concerns_the_key(outcome: &Result<(), String>) -> bool {
+    match outcome {
+        Ok(()) => true,
+        Err(reason) => http_status(reason).is_some_and(|code| KEY_FAILURE_STATUSES.contains(&code)),
+    }
+}
+
+fn fingerprint(api_key: &str) -> String {
+    let chars: Vec<char> = api_key.trim().chars().collect();
+    chars[chars.len().saturating_sub(FINGERPRINT_CHARS)..]
+        .iter()
+        .collect()
+}
+
+fn metadata_entry(provider_id: &str) -> Result<Entry, String> {
+    validate_provider_id(provider_id)?;
+    Entry::new(CREDENTIAL_SERVICE, &format!("{provider_id}.metadata"))
+        .map_err(|_| "Unable to access the operating system credential store".to_string())
+}
+
+fn load_metadata(provider_id: &str) -> Option<CredentialMetadata> {
+    let serialized = metadata_entry(provider_id).ok()?.get_password().ok()?;
+    serde_json::from_str(&serialized).ok()
+}
+
+fn store_metadata(provider_id: &str, metadata: &CredentialMetadata) -> Result<(), String> {
+    let serialized = serde_json::to_string(metadata)
+        .map_err(|_| "Unable to prepare the credential metadata".to_string())?;
+    metadata_entry(provider_id)?
+        .set_password(&serialized)
+        .map_err(|_| "Unable to save the credential metadata".to_string())
+}
+
+/// Called whenever a credential is saved. Re-saving the same key (for example with a
+/// new model) keeps its history; a different key starts afresh.
+pub(crate) fn record_saved(provider_id: &str, api_key: &str) {
+    let fingerprint = fingerprint(api_key);
+    let metadata = match load_metadata(provider_id) {
+        Some(existing) if existing.fingerprint == fingerprint => return,
+        _ => CredentialMetadata {
+            created_at: now_secs(),
+            fingerprint,
+            ..CredentialMetadata::default()
+        },
+    };
+    if let Err(e) = store_metadata(provider_id, &metadata) {
+        eprintln!("Failed to save credential metadata: {}", e);
+    }
+}
+
+pub(crate) fn forget(provider_id: &str) {
+    if let Ok(entry) = metadata_entry(provider_id) {
+        let _ = entry.delete_credential();
+    }
+}
+
+/// Notes whether a request made with the stored key worked, skipping failures that
+/// say nothing about the key. Returns the event to emit when the key has just
+/// started failing.
+fn record_outcome(
+    provider_id: &str,
+    outcome: &Result<(), String>,
+) -> Option<CredentialFailingEvent> {
+    if !concerns_the_key(outcome) {
+        return None;
+    }
+    let now = now_secs();
+    // Keys saved before metadata existed are adopted the first time they are used.
+    let mut metadata = load_metadata(provider_id).or_else(|| {
+        read_credential(provider_id)
+            .ok()
+            .map(|credential| CredentialMetadata {
+                created_at: now,
+                fingerprint: fingerprint(&credential.api_key),
+                ..CredentialMetadata::default()
+            })
+    })?;
+    let started_failing = metadata.record(outcome, now);
+    if let Err(e) = store_metadata(provider_id, &metadata) {
+        eprintln!("Failed to save credential metadata: {}", e);
+    }
+    started_failing.then(|| CredentialFailingEvent {
+        provider_id: provider_id.to_string(),
+        fingerprint: metadata.fingerprint,
+        reason: metadata.last_failure_reason.unwrap_or_default(),
+    })
+}
+
+fn status(provider_id: &str, now: u64) -> CredentialStatus {
+    let problem = read_credential(provider_id).err();
+    let metadata = load_metadata(provider_id);
+    CredentialStatus {
+        provider_id: provider_id.to_string(),
+        configured: problem.is_none(),
+        problem,
+        created_at: metadata.as_ref().map(|m| m.created_at),
+        last_success_at: metadata.as_ref().and_then(|m| m.last_success_at),
+        last_failure_at: metadata.as_ref().and_then(|m| m.last_failure_at),
+        last_failure_reason: metadata
+            .as_ref()
+            .and_then(|m| m.last_failure_reason.clone()),
+        fingerprint: metadata.as_ref().map(|m| m.fingerprint.clone()),
+        failing: metadata
+            .as_ref()
+            .is_some_and(CredentialMetadata::is_failing),
+        rotation_due: metadata.as_ref().is_some_and(|m| m.rotation_due(now)),
+    }
+}
+
+/// Records the outcome of a request made with a stored credential and tells the
+/// frontend if the key just stopped working.
+pub(crate) fn report(app: &AppHandle, provider_id: &str, outcome: &Result<(), String>) {
+    if let Some(event) = record_outcome(provider_id, outcome) {
+        if let Err(e) = app.emit(CREDENTIAL_FAILING_EVENT, event) {
+            eprintln!("Failed to emit credential failure: {}", e);
+        }
+    }
+}
+
+/// Starts the background thread that periodically re-tests every stored credential
+/// with the same request as `test_provider_configuration`.
+pub(crate) fn start(app: &AppHandle) {
+    let handle = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(FIRST_CHECK_DELAY);
+        loop {
+            for provider_id in PROVIDER_IDS {
+                let Ok(credential) = read_credential(provider_id) else {
+                    continue;
+                };
+                let outcome = tauri::async_runtime::block_on(crate::check_credential(
+                    provider_id,
+                    &credential,
+                ));
+                report(&handle, provider_id, &outcome);
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) fn credential_status() -> Result<Vec<CredentialStatus>, String> {
+    let now = now_secs();
+    Ok(PROVIDER_IDS
+        .iter()
+        .map(|provider_id| status(provider_id, now))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_only_the_transition_into_failure() {
+        let mut metadata = CredentialMetadata {
+            created_at: 100,
+            fingerprint: fingerprint(" sk-abcdWXYZ "),
+            ..CredentialMetadata::default()
+        };
+        assert_eq!(metadata.fingerprint, "WXYZ");
+        assert_eq!(fingerprint("ab"), "ab");
+
+        assert!(!metadata.record(&Ok(()), 200));
+        let failure = Err("AI provider request failed with HTTP 401 Unauthorized".to_string());
+        assert!(metadata.record(&failure, 300));
+        assert!(!metadata.record(&failure, 400));
+        assert!(metadata.is_failing());
+        assert_eq!(metadata.last_success_at, Some(200));
+        assert_eq!(metadata.last_failure_at, Some(400));
+
+        assert!(!metadata.record(&Ok(()), 500));
+        assert!(!metadata.is_failing());
+        assert_eq!(
+            metadata.last_failure_reason.as_deref(),
+            Some("AI provider request failed with HTTP 401 Unauthorized")
+        );
+        assert!(metadata.record(&failure, 600));
+
+        for unrelated in [
+            "Unable to reach the AI provider",
+            "AI provider request failed with HTTP 429 Too Many Requests",
+            "AI provider request failed with HTTP 503 Service Unavailable",
+            "AI provider request failed with HTTP 408 Request Timeout",
+            "AI provider request failed with HTTP 400 Bad Request",
+            "Prompt is invalid",
+        ] {
+            assert!(
+                !concerns_the_key(&Err(unrelated.to_string())),
+                "{unrelated}"
+            );
+        }
+        for about_the_key in [
+            "AI provider request failed with HTTP 401 Unauthorized",
+            "AI provider request failed with HTTP 402 Payment Required",
+            "AI provider request failed with HTTP 403 Forbidden",
+        ] {
+            assert!(concerns_the_key(&Err(about_the_key.to_string())));
+        }
+        assert!(concerns_the_key(&Ok(())));
+    }
+
+    #[test]
+    fn flags_old_keys_for_rotation() {
+        let metadata = CredentialMetadata {
+            created_at: 1_000,
+            fingerprint: "WXYZ".to_string(),
+            ..CredentialMetadata::default()
+        };
+        assert!(!metadata.rotation_due(1_000 + ROTATION_AGE_SECS - 1));
+        assert!(metadata.rotation_due(1_000 + ROTATION_AGE_SECS));
+        assert!(!metadata.rotation_due(0));
+
+        let fresh = CredentialMetadata::default();
+        assert!(!fresh.is_failing());
+        let serialized = serde_json::to_string(&metadata).unwrap();
+        assert!(!serialized.contains("sk-"));
+        assert_eq!(
+            serde_json::from_str::<CredentialMetadata>(&serialized).unwrap(),
+            metadata
+        );
+    }
+}
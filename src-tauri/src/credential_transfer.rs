@@ -0,0 +1,370 @@
+use crate::exporter::validate_export_path;
+use crate::{
+    read_credential, save_credential, validate_credential, validate_provider_id,
+    StoredProviderCredential, PROVIDER_IDS,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+const FORMAT: &str = "chefmind-credentials";
+const FORMAT_VERSION: u32 = 1;
+const MIN_PASSPHRASE_CHARS: usize = 12;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Argon2id with 64 MiB, three passes and one lane, as recommended by RFC 9106 for
+/// memory-constrained machines.
+const DEFAULT_KDF: KdfParams = KdfParams {
+    memory_kib: 64 * 1024,
+    iterations: 3,
+    parallelism: 1,
+};
+/// Files asking for more than this are refused before any work is done, so a crafted
+/// file cannot make the import allocate gigabytes.
+const MAX_KDF: KdfParams = KdfParams {
+    memory_kib: 1024 * 1024,
+    iterations: 10,
+    parallelism: 8,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfParams {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+/// On-disk envelope. Everything but the ciphertext is authenticated as associated
+/// data, so the KDF settings cannot be swapped without the import failing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CredentialFile {
+    format: String,
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PortableCredential {
+    provider_id: String,
+    #[serde(flatten)]
+    credential: StoredProviderCredential,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CredentialExportResult {
+    pub path: String,
+    pub provider_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CredentialImportResult {
+    pub imported: Vec<String>,
+    /// Providers that already had a credential and were left alone.
+    pub skipped: Vec<String>,
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS || passphrase.len() > 1024 {
+        Err("Passphrase must be at least 12 characters".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn associated_data(kdf: &KdfParams, salt: &str) -> Vec<u8> {
+    format!(
+        "{FORMAT}:{FORMAT_VERSION}:{}:{}:{}:{salt}",
+        kdf.memory_kib, kdf.iterations, kdf.parallelism
+    )
+    .into_bytes()
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], kdf: &KdfParams) -> Result<[u8; 32], String> {
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| format!("Invalid key derivation settings: {}", e))?;
+    let mut key = [0_u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive encryption key: {}", e))?;
+    Ok(key)
+}
+
+fn cipher(key: &[u8; 32]) -> Result<XChaCha20Poly1305, String> {
+    XChaCha20Poly1305::new_from_slice(key)
+        .map_err(|_| "Unable to initialise the cipher".to_string())
+}
+
+fn seal(
+    credentials: &[PortableCredential],
+    passphrase: &str,
+    kdf: KdfParams,
+) -> Result<CredentialFile, String> {
+    validate_passphrase(passphrase)?;
+    let mut salt = [0_u8; SALT_LEN];
+    let mut nonce = [0_u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    let key = derive_key(passphrase, &salt, &kdf)?;
+    let salt = STANDARD.encode(salt);
+
+    let plaintext = serde_json::to_vec(credentials)
+        .map_err(|_| "Unable to prepare the credentials".to_string())?;
+    let aad = associated_data(&kdf, &salt);
+    let ciphertext = cipher(&key)?
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| "Failed to encrypt the credentials".to_string())?;
+    Ok(CredentialFile {
+        format: FORMAT.to_string(),
+        version: FORMAT_VERSION,
+        kdf,
+        salt,
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+fn open(file: &CredentialFile, passphrase: &str) -> Result<Vec<PortableCredential>, String> {
+    if file.format != FORMAT {
+        return Err("This is not a ChefMind credential file".to_string());
+    }
+    if file.version > FORMAT_VERSION {
+        return Err("The credential file was written by a newer version of ChefMind".to_string());
+    }
+    let kdf = file.kdf;
+    if kdf.memory_kib > MAX_KDF.memory_kib
+        || kdf.iterations > MAX_KDF.iterations
+        || kdf.parallelism > MAX_KDF.parallelism
+    {
+        return Err("The credential file asks for unsupported key derivation settings".to_string());
+    }
+    let damaged = |_| "The credential file is damaged".to_string();
+    let salt = STANDARD.decode(&file.salt).map_err(damaged)?;
+    let nonce = STANDARD.decode(&file.nonce).map_err(damaged)?;
+    let ciphertext = STANDARD.decode(&file.ciphertext).map_err(damaged)?;
+    if salt.len() != SALT_LEN || nonce.len() != NONCE_LEN {
+        return Err("The credential file is damaged".to_string());
+    }
+
+    let key = derive_key(passphrase, &salt, &kdf)?;
+    let aad = associated_data(&kdf, &file.salt);
+    let plaintext = cipher(&key)?
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| "The passphrase is incorrect or the file is damaged".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|_| "The credential file is damaged".to_string())
+}
+
+/// Checks every imported credential before any is saved, so a bad entry does not
+/// leave the keyring half updated.
+fn validate_imported(credentials: &[PortableCredential]) -> Result<(), String> {
+    if credentials.is_empty() {
+        return Err("The credential file does not contain any credentials".to_string());
+    }
+    for (index, entry) in credentials.iter().enumerate() {
+        validate_provider_id(&entry.provider_id)?;
+        // Checked up front so a bad entry cannot leave the import half applied.
+        validate_credential(&entry.credential)
+            .map_err(|e| format!("Credential for {}: {}", entry.provider_id, e))?;
+        if credentials[..index]
+            .iter()
+            .any(|earlier| earlier.provider_id == entry.provider_id)
+        {
+            return Err("The credential file lists a provider twice".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn credential_export(
+    path: String,
+    passphrase: String,
+) -> Result<CredentialExportResult, String> {
+    let target = validate_export_path(&path)?;
+    let credentials: Vec<PortableCredential> = PROVIDER_IDS
+        .iter()
+        .filter_map(|provider_id| {
+            read_credential(provider_id)
+                .ok()
+                .map(|credential| PortableCredential {
+                    provider_id: provider_id.to_string(),
+                    credential,
+                })
+        })
+        .collect();
+    if credentials.is_empty() {
+        return Err("There are no provider credentials to export".to_string());
+    }
+    let sealed = seal(&credentials, &passphrase, DEFAULT_KDF)?;
+    let serialized = serde_json::to_vec_pretty(&sealed)
+        .map_err(|_| "Unable to prepare the credential file".to_string())?;
+    fs::write(target, serialized).map_err(|e| format!("Failed to write credential file: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(target, fs::Permissions::from_mode(0o600));
+    }
+    Ok(CredentialExportResult {
+        path,
+        provider_ids: credentials
+            .into_iter()
+            .map(|entry| entry.provider_id)
+            .collect(),
+    })
+}
+
+#[tauri::command]
+pub(crate) fn credential_import(
+    path: String,
+    passphrase: String,
+    overwrite: Option<bool>,
+) -> Result<CredentialImportResult, String> {
+    let size = fs::metadata(&path)
+        .map_err(|e| format!("Failed to read credential file: {}", e))?
+        .len();
+    if size > MAX_FILE_BYTES {
+        return Err("The credential file is too large".to_string());
+    }
+    let raw = fs::read(&path).map_err(|e| format!("Failed to read credential file: {}", e))?;
+    let file = serde_json::from_slice::<CredentialFile>(&raw)
+        .map_err(|_| "This is not a ChefMind credential file".to_string())?;
+    let credentials = open(&file, &passphrase)?;
+    validate_imported(&credentials)?;
+
+    let mut result = CredentialImportResult {
+        imported: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for entry in credentials {
+        if !overwrite.unwrap_or(false) && read_credential(&entry.provider_id).is_ok() {
+            result.skipped.push(entry.provider_id);
+            continue;
+        }
+        save_credential(&entry.provider_id, entry.credential)?;
+        result.imported.push(entry.provider_id);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap settings keep the tests fast; real exports use DEFAULT_KDF.
+    const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn credential(base_url: &str) -> PortableCredential {
+        PortableCredential {
+            provider_id: "openai".to_string(),
+            credential: StoredProviderCredential {
+                api_key: "sk-test-1234".to_string(),
+                base_url: base_url.to_string(),
+                model: "gpt-4o-mini".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn round_trips_and_rejects_wrong_passphrases_or_tampering() {
+        let passphrase = "correct horse battery";
+        let sealed = seal(
+            &[credential("https://api.openai.com/v1")],
+            passphrase,
+            TEST_KDF,
+        )
+        .unwrap();
+        assert!(!sealed.ciphertext.contains("sk-test"));
+
+        let opened = open(&sealed, passphrase).unwrap();
+        assert_eq!(opened.len(), 1);
+        assert_eq!(opened[0].credential.api_key, "sk-test-1234");
+        assert_eq!(opened[0].provider_id, "openai");
+
+        assert_eq!(
+            open(&sealed, "wrong passphrase!").unwrap_err(),
+            "The passphrase is incorrect or the file is damaged"
+        );
+        let weakened = CredentialFile {
+            kdf: KdfParams {
+                iterations: 2,
+                ..TEST_KDF
+            },
+            ..sealed.clone()
+        };
+        assert!(open(&weakened, passphrase).is_err());
+        let greedy = CredentialFile {
+            kdf: KdfParams {
+                memory_kib: u32::MAX,
+                ..TEST_KDF
+            },
+            ..sealed.clone()
+        };
+        assert_eq!(
+            open(&greedy, passphrase).unwrap_err(),
+            "The credential file asks for unsupported key derivation settings"
+        );
+        assert_eq!(
+            seal(&[], "short", TEST_KDF).unwrap_err(),
+            "Passphrase must be at least 12 characters"
+        );
+    }
+
+    #[test]
+    fn revalidates_imported_credentials() {
+        assert!(validate_imported(&[credential("https://api.openai.com/v1")]).is_ok());
+        assert!(validate_imported(&[credential("http://example.com/v1")])
+            .unwrap_err()
+            .starts_with("Credential for openai:"));
+        assert_eq!(
+            validate_imported(&[]).unwrap_err(),
+            "The credential file does not contain any credentials"
+        );
+        assert_eq!(
+            validate_imported(&[
+                credential("https://api.openai.com/v1"),
+                credential("https://api.openai.com/v1"),
+            ])
+            .unwrap_err(),
+            "The credential file lists a provider twice"
+        );
+        let mut keyless = credential("https://api.openai.com/v1");
+        keyless.credential.api_key = "  ".to_string();
+        assert_eq!(
+            validate_imported(&[keyless]).unwrap_err(),
+            "Credential for openai: Provider credential is incomplete"
+        );
+        let mut long_model = credential("https://api.openai.com/v1");
+        long_model.credential.model = "m".repeat(257);
+        assert!(validate_imported(&[long_model]).is_err());
+        let mut unknown = credential("https://api.openai.com/v1");
+        unknown.provider_id = "elsewhere".to_string();
+        assert_eq!(
+            validate_imported(&[unknown]).unwrap_err(),
+            "Unsupported AI provider credential"
+        );
+    }
+}
@@ -0,0 +1,591 @@
+use crate::health::load_health_profile;
+use crate::matching::ingredient_names_match;
+use crate::profiles::ProfileState;
+use crate::recipe::{insert_recipe, RecipeDraft};
+use crate::DatabaseState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+// Sodium per serving above which a recipe no longer counts as low-sodium (mg).
+const LOW_SODIUM_LIMIT_MG: f64 = 600.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllergenClass {
+    Peanut,
+    TreeNut,
+    Shellfish,
+    Fish,
+    Gluten,
+    Dairy,
+    Egg,
+    Soy,
+    Sesame,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DietClass {
+    Vegetarian,
+    Vegan,
+    Halal,
+    LowSodium,
+}
+
+/// Keywords that place an ingredient in a class, plus names that contain a keyword
+/// without belonging to it (椰奶 is not dairy, 牛油果 is not meat).
+struct ClassRule {
+    keywords: &'static [&'static str],
+    exceptions: &'static [&'static str],
+}
+
+const MEAT_KEYWORDS: &[&str] = &[
+    "肉", "猪", "牛", "羊", "鸡", "鸭", "鹅", "排骨", "培根", "火腿", "香肠", "腊肠", "高汤",
+    "骨汤", "鱼", "虾", "蟹", "贝", "蚝", "鱿鱼", "海参", "meat", "pork", "beef", "lamb",
+    "chicken", "duck", "bacon", "ham", "sausage", "fish", "shrimp", "crab", "anchovy",
+];
+const MEAT_EXCEPTIONS: &[&str] = &[
+    "鸡蛋",
+    "鸭蛋",
+    "鹅蛋",
+    "牛奶",
+    "羊奶",
+    "牛油果",
+    "素肉",
+    "素鸡",
+    "鸡腿菇",
+    "牛肝菌",
+    "猴头菇",
+    "鱼香",
+    "蟹味菇",
+    "贝贝南瓜",
+    "肉桂",
+    "肉豆蔻",
+    "果肉",
+];
+
+fn allergen_rule(class: AllergenClass) -> ClassRule {
+    match class {
+        AllergenClass::Peanut => ClassRule {
+            keywords: &["花生", "peanut"],
+            exceptions: &[],
+        },
+        AllergenClass::TreeNut => ClassRule {
+            keywords: &[
+                "核桃",
+                "杏仁",
+                "腰果",
+                "榛子",
+                "开心果",
+                "松子",
+                "碧根果",
+                "夏威夷果",
+                "walnut",
+                "almond",
+                "cashew",
+                "hazelnut",
+                "pecan",
+                "pistachio",
+            ],
+            exceptions: &[],
+        },
+        AllergenClass::Shellfish => ClassRule {
+            keywords: &[
+                "虾", "蟹", "贝", "蛤", "蚝", "牡蛎", "蛏", "螺", "鱿鱼", "墨鱼", "章鱼", "shrimp",
+                "prawn", "crab", "lobster", "oyster", "clam", "mussel", "scallop", "squid",
+            ],
+            exceptions: &["蟹味菇", "贝贝南瓜", "虾皮菜"],
+        },
+        AllergenClass::Fish => ClassRule {
+            keywords: &[
+                "鱼",
+                "鳕",
+                "三文鱼",
+                "金枪鱼",
+                "fish",
+                "salmon",
+                "tuna",
+                "cod",
+                "anchovy",
+            ],
+            exceptions: &["鱼香", "鱿鱼", "墨鱼", "章鱼", "鱼腥草"],
+        },
+        AllergenClass::Gluten => ClassRule {
+            keywords: &[
+                "面粉",
+                "小麦",
+                "面条",
+                "挂面",
+                "面包",
+                "馒头",
+                "饺子皮",
+                "馄饨皮",
+                "面筋",
+                "烤麸",
+                "大麦",
+                "黑麦",
+                "啤酒",
+                "酱油",
+                "生抽",
+                "老抽",
+                "flour",
+                "wheat",
+                "bread",
+                "pasta",
+                "noodle",
+                "barley",
+                "rye",
+                "beer",
+                "soy sauce",
+            ],
+            exceptions: &["荞麦面", "米粉", "米线", "粉丝", "无麸质"],
+        },
+        AllergenClass::Dairy => ClassRule {
+            keywords: &[
+                "奶", "黄油", "芝士", "奶酪", "乳酪", "酸奶", "炼乳", "乳清", "milk", "butter",
+                "cheese", "cream", "yogurt", "whey",
+            ],
+            exceptions: &[
+                "椰奶",
+                "椰浆",
+                "豆奶",
+                "杏仁奶",
+                "燕麦奶",
+                "花生奶",
+                "奶白菜",
+                "coconut milk",
+            ],
+        },
+        AllergenClass::Egg => ClassRule {
+            keywords: &["蛋", "egg", "mayonnaise", "蛋黄酱"],
+            exceptions: &["蛋白粉", "eggplant"],
+        },
+        AllergenClass::Soy => ClassRule {
+            keywords: &[
+                "豆腐",
+                "大豆",
+                "黄豆",
+                "豆浆",
+                "豆奶",
+                "腐竹",
+                "豆皮",
+                "千张",
+                "酱油",
+                "生抽",
+                "老抽",
+                "豆瓣酱",
+                "黄豆酱",
+                "味噌",
+                "毛豆",
+                "tofu",
+                "soy",
+                "edamame",
+                "miso",
+            ],
+            exceptions: &[],
+        },
+        AllergenClass::Sesame => ClassRule {
+            keywords: &["芝麻", "麻油", "香油", "麻酱", "sesame", "tahini"],
+            exceptions: &[],
+        },
+    }
+}
+
+fn diet_rules(class: DietClass) -> Vec<ClassRule> {
+    match class {
+        DietClass::Vegetarian => vec![ClassRule {
+            keywords: MEAT_KEYWORDS,
+            exceptions: MEAT_EXCEPTIONS,
+        }],
+        DietClass::Vegan => vec![
+            ClassRule {
+                keywords: MEAT_KEYWORDS,
+                exceptions: MEAT_EXCEPTIONS,
+            },
+            allergen_rule(AllergenClass::Dairy),
+            allergen_rule(AllergenClass::Egg),
+            ClassRule {
+                keywords: &["蜂蜜", "明胶", "猪油", "honey", "gelatin", "lard"],
+                exceptions: &[],
+            },
+        ],
+        DietClass::Halal => vec![ClassRule {
+            keywords: &[
+                "猪",
+                "培根",
+                "火腿",
+                "腊肉",
+                "午餐肉",
+                "酒",
+                // Blood dishes only; a bare "血" would also flag 血糯米 and 血橙.
+                "鸭血",
+                "鸡血",
+                "血豆腐",
+                "血肠",
+                "血旺",
+                "pork",
+                "bacon",
+                "ham",
+                "lard",
+                "wine",
+                "beer",
+                "blood",
+            ],
+            exceptions: &["酒酿"],
+        }],
+        DietClass::LowSodium => vec![ClassRule {
+            keywords: &[
+                "酱油",
+                "生抽",
+                "老抽",
+                "蚝油",
+                "豆瓣酱",
+                "鱼露",
+                "咸菜",
+                "榨菜",
+                "腌",
+                "腊",
+                "培根",
+                "火腿",
+                "咸",
+                "味精",
+                "鸡精",
+                "bacon",
+                "ham",
+                "fish sauce",
+                "soy sauce",
+            ],
+            exceptions: &[],
+        }],
+    }
+}
+
+impl ClassRule {
+    fn matches(&self, ingredient: &str) -> bool {
+        let name = ingredient.to_lowercase();
+        self.keywords.iter().any(|keyword| name.contains(keyword))
+            && !self
+                .exceptions
+                .iter()
+                .any(|exception| name.contains(exception))
+    }
+}
+
+fn allergen_from_label(label: &str) -> Option<AllergenClass> {
+    let label = label.trim().to_lowercase();
+    let class = match label.as_str() {
+        "花生" | "peanut" | "peanuts" => AllergenClass::Peanut,
+        "坚果" | "树坚果" | "核桃" | "杏仁" | "腰果" | "tree_nut" | "tree nuts" | "nuts" => {
+            AllergenClass::TreeNut
+        }
+        "海鲜" | "贝类" | "甲壳类" | "虾" | "蟹" | "shellfish" | "seafood" => {
+            AllergenClass::Shellfish
+        }
+        "鱼" | "鱼类" | "fish" => AllergenClass::Fish,
+        "麸质" | "小麦" | "面筋" | "无麸质" | "无麸质饮食" | "gluten" | "wheat" | "gluten-free"
+        | "gluten_free" => AllergenClass::Gluten,
+        "乳制品" | "牛奶" | "奶" | "乳糖" | "dairy" | "milk" | "lactose" => {
+            AllergenClass::Dairy
+        }
+        "鸡蛋" | "蛋" | "蛋类" | "egg" | "eggs" => AllergenClass::Egg,
+        "大豆" | "黄豆" | "豆制品" | "soy" | "soybean" => AllergenClass::Soy,
+        "芝麻" | "sesame" => AllergenClass::Sesame,
+        _ => return None,
+    };
+    Some(class)
+}
+
+fn diet_from_label(label: &str) -> Option<DietClass> {
+    let class = match label.trim().to_lowercase().as_str() {
+        "素食" | "素食主义" | "蛋奶素" | "vegetarian" => DietClass::Vegetarian,
+        "纯素" | "纯素食" | "vegan" => DietClass::Vegan,
+        "清真" | "halal" => DietClass::Halal,
+        "低盐" | "低盐饮食" | "低钠" | "low-sodium" | "low_sodium" => DietClass::LowSodium,
+        _ => return None,
+    };
+    Some(class)
+}
+
+/// User-supplied allergies and dietary restrictions resolved into rule classes.
+/// Allergy labels the engine doesn't know are kept as literal ingredient names.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DietaryRules {
+    pub allergens: Vec<AllergenClass>,
+    pub diets: Vec<DietClass>,
+    pub custom_allergens: Vec<String>,
+    pub unrecognized_restrictions: Vec<String>,
+}
+
+impl DietaryRules {
+    pub(crate) fn from_labels(allergens: &[String], dietary_restrictions: &[String]) -> Self {
+        let mut rules = Self::default();
+        for label in allergens.iter().map(|label| label.trim()) {
+            if label.is_empty() {
+                continue;
+            }
+            match allergen_from_label(label) {
+                Some(class) if !rules.allergens.contains(&class) => rules.allergens.push(class),
+                Some(_) => {}
+                None => rules.custom_allergens.push(label.to_string()),
+            }
+        }
+        for label in dietary_restrictions.iter().map(|label| label.trim()) {
+            if label.is_empty() {
+                continue;
+            }
+            if let Some(class) = diet_from_label(label) {
+                if !rules.diets.contains(&class) {
+                    rules.diets.push(class);
+                }
+            } else if let Some(class) = allergen_from_label(label) {
+                // "无麸质" arrives as a dietary restriction but behaves like an allergy.
+                if !rules.allergens.contains(&class) {
+                    rules.allergens.push(class);
+                }
+            } else {
+                rules.unrecognized_restrictions.push(label.to_string());
+            }
+        }
+        rules
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.allergens.is_empty() && self.diets.is_empty() && self.custom_allergens.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DietaryViolation {
+    Allergen {
+        ingredient: String,
+        class: AllergenClass,
+    },
+    CustomAllergen {
+        ingredient: String,
+        allergen: String,
+    },
+    Diet {
+        ingredient: String,
+        class: DietClass,
+    },
+    SodiumLimit {
+        sodium_mg: f64,
+        limit_mg: f64,
+    },
+}
+
+/// Checks ingredient names (and per-serving sodium when known) against the rules.
+pub(crate) fn check_ingredients(
+    rules: &DietaryRules,
+    ingredients: &[String],
+    sodium_mg: Option<f64>,
+) -> Vec<DietaryViolation> {
+    let mut violations = Vec::new();
+    for ingredient in ingredients {
+        for class in &rules.allergens {
+            if allergen_rule(*class).matches(ingredient) {
+                violations.push(DietaryViolation::Allergen {
+                    ingredient: ingredient.clone(),
+                    class: *class,
+                });
+            }
+        }
+        for allergen in &rules.custom_allergens {
+            if ingredient_names_match(allergen, ingredient) {
+                violations.push(DietaryViolation::CustomAllergen {
+                    ingredient: ingredient.clone(),
+                    allergen: allergen.clone(),
+                });
+            }
+        }
+        for class in &rules.diets {
+            if diet_rules(*class)
+                .iter()
+                .any(|rule| rule.matches(ingredient))
+            {
+                violations.push(DietaryViolation::Diet {
+                    ingredient: ingredient.clone(),
+                    class: *class,
+                });
+            }
+        }
+    }
+
+    if rules.diets.contains(&DietClass::LowSodium) {
+        if let Some(sodium_mg) = sodium_mg.filter(|sodium| *sodium > LOW_SODIUM_LIMIT_MG) {
+            violations.push(DietaryViolation::SodiumLimit {
+                sodium_mg,
+                limit_mg: LOW_SODIUM_LIMIT_MG,
+            });
+        }
+    }
+    violations
+}
+
+/// Rules from the profile's health settings plus any labels the caller adds, so a
+/// save is checked even when the UI sends nothing.
+pub(crate) fn rules_for_session(
+    conn: &Connection,
+    session_id: &str,
+    allergens: &[String],
+    dietary_restrictions: &[String],
+) -> Result<DietaryRules, String> {
+    let mut allergens = allergens.to_vec();
+    let mut dietary_restrictions = dietary_restrictions.to_vec();
+    if let Some(profile) = load_health_profile(conn, session_id)? {
+        allergens.extend(profile.allergies);
+        dietary_restrictions.extend(profile.dietary_restrictions);
+    }
+    Ok(DietaryRules::from_labels(&allergens, &dietary_restrictions))
+}
+
+pub(crate) fn check_draft(rules: &DietaryRules, draft: &RecipeDraft) -> Vec<DietaryViolation> {
+    let names: Vec<String> = draft
+        .ingredient_list()
+        .into_iter()
+        .map(|ingredient| ingredient.name)
+        .collect();
+    check_ingredients(rules, &names, draft.nutrition().map(|facts| facts.sodium))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DietaryCheckResult {
+    pub rules: DietaryRules,
+    pub violations: Vec<DietaryViolation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GeneratedRecipeSaveResult {
+    pub recipe_id: Option<i64>,
+    pub violations: Vec<DietaryViolation>,
+}
+
+#[tauri::command]
+pub(crate) fn recipe_check_dietary(
+    recipe: RecipeDraft,
+    allergens: Option<Vec<String>>,
+    dietary_restrictions: Option<Vec<String>>,
+) -> Result<DietaryCheckResult, String> {
+    let rules = DietaryRules::from_labels(
+        &allergens.unwrap_or_default(),
+        &dietary_restrictions.unwrap_or_default(),
+    );
+    let violations = check_draft(&rules, &recipe);
+    Ok(DietaryCheckResult { rules, violations })
+}
+
+/// Saves an AI-generated recipe only when it passes the active profile's allergy and
+/// diet rules plus any the caller adds; otherwise nothing is written and the
+/// violations go back to the UI. This is the frontend's save path for AI recipes.
+#[tauri::command]
+pub(crate) fn recipe_save_generated(
+    recipe: RecipeDraft,
+    allergens: Option<Vec<String>>,
+    dietary_restrictions: Option<Vec<String>>,
+    db: State<DatabaseState>,
+    profiles: State<ProfileState>,
+) -> Result<GeneratedRecipeSaveResult, String> {
+    recipe.validate()?;
+    let conn = db.open_connection()?;
+    let rules = rules_for_session(
+        &conn,
+        &profiles.session_id()?,
+        &allergens.unwrap_or_default(),
+        &dietary_restrictions.unwrap_or_default(),
+    )?;
+    let violations = check_draft(&rules, &recipe);
+    if !violations.is_empty() {
+        return Ok(GeneratedRecipeSaveResult {
+            recipe_id: None,
+            violations,
+        });
+    }
+
+    let recipe_id =
+        insert_recipe(&conn, &recipe).map_err(|e| format!("Failed to save recipe: {}", e))?;
+    Ok(GeneratedRecipeSaveResult {
+        recipe_id: Some(recipe_id),
+        violations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn maps_ingredients_to_allergen_classes_with_exceptions() {
+        let rules = DietaryRules::from_labels(&labels(&["花生", "海鲜", "乳制品", "香菜"]), &[]);
+        assert_eq!(
+            rules.allergens,
+            vec![
+                AllergenClass::Peanut,
+                AllergenClass::Shellfish,
+                AllergenClass::Dairy
+            ]
+        );
+        assert_eq!(rules.custom_allergens, vec!["香菜"]);
+
+        let violations = check_ingredients(
+            &rules,
+            &labels(&["花生酱", "虾仁", "椰奶", "蟹味菇", "黄油", "芫荽"]),
+            None,
+        );
+        let flagged: Vec<&str> = violations
+            .iter()
+            .map(|violation| match violation {
+                DietaryViolation::Allergen { ingredient, .. }
+                | DietaryViolation::CustomAllergen { ingredient, .. }
+                | DietaryViolation::Diet { ingredient, .. } => ingredient.as_str(),
+                DietaryViolation::SodiumLimit { .. } => "sodium",
+            })
+            .collect();
+        assert_eq!(flagged, vec!["花生酱", "虾仁", "黄油", "芫荽"]);
+    }
+
+    #[test]
+    fn applies_vegetarian_halal_and_low_sodium_rules() {
+        let rules =
+            DietaryRules::from_labels(&[], &labels(&["素食主义", "清真", "低盐饮食", "无麸质"]));
+        assert_eq!(
+            rules.diets,
+            vec![
+                DietClass::Vegetarian,
+                DietClass::Halal,
+                DietClass::LowSodium
+            ]
+        );
+        assert_eq!(rules.allergens, vec![AllergenClass::Gluten]);
+
+        let clean = check_ingredients(
+            &rules,
+            &labels(&["鸡蛋", "牛油果", "番茄", "血糯米"]),
+            Some(300.0),
+        );
+        assert!(clean.is_empty(), "unexpected violations: {clean:?}");
+
+        let violations = check_ingredients(&rules, &labels(&["猪肉", "料酒", "鸭血"]), Some(900.0));
+        assert!(violations.contains(&DietaryViolation::Diet {
+            ingredient: "鸭血".to_string(),
+            class: DietClass::Halal,
+        }));
+        assert!(violations.contains(&DietaryViolation::Diet {
+            ingredient: "猪肉".to_string(),
+            class: DietClass::Vegetarian,
+        }));
+        assert!(violations.contains(&DietaryViolation::Diet {
+            ingredient: "料酒".to_string(),
+            class: DietClass::Halal,
+        }));
+        assert!(matches!(
+            violations.last(),
+            Some(DietaryViolation::SodiumLimit { .. })
+        ));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::{DatabaseState, CREDENTIAL_SERVICE};
+use keyring::Entry;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// Keyring account holding the database key, next to the provider credentials.
+const DATABASE_KEY_ACCOUNT: &str = "chefmind-database-key";
+/// Every plaintext SQLite file starts with this header; a SQLCipher file does not.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+const KEY_HEX_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatabaseEncryptionStatus {
+    /// Whether this build links SQLCipher.
+    pub available: bool,
+    pub encrypted: bool,
+    pub key_present: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatabaseEncryptionResult {
+    /// Shown once so the user can write it down; it is the only way back in if the
+    /// keyring entry is lost.
+    pub recovery_key: String,
+}
+
+pub(crate) fn is_available() -> bool {
+    cfg!(feature = "sqlcipher")
+}
+
+pub(crate) fn is_encrypted(path: &Path) -> bool {
+    let mut header = [0_u8; 16];
+    match fs::File::open(path) {
+        Ok(mut file) => file.read_exact(&mut header).is_ok() && &header != SQLITE_HEADER,
+        Err(_) => false,
+    }
+}
+
+fn is_key(key: &str) -> bool {
+    key.len() == KEY_HEX_LEN && key.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn key_entry() -> Result<Entry, String> {
+    Entry::new(CREDENTIAL_SERVICE, DATABASE_KEY_ACCOUNT)
+        .map_err(|_| "Unable to access the operating system credential store".to_string())
+}
+
+pub(crate) fn load_key() -> Option<String> {
+    key_entry()
+        .ok()?
+        .get_password()
+        .ok()
+        .filter(|key| is_key(key))
+}
+
+fn store_key(key: &str) -> Result<(), String> {
+    key_entry()?
+        .set_password(key)
+        .map_err(|_| "Unable to save the database key in the operating system store".to_string())
+}
+
+/// Applies the raw 256-bit key; must run before anything else touches the connection.
+pub(crate) fn apply_key(conn: &Connection, key: &str) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!("PRAGMA key = \"x'{key}'\";"))
+}
+
+pub(crate) fn missing_key_error() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTADB),
+        Some(
+            "The database is encrypted but its key is missing from the system keyring; enter the recovery key to unlock it"
+                .to_string(),
+        ),
+    )
+}
+
+/// SQLite's `randomblob` draws from the operating system's random source.
+fn generate_key(conn: &Connection) -> Result<String, String> {
+    conn.query_row("SELECT lower(hex(randomblob(32)))", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to generate database key: {}", e))
+}
+
+/// Groups the key as `XXXXXXXX-XXXXXXXX-…` so it can be copied down by hand.
+pub(crate) fn format_recovery_key(key: &str) -> String {
+    key.to_ascii_uppercase()
+        .as_bytes()
+        .chunks(8)
+        .map(|chunk| String::from_utf8_lossy(chunk).to_string())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+pub(crate) fn parse_recovery_key(input: &str) -> Result<String, String> {
+    let key: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if is_key(&key) {
+        Ok(key)
+    } else {
+        Err("The recovery key is not valid".to_string())
+    }
+}
+
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Copies every table, index and trigger into a new file, encrypted with `key` or
+/// plaintext without one.
+fn export_database(source: &Connection, target: &Path, key: Option<&str>) -> Result<(), String> {
+    let _ = fs::remove_file(target);
+    let key = key.map_or_else(|| "''".to_string(), |key| format!("\"x'{key}'\""));
+    source
+        .execute(
+            &format!("ATTACH DATABASE ?1 AS export KEY {key}"),
+            [target.to_string_lossy()],
+        )
+        .and_then(|_| source.query_row("SELECT sqlcipher_export('export')", [], |_| Ok(())))
+        .and_then(|_| source.execute_batch("DETACH DATABASE export;"))
+        .map_err(|e| {
+            let _ = fs::remove_file(target);
+            format!("Failed to export database: {}", e)
+        })
+}
+
+fn verify(path: &Path, key: Option<&str>) -> Result<(), String> {
+    let conn = Connection::open(path).map_err(|e| format!("Failed to verify database: {}", e))?;
+    if let Some(key) = key {
+        apply_key(&conn, key).map_err(|e| format!("Failed to verify database: {}", e))?;
+    }
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))
+        .map_err(|e| format!("Failed to verify database: {}", e))
+}
+
+/// Moves `replacement` over the database file, dropping the old WAL and shared
+/// memory files, which belong to the old file and were checkpointed beforehand.
+fn replace_database(db_path: &Path, replacement: &Path) -> Result<(), String> {
+    for suffix in ["-wal", "-shm"] {
+        let _ = fs::remove_file(sidecar(db_path, suffix));
+    }
+    fs::rename(replacement, db_path).map_err(|e| format!("Failed to replace database: {}", e))
+}
+
+/// Re-writes the database through `sqlcipher_export` with or without a key. Waits
+/// for every other connection to close and keeps new ones out until the new file
+/// is in place; the shared connection is closed for the swap and reopened afterwards.
+fn rewrite_database(db: &DatabaseState, key: Option<&str>) -> Result<(), String> {
+    let _gate = db.rewrite_gate.write().unwrap_or_else(|e| e.into_inner());
+    let mut held = db._connection.lock().unwrap_or_else(|e| e.into_inner());
+    *held = None;
+    let conn = db
+        .connect()
+        .map_err(|e| format!("Database connection error: {}", e))?;
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+        .map_err(|e| format!("Failed to checkpoint database: {}", e))?;
+    let target = sidecar(&db.db_path, ".rewrite");
+    export_database(&conn, &target, key)?;
+    verify(&target, key)?;
+    drop(conn);
+    replace_database(&db.db_path, &target)?;
+    db.set_encryption_key(key.map(str::to_string));
+    *held = db.connect().ok();
+    Ok(())
+}
+
+/// Encrypts a plaintext database with a fresh random key. The key is stored in the
+/// keyring before the file is rewritten, so an interrupted run never leaves an
+/// encrypted file without its key.
+pub(crate) fn encrypt_database(db: &DatabaseState) -> Result<DatabaseEncryptionResult, String> {
+    if !is_available() {
+        return Err("This build of ChefMind does not include database encryption".to_string());
+    }
+    if is_encrypted(&db.db_path) {
+        return Err("The database is already encrypted".to_string());
+    }
+    let key = generate_key(&*db.open_connection()?)?;
+    store_key(&key)?;
+    rewrite_database(db, Some(&key))?;
+    Ok(DatabaseEncryptionResult {
+        recovery_key: format_recovery_key(&key),
+    })
+}
+
+pub(crate) fn decrypt_database(db: &DatabaseState) -> Result<(), String> {
+    if !is_encrypted(&db.db_path) {
+        return Err("The database is not encrypted".to_string());
+    }
+    rewrite_database(db, None)?;
+    if let Ok(entry) = key_entry() {
+        let _ = entry.delete_credential();
+    }
+    Ok(())
+}
+
+/// Unlocks the database with the recovery key and puts the key back in the keyring.
+pub(crate) fn recover_key(db: &DatabaseState, recovery_key: &str) -> Result<(), String> {
+    if !is_encrypted(&db.db_path) {
+        return Err("The database is not encrypted".to_string());
+    }
+    let key = parse_recovery_key(recovery_key)?;
+    verify(&db.db_path, Some(&key))
+        .map_err(|_| "The recovery key does not unlock this database".to_string())?;
+    store_key(&key)?;
+    db.set_encryption_key(Some(key));
+    Ok(())
+}
+
+/// Last resort when the key and the recovery key are both lost: the unreadable file
+/// is moved aside, never deleted, and an empty database is created in its place.
+/// Returns where the old file went.
+pub(crate) fn set_aside_unreadable(db: &DatabaseState) -> Result<String, String> {
+    if !is_encrypted(&db.db_path) {
+        return Err("The database is not encrypted".to_string());
+    }
+    if db
+        .database_key()
+        .is_some_and(|key| verify(&db.db_path, Some(&key)).is_ok())
+    {
+        return Err("The database can still be unlocked".to_string());
+    }
+    let _gate = db.rewrite_gate.write().unwrap_or_else(|e| e.into_inner());
+    let mut held = db._connection.lock().unwrap_or_else(|e| e.into_inner());
+    *held = None;
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let target = sidecar(&db.db_path, &format!(".unreadable-{stamp}"));
+    fs::rename(&db.db_path, &target)
+        .map_err(|e| format!("Failed to move the database aside: {}", e))?;
+    for suffix in ["-wal", "-shm"] {
+        let _ = fs::rename(sidecar(&db.db_path, suffix), sidecar(&target, suffix));
+    }
+    db.set_encryption_key(None);
+    if let Ok(entry) = key_entry() {
+        let _ = entry.delete_credential();
+    }
+    *held = db.connect().ok();
+    Ok(target.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub(crate) fn database_encryption_status(
+    db: State<DatabaseState>,
+) -> Result<DatabaseEncryptionStatus, String> {
+    let encrypted = is_encrypted(&db.db_path);
+    Ok(DatabaseEncryptionStatus {
+        available: is_available(),
+        encrypted,
+        key_present: encrypted && db.database_key().is_some(),
+    })
+}
+
+#[tauri::command]
+pub(crate) fn database_encrypt(
+    db: State<DatabaseState>,
+) -> Result<DatabaseEncryptionResult, String> {
+    encrypt_database(&db)
+}
+
+#[tauri::command]
+pub(crate) fn database_decrypt(db: State<DatabaseState>) -> Result<(), String> {
+    decrypt_database(&db)
+}
+
+#[tauri::command]
+pub(crate) fn database_recover_key(
+    recovery_key: String,
+    db: State<DatabaseState>,
+) -> Result<(), String> {
+    recover_key(&db, &recovery_key)
+}
+
+#[tauri::command]
+pub(crate) fn database_set_aside_unreadable(db: State<DatabaseState>) -> Result<String, String> {
+    set_aside_unreadable(&db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_parses_recovery_keys() {
+        let key = "0123456789abcdef".repeat(4);
+        let recovery = format_recovery_key(&key);
+        assert_eq!(&recovery[..18], "01234567-89ABCDEF-");
+        assert_eq!(recovery.len(), 64 + 7);
+        assert_eq!(parse_recovery_key(&recovery).unwrap(), key);
+        assert_eq!(
+            parse_recovery_key(&format!(" {} ", recovery.replace('-', " "))).unwrap(),
+            key
+        );
+        assert_eq!(
+            parse_recovery_key("0123-4567").unwrap_err(),
+            "The recovery key is not valid"
+        );
+        assert!(parse_recovery_key(&"g".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn detects_encrypted_files_by_header() {
+        let dir = std::env::temp_dir().join(format!("chefmind-encryption-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let plain = dir.join("plain.db");
+        Connection::open(&plain)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (x INTEGER);")
+            .unwrap();
+        assert!(!is_encrypted(&plain));
+        assert!(!is_encrypted(&dir.join("missing.db")));
+
+        let scrambled = dir.join("scrambled.db");
+        fs::write(&scrambled, [0x5a_u8; 4096]).unwrap();
+        assert!(is_encrypted(&scrambled));
+        assert_eq!(sidecar(&plain, "-wal"), dir.join("plain.db-wal"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn encrypts_reopens_and_decrypts_a_database() {
+        let dir = std::env::temp_dir().join(format!(
+            "chefmind-encryption-rewrite-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let probe = |db: &DatabaseState| -> String {
+            db.open_connection()
+                .unwrap()
+                .query_row(
+                    "SELECT value FROM settings WHERE key = 'probe'",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+
+        let db = DatabaseState::at_path(dir.join("chefmind.db"));
+        db.open_connection()
+            .unwrap()
+            .execute(
+                "INSERT INTO settings (key, value, category) VALUES ('probe', 'kept', 'test')",
+                [],
+            )
+            .unwrap();
+        let key = "0123456789abcdef".repeat(4);
+        rewrite_database(&db, Some(&key)).unwrap();
+        assert!(is_encrypted(&db.db_path));
+        assert!(verify(&db.db_path, None).is_err());
+
+        let reopened = DatabaseState::at_path(db.db_path.clone());
+        reopened.set_encryption_key(Some(key.clone()));
+        assert_eq!(probe(&reopened), "kept");
+
+        rewrite_database(&reopened, None).unwrap();
+        assert!(!is_encrypted(&reopened.db_path));
+        assert!(verify(&reopened.db_path, None).is_ok());
+        assert_eq!(probe(&reopened), "kept");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,588 @@
+use crate::images::{is_portable_image_url, resolve_image_path};
+use crate::profiles::ProfileState;
+use crate::recipe::{
+    parse_ingredient_lines, parse_steps, require_recipe, StoredRecipe, RECIPE_COLUMNS,
+};
+use crate::DatabaseState;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::State;
+
+const MAX_EXPORT_RECIPES: usize = 1_000;
+const MAX_EMBEDDED_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeExportFormat {
+    Markdown,
+    JsonLd,
+    Html,
+    PlainText,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeExportResult {
+    pub path: String,
+    pub format: RecipeExportFormat,
+    pub recipe_count: usize,
+}
+
+/// Format-independent view of a recipe; every template renders from this so the
+/// same fields appear in the same order whichever format the user picks.
+pub(crate) struct RecipeCard {
+    title: String,
+    description: Option<String>,
+    details: Vec<(&'static str, String)>,
+    ingredients: Vec<String>,
+    steps: Vec<String>,
+    nutrition: Vec<(&'static str, String)>,
+    tags: Vec<String>,
+    image_url: Option<String>,
+    recipe: StoredRecipe,
+}
+
+// Tags and cooking methods: a JSON array of strings or one entry per line.
+fn json_list(raw: Option<&str>) -> Vec<String> {
+    let Some(raw) = raw else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(serde_json::Value::as_str)
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => raw
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect(),
+    }
+}
+
+fn format_amount(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+impl RecipeCard {
+    /// Stored images only resolve inside this app, so text formats keep remote URLs only.
+    fn portable_image(&self) -> Option<&str> {
+        self.image_url
+            .as_deref()
+            .filter(|url| is_portable_image_url(url))
+    }
+
+    pub(crate) fn from_recipe(recipe: StoredRecipe) -> Self {
+        let mut details = Vec::new();
+        if let Some(minutes) = recipe.cooking_time {
+            details.push(("烹饪时间", format!("{minutes} 分钟")));
+        }
+        if let Some(servings) = recipe.servings {
+            details.push(("份量", format!("{servings} 人份")));
+        }
+        if let Some(difficulty) = recipe.difficulty.as_ref().filter(|text| !text.is_empty()) {
+            details.push(("难度", difficulty.clone()));
+        }
+        if let Some(category) = recipe.category.as_ref().filter(|text| !text.is_empty()) {
+            details.push(("分类", category.clone()));
+        }
+        let methods = json_list(recipe.cooking_methods.as_deref());
+        if !methods.is_empty() {
+            details.push(("烹饪方式", methods.join("、")));
+        }
+
+        let nutrition = recipe
+            .nutrition()
+            .map(|facts| {
+                [
+                    ("热量", facts.calories, "kcal"),
+                    ("蛋白质", facts.protein, "g"),
+                    ("碳水化合物", facts.carbs, "g"),
+                    ("脂肪", facts.fat, "g"),
+                    ("膳食纤维", facts.fiber, "g"),
+                    ("钠", facts.sodium, "mg"),
+                ]
+                .into_iter()
+                .filter(|(_, value, _)| *value > 0.0)
+                .map(|(label, value, unit)| (label, format!("{} {unit}", format_amount(value))))
+                .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            title: recipe.title.clone(),
+            description: recipe
+                .description
+                .clone()
+                .filter(|text| !text.trim().is_empty()),
+            details,
+            ingredients: parse_ingredient_lines(&recipe.ingredients),
+            steps: parse_steps(&recipe.instructions),
+            nutrition,
+            tags: json_list(recipe.tags.as_deref()),
+            image_url: recipe
+                .image_url
+                .clone()
+                .filter(|url| !url.trim().is_empty()),
+            recipe,
+        }
+    }
+}
+
+pub(crate) fn render_markdown(cards: &[RecipeCard]) -> String {
+    cards
+        .iter()
+        .map(|card| {
+            let mut out = format!("# {}\n\n", card.title);
+            if let Some(url) = card.portable_image() {
+                out.push_str(&format!("![{}]({})\n\n", card.title, url));
+            }
+            if let Some(description) = &card.description {
+                out.push_str(&format!("{description}\n\n"));
+            }
+            for (label, value) in &card.details {
+                out.push_str(&format!("- {label}：{value}\n"));
+            }
+            if !card.details.is_empty() {
+                out.push('\n');
+            }
+            out.push_str("## 食材\n\n");
+            for item in &card.ingredients {
+                out.push_str(&format!("- {item}\n"));
+            }
+            out.push_str("\n## 做法\n\n");
+            for (index, step) in card.steps.iter().enumerate() {
+                out.push_str(&format!("{}. {step}\n", index + 1));
+            }
+            if !card.nutrition.is_empty() {
+                out.push_str("\n## 营养信息（每份）\n\n");
+                for (label, value) in &card.nutrition {
+                    out.push_str(&format!("- {label}：{value}\n"));
+                }
+            }
+            if !card.tags.is_empty() {
+                out.push_str(&format!("\n标签：{}\n", card.tags.join("、")));
+            }
+            out
+        })
+        .collect::<Vec<_>>()
+        .join("\n---\n\n")
+}
+
+pub(crate) fn render_plain_text(cards: &[RecipeCard]) -> String {
+    const RULE: &str = "========================================";
+    cards
+        .iter()
+        .map(|card| {
+            let mut out = format!("{RULE}\n{}\n{RULE}\n", card.title);
+            if let Some(description) = &card.description {
+                out.push_str(&format!("{description}\n"));
+            }
+            if !card.details.is_empty() {
+                let details: Vec<String> = card
+                    .details
+                    .iter()
+                    .map(|(label, value)| format!("{label}：{value}"))
+                    .collect();
+                out.push_str(&format!("{}\n", details.join(" | ")));
+            }
+            out.push_str("\n【食材】\n");
+            for item in &card.ingredients {
+                out.push_str(&format!("  · {item}\n"));
+            }
+            out.push_str("\n【做法】\n");
+            for (index, step) in card.steps.iter().enumerate() {
+                out.push_str(&format!("  {}. {step}\n", index + 1));
+            }
+            if !card.nutrition.is_empty() {
+                let nutrition: Vec<String> = card
+                    .nutrition
+                    .iter()
+                    .map(|(label, value)| format!("{label} {value}"))
+                    .collect();
+                out.push_str(&format!(
+                    "\n【营养信息（每份）】\n  {}\n",
+                    nutrition.join("，")
+                ));
+            }
+            if !card.tags.is_empty() {
+                out.push_str(&format!("\n标签：{}\n", card.tags.join("、")));
+            }
+            out
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn json_ld_node(card: &RecipeCard) -> serde_json::Value {
+    let recipe = &card.recipe;
+    let mut node = serde_json::json!({
+        "@type": "Recipe",
+        "name": card.title,
+        "recipeIngredient": card.ingredients,
+        "recipeInstructions": card
+            .steps
+            .iter()
+            .map(|step| serde_json::json!({ "@type": "HowToStep", "text": step }))
+            .collect::<Vec<_>>(),
+    });
+    let map = node.as_object_mut().expect("recipe node is an object");
+    let mut set = |key: &str, value: serde_json::Value| {
+        map.insert(key.to_string(), value);
+    };
+    if let Some(description) = &card.description {
+        set("description", description.clone().into());
+    }
+    if let Some(minutes) = recipe.cooking_time {
+        set("totalTime", format!("PT{minutes}M").into());
+    }
+    if let Some(servings) = recipe.servings {
+        set("recipeYield", servings.to_string().into());
+    }
+    if let Some(category) = &recipe.category {
+        set("recipeCategory", category.clone().into());
+    }
+    if !card.tags.is_empty() {
+        set("keywords", card.tags.join(", ").into());
+    }
+    let methods = json_list(recipe.cooking_methods.as_deref());
+    if !methods.is_empty() {
+        set("cookingMethod", methods.join(", ").into());
+    }
+    if let Some(url) = card.portable_image() {
+        set("image", url.into());
+    }
+    if let Some(facts) = recipe.nutrition() {
+        set(
+            "nutrition",
+            serde_json::json!({
+                "@type": "NutritionInformation",
+                "calories": format!("{} kcal", format_amount(facts.calories)),
+                "proteinContent": format!("{} g", format_amount(facts.protein)),
+                "carbohydrateContent": format!("{} g", format_amount(facts.carbs)),
+                "fatContent": format!("{} g", format_amount(facts.fat)),
+                "fiberContent": format!("{} g", format_amount(facts.fiber)),
+                "sodiumContent": format!("{} mg", format_amount(facts.sodium)),
+            }),
+        );
+    }
+    node
+}
+
+pub(crate) fn render_json_ld(cards: &[RecipeCard]) -> String {
+    let document = match cards {
+        [card] => {
+            let mut node = json_ld_node(card);
+            node.as_object_mut()
+                .expect("recipe node is an object")
+                .insert("@context".to_string(), "https://schema.org".into());
+            node
+        }
+        _ => serde_json::json!({
+            "@context": "https://schema.org",
+            "@graph": cards.iter().map(json_ld_node).collect::<Vec<_>>(),
+        }),
+    };
+    serde_json::to_string_pretty(&document).unwrap_or_default()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub(crate) fn image_mime(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+/// Reads an `image_url` that points at the image store. Anything else, including
+/// paths to files elsewhere on disk, yields `None`, as do missing and oversized files;
+/// an imported or synced recipe must not be able to pull arbitrary files into an export.
+pub(crate) fn read_local_image(images_dir: &Path, url: &str) -> Option<(&'static str, Vec<u8>)> {
+    if is_portable_image_url(url) {
+        return None;
+    }
+    let path = resolve_image_path(images_dir, url)?;
+    let mime = image_mime(&path)?;
+    let metadata = fs::metadata(&path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_EMBEDDED_IMAGE_BYTES {
+        return None;
+    }
+    fs::read(&path).ok().map(|bytes| (mime, bytes))
+}
+
+/// Inlines images from the image store as data URIs so the HTML stays self-contained.
+/// Data URIs, remote URLs and paths outside the store are kept as they are.
+fn embedded_image_source(images_dir: &Path, url: &str) -> String {
+    match read_local_image(images_dir, url) {
+        Some((mime, bytes)) => format!("data:{mime};base64,{}", STANDARD.encode(bytes)),
+        None => url.to_string(),
+    }
+}
+
+pub(crate) fn render_html(cards: &[RecipeCard], images_dir: &Path) -> String {
+    let document_title = match cards {
+        [card] => card.title.clone(),
+        _ => format!("我的收藏菜谱（{} 道）", cards.len()),
+    };
+    let articles: Vec<String> = cards
+        .iter()
+        .map(|card| {
+            let mut out = format!("<article>\n<h1>{}</h1>\n", escape_html(&card.title));
+            if let Some(url) = &card.image_url {
+                out.push_str(&format!(
+                    "<img src=\"{}\" alt=\"{}\">\n",
+                    escape_html(&embedded_image_source(images_dir, url)),
+                    escape_html(&card.title)
+                ));
+            }
+            if let Some(description) = &card.description {
+                out.push_str(&format!("<p>{}</p>\n", escape_html(description)));
+            }
+            if !card.details.is_empty() {
+                out.push_str("<ul class=\"details\">\n");
+                for (label, value) in &card.details {
+                    out.push_str(&format!(
+                        "<li><strong>{label}：</strong>{}</li>\n",
+                        escape_html(value)
+                    ));
+                }
+                out.push_str("</ul>\n");
+            }
+            out.push_str("<h2>食材</h2>\n<ul>\n");
+            for item in &card.ingredients {
+                out.push_str(&format!("<li>{}</li>\n", escape_html(item)));
+            }
+            out.push_str("</ul>\n<h2>做法</h2>\n<ol>\n");
+            for step in &card.steps {
+                out.push_str(&format!("<li>{}</li>\n", escape_html(step)));
+            }
+            out.push_str("</ol>\n");
+            if !card.nutrition.is_empty() {
+                out.push_str("<h2>营养信息（每份）</h2>\n<ul class=\"nutrition\">\n");
+                for (label, value) in &card.nutrition {
+                    out.push_str(&format!("<li>{label}：{}</li>\n", escape_html(value)));
+                }
+                out.push_str("</ul>\n");
+            }
+            if !card.tags.is_empty() {
+                out.push_str(&format!(
+                    "<p class=\"tags\">标签：{}</p>\n",
+                    escape_html(&card.tags.join("、"))
+                ));
+            }
+            out.push_str("</article>");
+            out
+        })
+        .collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>\n\
+         body {{ font-family: -apple-system, \"PingFang SC\", \"Microsoft YaHei\", sans-serif; max-width: 760px; margin: 2em auto; color: #222; line-height: 1.6; }}\n\
+         img {{ max-width: 100%; border-radius: 8px; }}\n\
+         ul.details, ul.nutrition {{ list-style: none; padding: 0; display: flex; flex-wrap: wrap; gap: 0.5em 1.5em; }}\n\
+         .tags {{ color: #666; }}\n\
+         article + article {{ border-top: 1px solid #ddd; margin-top: 2em; page-break-before: always; }}\n\
+         @media print {{ body {{ margin: 0; }} }}\n\
+         </style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        escape_html(&document_title),
+        articles.join("\n")
+    )
+}
+
+pub(crate) fn render(
+    format: RecipeExportFormat,
+    cards: &[RecipeCard],
+    images_dir: &Path,
+) -> String {
+    match format {
+        RecipeExportFormat::Markdown => render_markdown(cards),
+        RecipeExportFormat::JsonLd => render_json_ld(cards),
+        RecipeExportFormat::Html => render_html(cards, images_dir),
+        RecipeExportFormat::PlainText => render_plain_text(cards),
+    }
+}
+
+pub(crate) fn favorite_recipes(
+    conn: &Connection,
+    session_id: &str,
+) -> Result<Vec<StoredRecipe>, String> {
+    let columns = RECIPE_COLUMNS
+        .split(", ")
+        .map(|column| format!("r.{column}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {columns} FROM favorites f JOIN recipes r ON r.id = f.recipe_id
+             WHERE f.session_id = ?1 GROUP BY r.id ORDER BY MIN(f.created_at), r.id"
+        ))
+        .map_err(|e| format!("Failed to load favorites: {}", e))?;
+    let recipes = stmt
+        .query_map([session_id], StoredRecipe::from_row)
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load favorites: {}", e))?;
+    Ok(recipes)
+}
+
+pub(crate) fn validate_export_path(path: &str) -> Result<&Path, String> {
+    let path = Path::new(path);
+    if !path.is_absolute() || path.file_name().is_none() || path.is_dir() {
+        return Err("Export path must be an absolute file path".to_string());
+    }
+    match path.parent() {
+        Some(parent) if parent.is_dir() => Ok(path),
+        _ => Err("Export directory does not exist".to_string()),
+    }
+}
+
+/// Writes the given recipes, or every favorite of the active profile, to `path`.
+#[tauri::command]
+pub(crate) fn recipe_export(
+    path: String,
+    format: RecipeExportFormat,
+    recipe_ids: Option<Vec<i64>>,
+    favorites: Option<bool>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<RecipeExportResult, String> {
+    let target = validate_export_path(&path)?;
+    let conn = db.open_connection()?;
+    let recipes = match (recipe_ids, favorites.unwrap_or(false)) {
+        (Some(ids), false) if !ids.is_empty() && ids.len() <= MAX_EXPORT_RECIPES => ids
+            .into_iter()
+            .map(|id| require_recipe(&conn, id))
+            .collect::<Result<Vec<_>, _>>()?,
+        (None, true) => favorite_recipes(&conn, &profiles.session_id()?)?,
+        _ => return Err("Provide either recipe ids or favorites to export".to_string()),
+    };
+    if recipes.is_empty() {
+        return Err("There are no recipes to export".to_string());
+    }
+
+    let cards: Vec<RecipeCard> = recipes.into_iter().map(RecipeCard::from_recipe).collect();
+    fs::write(target, render(format, &cards, &db.images_dir()))
+        .map_err(|e| format!("Failed to write export file: {}", e))?;
+    Ok(RecipeExportResult {
+        path,
+        format,
+        recipe_count: cards.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importer::parse_json_ld;
+    use crate::initialize_schema;
+    use crate::recipe::{insert_recipe, RecipeDraft};
+
+    fn sample_recipe() -> StoredRecipe {
+        StoredRecipe {
+            id: 1,
+            title: "番茄<炒>蛋".to_string(),
+            description: Some("家常菜".to_string()),
+            ingredients: r#"["番茄 2个", {"name": "鸡蛋", "amount": 3, "unit": "个"}]"#.to_string(),
+            instructions: r#"["打蛋", "翻炒"]"#.to_string(),
+            cooking_time: Some(15),
+            difficulty: Some("简单".to_string()),
+            servings: Some(2),
+            category: Some("家常菜".to_string()),
+            tags: Some(r#"["快手", "下饭"]"#.to_string()),
+            nutrition_info: Some(
+                r#"{"calories": 180, "protein": "12g", "sodium": 420}"#.to_string(),
+            ),
+            image_url: None,
+            cooking_methods: Some(r#"["炒"]"#.to_string()),
+            ai_provider: None,
+            ai_model: None,
+        }
+    }
+
+    #[test]
+    fn renders_the_same_fields_in_every_format() {
+        let cards = vec![RecipeCard::from_recipe(sample_recipe())];
+
+        let markdown = render_markdown(&cards);
+        assert!(markdown.starts_with("# 番茄<炒>蛋\n"));
+        assert!(markdown.contains("- 鸡蛋 3个\n"));
+        assert!(markdown.contains("2. 翻炒\n"));
+        assert!(markdown.contains("- 钠：420 mg\n"));
+
+        let text = render_plain_text(&cards);
+        assert!(text.contains("烹饪时间：15 分钟 | 份量：2 人份"));
+        assert!(text.contains("  1. 打蛋\n"));
+
+        let html = render_html(&cards, Path::new("images"));
+        assert!(html.contains("<h1>番茄&lt;炒&gt;蛋</h1>"));
+        assert!(html.contains("<li>鸡蛋 3个</li>"));
+
+        let imported = parse_json_ld(&render_json_ld(&cards)).expect("round-trip JSON-LD");
+        assert_eq!(imported.recipe.title, "番茄<炒>蛋");
+        assert_eq!(imported.recipe.ingredients, vec!["番茄 2个", "鸡蛋 3个"]);
+        assert_eq!(imported.recipe.instructions, vec!["打蛋", "翻炒"]);
+        assert_eq!(imported.recipe.cooking_time, Some(15));
+        assert_eq!(imported.recipe.servings, Some(2));
+        assert_eq!(imported.recipe.tags, vec!["快手", "下饭"]);
+    }
+
+    #[test]
+    fn exports_favorites_as_one_document() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        for title in ["红烧肉", "清蒸鱼"] {
+            let recipe_id = insert_recipe(
+                &conn,
+                &RecipeDraft {
+                    title: title.to_string(),
+                    ingredients: vec!["主料".to_string()],
+                    instructions: vec!["烹饪".to_string()],
+                    ..RecipeDraft::default()
+                },
+            )
+            .expect("insert recipe");
+            conn.execute(
+                "INSERT INTO favorites (session_id, recipe_id) VALUES ('session-1', ?1)",
+                [recipe_id],
+            )
+            .expect("insert favorite");
+        }
+
+        let cards: Vec<RecipeCard> = favorite_recipes(&conn, "session-1")
+            .expect("load favorites")
+            .into_iter()
+            .map(RecipeCard::from_recipe)
+            .collect();
+        assert_eq!(cards.len(), 2);
+
+        let html = render_html(&cards, Path::new("images"));
+        assert_eq!(html.matches("<article>").count(), 2);
+        assert!(html.contains("<title>我的收藏菜谱（2 道）</title>"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_json_ld(&cards)).expect("parse JSON-LD");
+        assert_eq!(json["@graph"][1]["name"], "清蒸鱼");
+        assert!(favorite_recipes(&conn, "other").expect("load").is_empty());
+    }
+}
@@ -0,0 +1,721 @@
+//! Import adapters for other recipe managers: Paprika `.paprikarecipes` archives and
+//! Mealie / Tandoor JSON exports (loose files or their zip archives).
+
+use crate::images::{image_extension, store_image};
+use crate::importer::{
+    first_text, json_ld_nutrition, parse_minutes, split_tags, strip_list_marker,
+};
+use crate::recipe::{insert_recipe, nutrition_from_value, parse_quantity, RecipeDraft};
+use crate::DatabaseState;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use tauri::State;
+use zip::ZipArchive;
+
+const MAX_ARCHIVE_ENTRIES: usize = 10_000;
+const MAX_ENTRY_BYTES: u64 = 20 * 1024 * 1024;
+/// Exports with photos run to tens of megabytes, so this is far above the 5 MB cap
+/// on single-recipe files.
+const MAX_EXPORT_FILE_BYTES: u64 = 200 * 1024 * 1024;
+/// Everything extracted from one export, nested archives and gzip included.
+const MAX_EXTRACTED_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalSource {
+    Paprika,
+    Mealie,
+    Tandoor,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExternalImage {
+    pub mime: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExternalRecipe {
+    pub recipe: RecipeDraft,
+    pub image: Option<ExternalImage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExternalImportResult {
+    pub source: ExternalSource,
+    pub imported_recipe_ids: Vec<i64>,
+    /// Titles (or entry names) of recipes that lacked ingredients or steps.
+    pub skipped: Vec<String>,
+    pub image_count: usize,
+}
+
+fn text(value: &serde_json::Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(serde_json::Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+/// Reads `["a", "b"]` or `[{ "name": "a" }]` as a list of names.
+fn names(value: Option<&serde_json::Value>) -> Vec<String> {
+    match value {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                serde_json::Value::String(name) => Some(name.trim().to_string()),
+                other => text(other, "name"),
+            })
+            .filter(|name| !name.is_empty())
+            .collect(),
+        Some(serde_json::Value::String(list)) => split_tags(list),
+        _ => Vec::new(),
+    }
+}
+
+fn lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(strip_list_marker)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn image_from_bytes(name: &str, bytes: Vec<u8>) -> Option<ExternalImage> {
+    let mime = crate::exporter::image_mime(Path::new(name))?;
+    (!bytes.is_empty()).then_some(ExternalImage { mime, bytes })
+}
+
+fn servings_from(value: Option<&serde_json::Value>) -> Option<i64> {
+    let servings = match value? {
+        serde_json::Value::Number(number) => number.as_f64(),
+        other => first_text(other).and_then(|text| parse_quantity(&text).0),
+    }?;
+    (servings >= 1.0).then(|| servings.round() as i64)
+}
+
+fn minutes_from(value: Option<&serde_json::Value>) -> Option<i64> {
+    match value? {
+        serde_json::Value::Number(number) => number.as_i64().filter(|minutes| *minutes > 0),
+        serde_json::Value::String(text) => parse_minutes(text),
+        _ => None,
+    }
+}
+
+/// Paprika stores nutrition as free text such as "Calories: 250\nProtein: 12 g".
+fn nutrition_from_text(text: &str) -> Option<serde_json::Value> {
+    let mut nutrition = serde_json::Map::new();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once([':', '：']) else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let field = match key.as_str() {
+            k if k.contains("calor") || k.contains("热量") => "calories",
+            k if k.contains("protein") || k.contains("蛋白") => "protein",
+            k if k.contains("carb") || k.contains("碳水") => "carbs",
+            k if k.contains("fiber") || k.contains("纤维") => "fiber",
+            k if k.contains("sodium") || k.contains("钠") => "sodium",
+            k if k.contains("fat") || k.contains("脂肪") => "fat",
+            _ => continue,
+        };
+        if let Some(amount) = parse_quantity(value).0 {
+            nutrition.insert(field.to_string(), serde_json::Value::from(amount));
+        }
+    }
+    (!nutrition.is_empty()).then_some(serde_json::Value::Object(nutrition))
+}
+
+pub(crate) fn paprika_recipe(entry: &serde_json::Value) -> ExternalRecipe {
+    let categories = names(entry.get("categories"));
+    let mut description = text(entry, "description");
+    if let Some(notes) = text(entry, "notes") {
+        description = Some(match description {
+            Some(description) => format!("{description}\n\n{notes}"),
+            None => notes,
+        });
+    }
+    let image = text(entry, "photo_data")
+        .and_then(|data| STANDARD.decode(data.as_bytes()).ok())
+        .and_then(|bytes| image_from_bytes("photo.jpg", bytes));
+    let recipe = RecipeDraft {
+        title: text(entry, "name").unwrap_or_default(),
+        description,
+        ingredients: text(entry, "ingredients")
+            .map(|list| lines(&list))
+            .unwrap_or_default(),
+        instructions: text(entry, "directions")
+            .map(|list| lines(&list))
+            .unwrap_or_default(),
+        cooking_time: ["total_time", "cook_time", "prep_time"]
+            .iter()
+            .find_map(|key| minutes_from(entry.get(*key))),
+        difficulty: text(entry, "difficulty"),
+        servings: servings_from(entry.get("servings")),
+        category: categories.first().cloned(),
+        tags: categories,
+        nutrition_info: text(entry, "nutritional_info").and_then(|info| nutrition_from_text(&info)),
+        image_url: text(entry, "image_url").filter(|url| url.starts_with("http")),
+        ..RecipeDraft::default()
+    };
+    ExternalRecipe { recipe, image }
+}
+
+fn mealie_ingredient(value: &serde_json::Value) -> Option<String> {
+    if let Some(line) = value.as_str() {
+        return Some(line.trim().to_string()).filter(|line| !line.is_empty());
+    }
+    if let Some(display) = text(value, "display").or_else(|| text(value, "originalText")) {
+        return Some(display);
+    }
+    let food = value.get("food").and_then(|food| text(food, "name"));
+    let note = text(value, "note");
+    let name = food.clone().or_else(|| note.clone())?;
+    let quantity = value
+        .get("quantity")
+        .and_then(serde_json::Value::as_f64)
+        .filter(|quantity| *quantity > 0.0)
+        .map(|quantity| quantity.to_string())
+        .unwrap_or_default();
+    let unit = value
+        .get("unit")
+        .and_then(|unit| text(unit, "name"))
+        .unwrap_or_default();
+    let mut line = format!("{name} {quantity}{unit}").trim().to_string();
+    if let (Some(_), Some(note)) = (food, note) {
+        line = format!("{line}（{note}）");
+    }
+    Some(line)
+}
+
+pub(crate) fn mealie_recipe(value: &serde_json::Value) -> ExternalRecipe {
+    let categories = names(value.get("recipeCategory"));
+    let mut tags = names(value.get("tags"));
+    tags.extend(categories.iter().cloned());
+    let total_time = minutes_from(value.get("totalTime")).or_else(|| {
+        let parts = [
+            minutes_from(value.get("prepTime")),
+            minutes_from(value.get("performTime")).or_else(|| minutes_from(value.get("cookTime"))),
+        ];
+        parts
+            .iter()
+            .any(Option::is_some)
+            .then(|| parts.iter().flatten().sum())
+    });
+    let mut instructions = Vec::new();
+    if let Some(serde_json::Value::Array(steps)) = value.get("recipeInstructions") {
+        for step in steps {
+            let step_text = step
+                .as_str()
+                .map(str::to_string)
+                .or_else(|| text(step, "text"));
+            instructions.extend(step_text.map(|step| lines(&step)).unwrap_or_default());
+        }
+    }
+    let recipe = RecipeDraft {
+        title: text(value, "name").unwrap_or_default(),
+        description: text(value, "description"),
+        ingredients: match value.get("recipeIngredient") {
+            Some(serde_json::Value::Array(items)) => {
+                items.iter().filter_map(mealie_ingredient).collect()
+            }
+            _ => Vec::new(),
+        },
+        instructions,
+        cooking_time: total_time,
+        servings: servings_from(value.get("recipeServings"))
+            .or_else(|| servings_from(value.get("recipeYield"))),
+        category: categories.first().cloned(),
+        tags,
+        nutrition_info: value.get("nutrition").and_then(json_ld_nutrition),
+        image_url: text(value, "image").filter(|url| url.starts_with("http")),
+        ..RecipeDraft::default()
+    };
+    ExternalRecipe {
+        recipe,
+        image: None,
+    }
+}
+
+fn tandoor_ingredient(value: &serde_json::Value) -> Option<String> {
+    if value.get("is_header").and_then(serde_json::Value::as_bool) == Some(true) {
+        return None;
+    }
+    let name = value.get("food").and_then(|food| text(food, "name"))?;
+    let no_amount = value.get("no_amount").and_then(serde_json::Value::as_bool) == Some(true);
+    let amount = value
+        .get("amount")
+        .and_then(serde_json::Value::as_f64)
+        .filter(|amount| *amount > 0.0 && !no_amount)
+        .map(|amount| amount.to_string())
+        .unwrap_or_default();
+    let unit = if amount.is_empty() {
+        String::new()
+    } else {
+        value
+            .get("unit")
+            .and_then(|unit| text(unit, "name"))
+            .unwrap_or_default()
+    };
+    let line = format!("{name} {amount}{unit}").trim().to_string();
+    Some(match text(value, "note") {
+        Some(note) => format!("{line}（{note}）"),
+        None => line,
+    })
+}
+
+pub(crate) fn tandoor_recipe(value: &serde_json::Value) -> ExternalRecipe {
+    let mut ingredients = Vec::new();
+    let mut instructions = Vec::new();
+    if let Some(serde_json::Value::Array(steps)) = value.get("steps") {
+        for step in steps {
+            if let Some(serde_json::Value::Array(items)) = step.get("ingredients") {
+                ingredients.extend(items.iter().filter_map(tandoor_ingredient));
+            }
+            if let Some(instruction) = text(step, "instruction") {
+                instructions.extend(lines(&instruction));
+            }
+        }
+    }
+    let working = minutes_from(value.get("working_time")).unwrap_or(0);
+    let waiting = minutes_from(value.get("waiting_time")).unwrap_or(0);
+    let keywords = names(value.get("keywords"));
+    let recipe = RecipeDraft {
+        title: text(value, "name").unwrap_or_default(),
+        description: text(value, "description"),
+        ingredients,
+        instructions,
+        cooking_time: Some(working + waiting).filter(|minutes| *minutes > 0),
+        servings: servings_from(value.get("servings")),
+        category: keywords.first().cloned(),
+        tags: keywords,
+        // Tandoor uses plain `calories` / `proteins` / `carbohydrates` / `fats` keys.
+        nutrition_info: value.get("nutrition").and_then(|nutrition| {
+            let mapped = serde_json::json!({
+                "calories": nutrition.get("calories"),
+                "protein": nutrition.get("proteins"),
+                "carbs": nutrition.get("carbohydrates"),
+                "fat": nutrition.get("fats"),
+            });
+            nutrition_from_value(&mapped).map(|_| mapped)
+        }),
+        ..RecipeDraft::default()
+    };
+    ExternalRecipe {
+        recipe,
+        image: None,
+    }
+}
+
+/// Decompresses one entry, charging its size to `budget`, the bytes still allowed for
+/// the whole export.
+fn read_limited(reader: impl Read, name: &str, budget: &mut u64) -> Result<Vec<u8>, String> {
+    let limit = MAX_ENTRY_BYTES.min(*budget);
+    let mut bytes = Vec::new();
+    reader
+        .take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {name}: {}", e))?;
+    if bytes.len() as u64 > MAX_ENTRY_BYTES {
+        return Err(format!("Archive entry {name} is too large"));
+    }
+    if bytes.len() as u64 > limit {
+        return Err("Archive is too large once extracted".to_string());
+    }
+    *budget -= bytes.len() as u64;
+    Ok(bytes)
+}
+
+/// Loads every file of a zip archive into memory, keyed by entry name.
+fn archive_entries<R: Read + Seek>(
+    reader: R,
+    budget: &mut u64,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut archive =
+        ZipArchive::new(reader).map_err(|_| "File is not a valid archive".to_string())?;
+    if archive.len() > MAX_ARCHIVE_ENTRIES {
+        return Err("Archive contains too many entries".to_string());
+    }
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read archive: {}", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let bytes = read_limited(entry, &name, budget)?;
+        entries.push((name, bytes));
+    }
+    Ok(entries)
+}
+
+fn parse_json(bytes: &[u8], name: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_slice(bytes).map_err(|_| format!("{name} is not valid JSON"))
+}
+
+/// `.paprikarecipes` is a zip of `.paprikarecipe` entries, each a gzipped JSON object.
+pub(crate) fn read_paprika<R: Read + Seek>(reader: R) -> Result<Vec<ExternalRecipe>, String> {
+    let mut budget = MAX_EXTRACTED_BYTES;
+    archive_entries(reader, &mut budget)?
+        .into_iter()
+        .filter(|(name, _)| name.ends_with(".paprikarecipe"))
+        .map(|(name, bytes)| {
+            let json = read_limited(GzDecoder::new(bytes.as_slice()), &name, &mut budget)?;
+            Ok(paprika_recipe(&parse_json(&json, &name)?))
+        })
+        .collect()
+}
+
+fn json_recipes(value: serde_json::Value) -> Vec<serde_json::Value> {
+    match value {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(ref map) if map.contains_key("recipes") => {
+            match map.get("recipes") {
+                Some(serde_json::Value::Array(items)) => items.clone(),
+                _ => Vec::new(),
+            }
+        }
+        other => vec![other],
+    }
+}
+
+fn looks_like_tandoor(value: &serde_json::Value) -> bool {
+    value.get("steps").is_some() && value.get("recipeIngredient").is_none()
+}
+
+/// Mealie exports hold `recipes/<slug>/<slug>.json` with the photo under
+/// `recipes/<slug>/images/`.
+pub(crate) fn read_mealie_archive<R: Read + Seek>(
+    reader: R,
+) -> Result<Vec<ExternalRecipe>, String> {
+    let mut budget = MAX_EXTRACTED_BYTES;
+    let entries = archive_entries(reader, &mut budget)?;
+    let mut recipes = Vec::new();
+    for (name, bytes) in entries.iter().filter(|(name, _)| name.ends_with(".json")) {
+        let folder = name.rsplit_once('/').map_or("", |(folder, _)| folder);
+        let image = entries
+            .iter()
+            .filter(|(other, _)| {
+                other.starts_with(&format!("{folder}/images/"))
+                    && crate::exporter::image_mime(Path::new(other)).is_some()
+            })
+            .min_by_key(|(other, _)| {
+                !other
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or("")
+                    .starts_with("original")
+            })
+            .and_then(|(other, bytes)| image_from_bytes(other, bytes.clone()));
+        for value in json_recipes(parse_json(bytes, name)?) {
+            let mut parsed = mealie_recipe(&value);
+            parsed.image = image.clone();
+            recipes.push(parsed);
+        }
+    }
+    Ok(recipes)
+}
+
+/// Tandoor exports a zip of per-recipe zips, each with `recipe.json` and an optional image.
+pub(crate) fn read_tandoor_archive<R: Read + Seek>(
+    reader: R,
+) -> Result<Vec<ExternalRecipe>, String> {
+    let mut budget = MAX_EXTRACTED_BYTES;
+    let mut recipes = Vec::new();
+    for (name, bytes) in archive_entries(reader, &mut budget)? {
+        if name.ends_with(".zip") {
+            let inner = archive_entries(Cursor::new(bytes), &mut budget)?;
+            let image = inner
+                .iter()
+                .find(|(entry, _)| entry.starts_with("image"))
+                .and_then(|(entry, bytes)| image_from_bytes(entry, bytes.clone()));
+            if let Some((entry, json)) = inner.iter().find(|(entry, _)| entry.ends_with(".json")) {
+                let mut parsed = tandoor_recipe(&parse_json(json, entry)?);
+                parsed.image = image;
+                recipes.push(parsed);
+            }
+        } else if name.ends_with(".json") {
+            recipes.extend(
+                json_recipes(parse_json(&bytes, &name)?)
+                    .iter()
+                    .map(tandoor_recipe),
+            );
+        }
+    }
+    Ok(recipes)
+}
+
+fn detect_archive_source(entries: &[String]) -> Option<ExternalSource> {
+    if entries.iter().any(|name| name.ends_with(".paprikarecipe")) {
+        Some(ExternalSource::Paprika)
+    } else if entries.iter().any(|name| name.ends_with(".zip")) {
+        Some(ExternalSource::Tandoor)
+    } else if entries.iter().any(|name| name.ends_with(".json")) {
+        Some(ExternalSource::Mealie)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn read_external_file(
+    bytes: Vec<u8>,
+    source: Option<ExternalSource>,
+) -> Result<(ExternalSource, Vec<ExternalRecipe>), String> {
+    let is_zip = bytes.starts_with(b"PK");
+    let source = match source {
+        Some(source) => source,
+        None if is_zip => {
+            let archive = ZipArchive::new(Cursor::new(bytes.as_slice()))
+                .map_err(|_| "File is not a valid archive".to_string())?;
+            let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+            detect_archive_source(&names)
+                .ok_or_else(|| "Archive does not contain a supported recipe export".to_string())?
+        }
+        None => {
+            let value = parse_json(&bytes, "Import file")?;
+            if json_recipes(value).first().is_some_and(looks_like_tandoor) {
+                ExternalSource::Tandoor
+            } else {
+                ExternalSource::Mealie
+            }
+        }
+    };
+
+    let recipes = match (source, is_zip) {
+        (ExternalSource::Paprika, true) => read_paprika(Cursor::new(bytes))?,
+        (ExternalSource::Paprika, false) => {
+            return Err("Paprika exports must be .paprikarecipes archives".to_string())
+        }
+        (ExternalSource::Mealie, true) => read_mealie_archive(Cursor::new(bytes))?,
+        (ExternalSource::Tandoor, true) => read_tandoor_archive(Cursor::new(bytes))?,
+        (ExternalSource::Mealie, false) => json_recipes(parse_json(&bytes, "Import file")?)
+            .iter()
+            .map(mealie_recipe)
+            .collect(),
+        (ExternalSource::Tandoor, false) => json_recipes(parse_json(&bytes, "Import file")?)
+            .iter()
+            .map(tandoor_recipe)
+            .collect(),
+    };
+    Ok((source, recipes))
+}
+
+/// Imports a Paprika, Mealie or Tandoor export. The source is detected from the file
+/// contents unless given explicitly.
+#[tauri::command]
+pub(crate) fn recipe_import_external(
+    path: String,
+    source: Option<ExternalSource>,
+    db: State<DatabaseState>,
+) -> Result<ExternalImportResult, String> {
+    let metadata = fs::metadata(&path).map_err(|e| format!("Failed to read import file: {}", e))?;
+    if !metadata.is_file() || metadata.len() > MAX_EXPORT_FILE_BYTES {
+        return Err("Import file must be a regular file no larger than 200 MB".to_string());
+    }
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read import file: {}", e))?;
+    let (source, recipes) = read_external_file(bytes, source)?;
+    let images_dir = db.images_dir();
+
+    let mut conn = db.open_connection()?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut imported_recipe_ids = Vec::new();
+    let mut skipped = Vec::new();
+    let mut image_count = 0;
+    for ExternalRecipe { mut recipe, image } in recipes {
+        if recipe.validate().is_err() {
+            skipped.push(recipe.title);
+            continue;
+        }
+        if let Some(image) = image {
+            recipe.image_url = Some(store_image(
+                &images_dir,
+                &image.bytes,
+                image_extension(image.mime),
+            )?);
+            image_count += 1;
+        }
+        imported_recipe_ids.push(
+            insert_recipe(&tx, &recipe).map_err(|e| format!("Failed to save recipe: {}", e))?,
+        );
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to import recipes: {}", e))?;
+
+    Ok(ExternalImportResult {
+        source,
+        imported_recipe_ids,
+        skipped,
+        image_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    fn zip_fixture(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, bytes) in entries {
+            zip.start_file(*name, SimpleFileOptions::default())
+                .expect("start fixture entry");
+            zip.write_all(bytes).expect("write fixture entry");
+        }
+        zip.finish().expect("finish fixture").into_inner()
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).expect("gzip fixture");
+        encoder.finish().expect("finish gzip")
+    }
+
+    #[test]
+    fn reads_a_paprika_archive() {
+        let entry = serde_json::json!({
+            "name": "Mapo Tofu",
+            "ingredients": "1 block tofu\n200g minced pork\n\n2 tbsp doubanjiang\n1.5 cups stock",
+            "directions": "1. Blanch the tofu.\n2. Fry the pork with doubanjiang.\n\n3. Simmer.",
+            "servings": "4 servings",
+            "total_time": "1 hr 5 mins",
+            "categories": ["Sichuan", "Dinner"],
+            "nutritional_info": "Calories: 320\nProtein: 18 g\nSodium: 900mg",
+            "notes": "Use silken tofu.",
+            "photo_data": STANDARD.encode(b"jpeg bytes"),
+        });
+        let archive = zip_fixture(&[(
+            "Mapo Tofu.paprikarecipe",
+            gzip(entry.to_string().as_bytes()),
+        )]);
+
+        let (source, recipes) = read_external_file(archive, None).expect("read paprika");
+        assert_eq!(source, ExternalSource::Paprika);
+        let recipe = &recipes[0].recipe;
+        assert_eq!(recipe.title, "Mapo Tofu");
+        assert_eq!(recipe.ingredients.len(), 4);
+        assert_eq!(recipe.ingredients[3], "1.5 cups stock");
+        assert_eq!(recipe.instructions[1], "Fry the pork with doubanjiang.");
+        assert_eq!((recipe.servings, recipe.cooking_time), (Some(4), Some(65)));
+        assert_eq!(recipe.category.as_deref(), Some("Sichuan"));
+        assert_eq!(recipe.description.as_deref(), Some("Use silken tofu."));
+        assert_eq!(recipe.nutrition().map(|facts| facts.sodium), Some(900.0));
+        assert_eq!(
+            recipes[0]
+                .image
+                .as_ref()
+                .map(|image| image.bytes.as_slice()),
+            Some(b"jpeg bytes".as_slice())
+        );
+    }
+
+    #[test]
+    fn caps_the_total_bytes_extracted_from_an_export() {
+        let archive = zip_fixture(&[
+            ("recipes/a/a.json", vec![b' '; 600]),
+            ("recipes/b/b.json", vec![b' '; 600]),
+        ]);
+        let mut budget = 1_000;
+        assert_eq!(
+            archive_entries(Cursor::new(archive.as_slice()), &mut budget).unwrap_err(),
+            "Archive is too large once extracted"
+        );
+        let mut budget = 1_200;
+        assert_eq!(
+            archive_entries(Cursor::new(archive), &mut budget)
+                .expect("within budget")
+                .len(),
+            2
+        );
+        assert_eq!(budget, 0);
+    }
+
+    #[test]
+    fn reads_mealie_and_tandoor_exports() {
+        let mealie = serde_json::json!({
+            "name": "Shakshuka",
+            "recipeYield": "2 servings",
+            "totalTime": "40 minutes",
+            "recipeCategory": [{"name": "Breakfast", "slug": "breakfast"}],
+            "tags": [{"name": "Vegetarian"}],
+            "recipeIngredient": [
+                {"quantity": 4.0, "unit": {"name": "个"}, "food": {"name": "鸡蛋"}, "note": "", "display": ""},
+                {"note": "1 can tomatoes", "display": "1 can tomatoes"}
+            ],
+            "recipeInstructions": [{"title": "", "text": "Simmer the sauce."}, {"text": "Poach the eggs."}],
+            "nutrition": {"calories": "310", "proteinContent": "19"}
+        });
+        let archive = zip_fixture(&[
+            (
+                "recipes/shakshuka/shakshuka.json",
+                mealie.to_string().into_bytes(),
+            ),
+            (
+                "recipes/shakshuka/images/original.webp",
+                b"webp bytes".to_vec(),
+            ),
+            (
+                "recipes/shakshuka/images/min-original.webp",
+                b"small".to_vec(),
+            ),
+        ]);
+        let (source, recipes) = read_external_file(archive, None).expect("read mealie");
+        assert_eq!(source, ExternalSource::Mealie);
+        let recipe = &recipes[0].recipe;
+        assert_eq!(recipe.ingredients, vec!["鸡蛋 4个", "1 can tomatoes"]);
+        assert_eq!(
+            recipe.instructions,
+            vec!["Simmer the sauce.", "Poach the eggs."]
+        );
+        assert_eq!((recipe.servings, recipe.cooking_time), (Some(2), Some(40)));
+        assert_eq!(recipe.tags, vec!["Vegetarian", "Breakfast"]);
+        assert_eq!(
+            recipes[0].image.as_ref().map(|image| image.mime),
+            Some("image/webp")
+        );
+
+        let tandoor = serde_json::json!({
+            "name": "红烧肉",
+            "servings": 4,
+            "working_time": 20,
+            "waiting_time": 60,
+            "keywords": [{"name": "家常菜"}],
+            "steps": [
+                {"instruction": "五花肉焯水", "ingredients": [
+                    {"food": {"name": "五花肉"}, "unit": {"name": "g"}, "amount": 500, "note": "切块"},
+                    {"food": {"name": "配料"}, "is_header": true, "amount": 0}
+                ]},
+                {"instruction": "炒糖色后炖煮", "ingredients": [
+                    {"food": {"name": "冰糖"}, "unit": null, "amount": 0, "no_amount": true}
+                ]}
+            ]
+        });
+        let inner = zip_fixture(&[
+            ("recipe.json", tandoor.to_string().into_bytes()),
+            ("image.png", b"png bytes".to_vec()),
+        ]);
+        let archive = zip_fixture(&[("1.zip", inner)]);
+        let (source, recipes) = read_external_file(archive, None).expect("read tandoor");
+        assert_eq!(source, ExternalSource::Tandoor);
+        let recipe = &recipes[0].recipe;
+        assert_eq!(recipe.ingredients, vec!["五花肉 500g（切块）", "冰糖"]);
+        assert_eq!(recipe.instructions, vec!["五花肉焯水", "炒糖色后炖煮"]);
+        assert_eq!((recipe.servings, recipe.cooking_time), (Some(4), Some(80)));
+        assert_eq!(
+            recipes[0].image.as_ref().map(|image| image.mime),
+            Some("image/png")
+        );
+    }
+}
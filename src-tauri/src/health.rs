@@ -0,0 +1,444 @@
+use crate::profiles::ProfileState;
+use crate::DatabaseState;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+// Bump when the stored profile shape changes so older rows can be migrated on read.
+// Version 1 used snake_case field names; the aliases below still read those rows.
+pub(crate) const HEALTH_PROFILE_VERSION: u32 = 2;
+const HEALTH_PROFILE_KEY: &str = "health_profile";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Gender {
+    Male,
+    Female,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivityLevel {
+    Sedentary,
+    Light,
+    Moderate,
+    Active,
+    VeryActive,
+}
+
+impl ActivityLevel {
+    fn multiplier(self) -> f64 {
+        match self {
+            ActivityLevel::Sedentary => 1.2,
+            ActivityLevel::Light => 1.375,
+            ActivityLevel::Moderate => 1.55,
+            ActivityLevel::Active => 1.725,
+            ActivityLevel::VeryActive => 1.9,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthGoal {
+    #[serde(alias = "lose_weight")]
+    LoseWeight,
+    Maintain,
+    #[serde(alias = "gain_weight")]
+    GainWeight,
+    #[serde(alias = "gain_muscle")]
+    GainMuscle,
+}
+
+impl HealthGoal {
+    const ALL: [HealthGoal; 4] = [
+        HealthGoal::LoseWeight,
+        HealthGoal::Maintain,
+        HealthGoal::GainWeight,
+        HealthGoal::GainMuscle,
+    ];
+
+    // Maps the free-form goals used by the profile form onto a calorie strategy.
+    fn from_label(label: &str) -> Option<Self> {
+        match label.trim() {
+            "减重" | "减脂" | "loseWeight" | "lose_weight" => Some(HealthGoal::LoseWeight),
+            "增重" | "gainWeight" | "gain_weight" => Some(HealthGoal::GainWeight),
+            "增肌" | "gainMuscle" | "gain_muscle" => Some(HealthGoal::GainMuscle),
+            "maintain" => Some(HealthGoal::Maintain),
+            _ => None,
+        }
+    }
+
+    // (calorie adjustment in kcal, protein g per kg body weight, share of calories from fat)
+    fn strategy(self) -> (f64, f64, f64) {
+        match self {
+            HealthGoal::LoseWeight => (-500.0, 1.8, 0.25),
+            HealthGoal::Maintain => (0.0, 1.2, 0.30),
+            HealthGoal::GainWeight => (300.0, 1.4, 0.30),
+            HealthGoal::GainMuscle => (250.0, 2.0, 0.25),
+        }
+    }
+}
+
+/// Mirrors the frontend `UserProfile`; its meal log is not stored here.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthProfile {
+    pub name: String,
+    pub age: u32,
+    pub gender: Gender,
+    #[serde(rename = "height", alias = "height_cm")]
+    pub height_cm: f64,
+    #[serde(rename = "weight", alias = "weight_kg")]
+    pub weight_kg: f64,
+    #[serde(alias = "activity_level")]
+    pub activity_level: ActivityLevel,
+    #[serde(default, alias = "health_goals")]
+    pub health_goals: Vec<String>,
+    #[serde(default, alias = "medical_conditions")]
+    pub medical_conditions: Vec<String>,
+    #[serde(default)]
+    pub allergies: Vec<String>,
+    #[serde(default, alias = "dietary_restrictions")]
+    pub dietary_restrictions: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredHealthProfile {
+    version: u32,
+    profile: HealthProfile,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroTarget {
+    pub goal: HealthGoal,
+    pub calories: u32,
+    pub protein_g: u32,
+    pub carbs_g: u32,
+    pub fat_g: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthMetrics {
+    pub bmi: f64,
+    pub bmr: u32,
+    pub tdee: u32,
+    pub primary_goal: HealthGoal,
+    pub macro_targets: Vec<MacroTarget>,
+}
+
+fn validate_profile(profile: &HealthProfile) -> Result<(), String> {
+    if !(10..=120).contains(&profile.age) {
+        return Err("Age must be between 10 and 120".to_string());
+    }
+    if !(50.0..=260.0).contains(&profile.height_cm) {
+        return Err("Height must be between 50 and 260 cm".to_string());
+    }
+    if !(20.0..=400.0).contains(&profile.weight_kg) {
+        return Err("Weight must be between 20 and 400 kg".to_string());
+    }
+    if profile.name.len() > 128 {
+        return Err("Profile name is too long".to_string());
+    }
+    Ok(())
+}
+
+// Mifflin-St Jeor equation.
+fn basal_metabolic_rate(profile: &HealthProfile) -> f64 {
+    let base = 10.0 * profile.weight_kg + 6.25 * profile.height_cm - 5.0 * profile.age as f64;
+    match profile.gender {
+        Gender::Male => base + 5.0,
+        Gender::Female => base - 161.0,
+    }
+}
+
+fn macro_target(goal: HealthGoal, tdee: f64, bmr: f64, weight_kg: f64) -> MacroTarget {
+    let (adjustment, protein_per_kg, fat_share) = goal.strategy();
+    // Never plan a deficit below the resting requirement.
+    let calories = (tdee + adjustment).max(bmr);
+    let protein_g = protein_per_kg * weight_kg;
+    let fat_g = calories * fat_share / 9.0;
+    let carbs_g = ((calories - protein_g * 4.0 - fat_g * 9.0) / 4.0).max(0.0);
+
+    MacroTarget {
+        goal,
+        calories: calories.round() as u32,
+        protein_g: protein_g.round() as u32,
+        carbs_g: carbs_g.round() as u32,
+        fat_g: fat_g.round() as u32,
+    }
+}
+
+pub(crate) fn calculate_health_metrics(profile: &HealthProfile) -> HealthMetrics {
+    let height_m = profile.height_cm / 100.0;
+    let bmi = profile.weight_kg / (height_m * height_m);
+    let bmr = basal_metabolic_rate(profile);
+    let tdee = bmr * profile.activity_level.multiplier();
+    let primary_goal = profile
+        .health_goals
+        .iter()
+        .find_map(|label| HealthGoal::from_label(label))
+        .unwrap_or(HealthGoal::Maintain);
+
+    HealthMetrics {
+        bmi: (bmi * 10.0).round() / 10.0,
+        bmr: bmr.round() as u32,
+        tdee: tdee.round() as u32,
+        primary_goal,
+        macro_targets: HealthGoal::ALL
+            .iter()
+            .map(|goal| macro_target(*goal, tdee, bmr, profile.weight_kg))
+            .collect(),
+    }
+}
+
+fn read_preferences(
+    conn: &Connection,
+    session_id: &str,
+) -> rusqlite::Result<serde_json::Map<String, serde_json::Value>> {
+    let preferences: Option<Option<String>> = conn
+        .query_row(
+            "SELECT preferences FROM users WHERE session_id = ?1",
+            [session_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(preferences
+        .flatten()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|value| match value {
+            serde_json::Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .unwrap_or_default())
+}
+
+/// Wraps a profile with the current version, as stored in preferences and bundles.
+pub(crate) fn versioned_profile(profile: HealthProfile) -> Result<serde_json::Value, String> {
+    validate_profile(&profile)?;
+    serde_json::to_value(StoredHealthProfile {
+        version: HEALTH_PROFILE_VERSION,
+        profile,
+    })
+    .map_err(|_| "Unable to prepare the health profile".to_string())
+}
+
+pub(crate) fn parse_versioned_profile(stored: serde_json::Value) -> Result<HealthProfile, String> {
+    let stored = serde_json::from_value::<StoredHealthProfile>(stored)
+        .map_err(|_| "The stored health profile is invalid".to_string())?;
+    if stored.version > HEALTH_PROFILE_VERSION {
+        return Err(
+            "The stored health profile was saved by a newer version of ChefMind".to_string(),
+        );
+    }
+    Ok(stored.profile)
+}
+
+pub(crate) fn load_health_profile(
+    conn: &Connection,
+    session_id: &str,
+) -> Result<Option<HealthProfile>, String> {
+    let preferences = read_preferences(conn, session_id)
+        .map_err(|e| format!("Failed to read user preferences: {}", e))?;
+    preferences
+        .get(HEALTH_PROFILE_KEY)
+        .map(|stored| parse_versioned_profile(stored.clone()))
+        .transpose()
+}
+
+pub(crate) fn save_health_profile(
+    conn: &Connection,
+    session_id: &str,
+    profile: HealthProfile,
+) -> Result<(), String> {
+    let stored = versioned_profile(profile)?;
+    let mut preferences = read_preferences(conn, session_id)
+        .map_err(|e| format!("Failed to read user preferences: {}", e))?;
+    preferences.insert(HEALTH_PROFILE_KEY.to_string(), stored);
+    let serialized = serde_json::Value::Object(preferences).to_string();
+
+    conn.execute(
+        "INSERT INTO users (session_id, preferences) VALUES (?1, ?2)
+         ON CONFLICT(session_id) DO UPDATE SET preferences = excluded.preferences, updated_at = CURRENT_TIMESTAMP",
+        params![session_id, serialized],
+    )
+    .map_err(|e| format!("Failed to save health profile: {}", e))?;
+    Ok(())
+}
+
+pub(crate) fn validate_session_id(session_id: &str) -> Result<(), String> {
+    if session_id.trim().is_empty() || session_id.len() > 128 {
+        Err("Session id is invalid".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub(crate) fn health_profile_save(
+    profile: HealthProfile,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<(), String> {
+    let session_id = profiles.session_id()?;
+    let conn = db.open_connection()?;
+    save_health_profile(&conn, &session_id, profile)
+}
+
+#[tauri::command]
+pub(crate) fn health_profile_get(
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Option<HealthProfile>, String> {
+    let session_id = profiles.session_id()?;
+    let conn = db.open_connection()?;
+    load_health_profile(&conn, &session_id)
+}
+
+#[tauri::command]
+pub(crate) fn health_metrics(
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<HealthMetrics, String> {
+    let session_id = profiles.session_id()?;
+    let conn = db.open_connection()?;
+    let profile = load_health_profile(&conn, &session_id)?
+        .ok_or_else(|| "No health profile is stored for this user".to_string())?;
+    Ok(calculate_health_metrics(&profile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+
+    fn sample_profile() -> HealthProfile {
+        HealthProfile {
+            name: "测试".to_string(),
+            age: 30,
+            gender: Gender::Male,
+            height_cm: 180.0,
+            weight_kg: 80.0,
+            activity_level: ActivityLevel::Moderate,
+            health_goals: vec!["增肌".to_string()],
+            medical_conditions: Vec::new(),
+            allergies: vec!["花生".to_string()],
+            dietary_restrictions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn computes_mifflin_st_jeor_metrics_and_goal_targets() {
+        let metrics = calculate_health_metrics(&sample_profile());
+
+        // 10 * 80 + 6.25 * 180 - 5 * 30 + 5
+        assert_eq!(metrics.bmr, 1780);
+        assert_eq!(metrics.tdee, 2759);
+        assert_eq!(metrics.bmi, 24.7);
+        assert_eq!(metrics.primary_goal, HealthGoal::GainMuscle);
+        assert_eq!(metrics.macro_targets.len(), 4);
+
+        let cut = &metrics.macro_targets[0];
+        assert_eq!(cut.goal, HealthGoal::LoseWeight);
+        assert_eq!(cut.calories, 2259);
+        assert_eq!(cut.protein_g, 144);
+        let bulk = &metrics.macro_targets[3];
+        assert_eq!(bulk.protein_g, 160);
+        assert!(bulk.calories > cut.calories);
+    }
+
+    #[test]
+    fn stores_versioned_profile_without_dropping_other_preferences() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        conn.execute(
+            "INSERT INTO users (session_id, preferences) VALUES ('s1', '{\"theme\":\"dark\"}')",
+            [],
+        )
+        .expect("insert user");
+
+        save_health_profile(&conn, "s1", sample_profile()).expect("save profile");
+
+        let raw: String = conn
+            .query_row(
+                "SELECT preferences FROM users WHERE session_id = 's1'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("read preferences");
+        let preferences: serde_json::Value = serde_json::from_str(&raw).expect("parse");
+        assert_eq!(preferences["theme"], "dark");
+        assert_eq!(
+            preferences["health_profile"]["version"],
+            HEALTH_PROFILE_VERSION
+        );
+        assert_eq!(
+            load_health_profile(&conn, "s1").expect("load profile"),
+            Some(sample_profile())
+        );
+        assert_eq!(load_health_profile(&conn, "missing").expect("load"), None);
+    }
+
+    #[test]
+    fn rejects_out_of_range_profiles_and_newer_versions() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+
+        let mut profile = sample_profile();
+        profile.weight_kg = 5.0;
+        assert!(save_health_profile(&conn, "s1", profile).is_err());
+
+        conn.execute(
+            "INSERT INTO users (session_id, preferences) VALUES ('s2', ?1)",
+            [serde_json::json!({
+                "health_profile": { "version": HEALTH_PROFILE_VERSION + 1, "profile": sample_profile() }
+            })
+            .to_string()],
+        )
+        .expect("insert user");
+        assert!(load_health_profile(&conn, "s2").is_err());
+    }
+
+    #[test]
+    fn reads_frontend_profiles_and_version_one_rows() {
+        let frontend = serde_json::json!({
+            "name": "测试",
+            "age": 30,
+            "gender": "male",
+            "height": 180,
+            "weight": 80,
+            "activityLevel": "veryActive",
+            "healthGoals": ["loseWeight"],
+            "meals": [{ "type": "breakfast", "description": "粥" }]
+        });
+        let profile: HealthProfile = serde_json::from_value(frontend).expect("frontend profile");
+        assert_eq!(profile.activity_level, ActivityLevel::VeryActive);
+        assert_eq!(
+            calculate_health_metrics(&profile).primary_goal,
+            HealthGoal::LoseWeight
+        );
+        let serialized = serde_json::to_value(&profile).expect("serialize");
+        assert_eq!(serialized["height"], 180.0);
+        assert_eq!(serialized["activityLevel"], "veryActive");
+
+        let version_one = serde_json::json!({
+            "version": 1,
+            "profile": {
+                "name": "旧",
+                "age": 40,
+                "gender": "female",
+                "height_cm": 165.0,
+                "weight_kg": 60.0,
+                "activity_level": "light",
+                "health_goals": ["maintain"],
+                "dietary_restrictions": ["素食"]
+            }
+        });
+        let migrated = parse_versioned_profile(version_one).expect("version 1 profile");
+        assert_eq!(migrated.height_cm, 165.0);
+        assert_eq!(migrated.dietary_restrictions, vec!["素食".to_string()]);
+    }
+}
@@ -0,0 +1,548 @@
+//! Content-addressed image store under `app_data_dir/images`. Files are named by the
+//! SHA-256 of their bytes, thumbnails live in `images/thumbs`, and the WebView loads
+//! both through the `chefmind-image` URI scheme.
+
+use crate::DatabaseState;
+use image::{codecs::jpeg::JpegEncoder, ImageFormat};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::ipc::InvokeBody;
+use tauri::State;
+
+pub(crate) const IMAGE_SCHEME: &str = "chefmind-image";
+const THUMBNAIL_DIR: &str = "thumbs";
+const THUMBNAIL_SIZE: u32 = 320;
+pub(crate) const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+// Files younger than this are never collected, so an attach that has written its file
+// but not yet committed the recipe update cannot lose it. Storing an existing file
+// again refreshes its age for the same reason.
+pub(crate) const GC_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StoredImage {
+    pub url: String,
+    pub thumbnail_url: Option<String>,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImageCollectionResult {
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Remote URLs and data URIs work on any install; store URLs and file paths do not.
+pub(crate) fn is_portable_image_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://") || url.starts_with("data:image/")
+}
+
+pub(crate) fn is_image_name(name: &str) -> bool {
+    let Some((stem, extension)) = name.split_once('.') else {
+        return false;
+    };
+    stem.len() == 64
+        && stem
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+        && ["png", "jpg", "gif", "webp", "svg"].contains(&extension)
+}
+
+pub(crate) fn image_extension(mime: &str) -> &'static str {
+    match mime {
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        _ => "jpg",
+    }
+}
+
+fn mime_for_extension(extension: &str) -> &'static str {
+    match extension {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => "image/jpeg",
+    }
+}
+
+/// Windows and Android WebViews reach custom schemes through `http://<scheme>.localhost`.
+pub(crate) fn image_url(path: &str) -> String {
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{IMAGE_SCHEME}.localhost/{path}")
+    } else {
+        format!("{IMAGE_SCHEME}://localhost/{path}")
+    }
+}
+
+/// Extracts the stored file name from either platform's form of a store URL.
+pub(crate) fn image_name_from_url(url: &str) -> Option<&str> {
+    let name = [
+        format!("{IMAGE_SCHEME}://localhost/"),
+        format!("http://{IMAGE_SCHEME}.localhost/"),
+        format!("https://{IMAGE_SCHEME}.localhost/"),
+    ]
+    .iter()
+    .find_map(|prefix| url.strip_prefix(prefix.as_str()))?;
+    is_image_name(name).then_some(name)
+}
+
+fn thumbnail_name(name: &str) -> String {
+    let stem = name.split_once('.').map_or(name, |(stem, _)| stem);
+    format!("{THUMBNAIL_DIR}/{stem}.jpg")
+}
+
+pub(crate) fn thumbnail_url(url: &str) -> Option<String> {
+    let name = image_name_from_url(url)?;
+    (!name.ends_with(".svg")).then(|| image_url(&thumbnail_name(name)))
+}
+
+pub(crate) fn resolve_image_path(images_dir: &Path, url: &str) -> Option<PathBuf> {
+    image_name_from_url(url).map(|name| images_dir.join(name))
+}
+
+fn write_thumbnail(images_dir: &Path, name: &str, bytes: &[u8]) -> Result<(u32, u32), String> {
+    let decoded =
+        image::load_from_memory(bytes).map_err(|_| "Image could not be decoded".to_string())?;
+    let (width, height) = (decoded.width(), decoded.height());
+    let target = images_dir.join(thumbnail_name(name));
+    if !target.exists() {
+        let thumbnail = decoded.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8();
+        let mut encoded = Vec::new();
+        JpegEncoder::new_with_quality(&mut encoded, 80)
+            .encode_image(&thumbnail)
+            .map_err(|e| format!("Failed to create thumbnail: {}", e))?;
+        fs::create_dir_all(images_dir.join(THUMBNAIL_DIR))
+            .map_err(|e| format!("Failed to create thumbnail: {}", e))?;
+        fs::write(&target, encoded).map_err(|e| format!("Failed to create thumbnail: {}", e))?;
+    }
+    Ok((width, height))
+}
+
+/// Saves image bytes under their SHA-256, so identical images are stored once, and
+/// returns the store URL. A thumbnail is generated for every raster format.
+pub(crate) fn store_image(
+    images_dir: &Path,
+    bytes: &[u8],
+    extension: &str,
+) -> Result<String, String> {
+    let name = format!("{}.{extension}", sha256_hex(bytes));
+    let target = images_dir.join(&name);
+    if target.exists() {
+        fs::File::options()
+            .write(true)
+            .open(&target)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+            .map_err(|e| format!("Failed to save image: {}", e))?;
+    } else {
+        fs::create_dir_all(images_dir).map_err(|e| format!("Failed to save image: {}", e))?;
+        fs::write(&target, bytes).map_err(|e| format!("Failed to save image: {}", e))?;
+    }
+    if extension != "svg" {
+        // A missing thumbnail only costs the UI a larger download, so it is not fatal.
+        if let Err(e) = write_thumbnail(images_dir, &name, bytes) {
+            eprintln!("Failed to create thumbnail for {}: {}", name, e);
+        }
+    }
+    Ok(image_url(&name))
+}
+
+fn referenced_names(conn: &Connection) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT image_url FROM recipes WHERE image_url IS NOT NULL
+             UNION SELECT recipe_image FROM favorites WHERE recipe_image IS NOT NULL
+             UNION SELECT photo_url FROM cook_log WHERE photo_url IS NOT NULL
+             UNION SELECT json_extract(snapshot, '$.image_url') FROM recipe_revisions
+                 WHERE json_valid(snapshot) AND json_extract(snapshot, '$.image_url') IS NOT NULL",
+        )
+        .map_err(|e| format!("Failed to load image references: {}", e))?;
+    let urls = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load image references: {}", e))?;
+    Ok(urls
+        .iter()
+        .filter_map(|url| image_name_from_url(url))
+        .map(str::to_string)
+        .collect())
+}
+
+fn is_past_grace_period(path: &Path, grace_period: Duration) -> bool {
+    let age = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .unwrap_or_default();
+    age >= grace_period
+}
+
+fn remove_stored_file(images_dir: &Path, name: &str, result: &mut ImageCollectionResult) {
+    for path in [images_dir.join(name), images_dir.join(thumbnail_name(name))] {
+        if let Ok(metadata) = fs::metadata(&path) {
+            if fs::remove_file(&path).is_ok() {
+                result.removed_files += 1;
+                result.freed_bytes += metadata.len();
+            }
+        }
+    }
+}
+
+/// Deletes stored images, and their thumbnails, that no recipe, favorite, cook log
+/// entry or recipe revision refers to any more.
+///
+/// Revisions pin their images on purpose: reverting restores the snapshot's
+/// `image_url`, which must still resolve. A replaced image is therefore kept until
+/// the recipe, and with it its revision history, is deleted.
+pub(crate) fn collect_garbage(
+    conn: &Connection,
+    images_dir: &Path,
+    grace_period: Duration,
+) -> Result<ImageCollectionResult, String> {
+    let mut result = ImageCollectionResult::default();
+    let entries = match fs::read_dir(images_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(result),
+    };
+    let referenced = referenced_names(conn)?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_image_name(&name) || referenced.contains(&name) {
+            continue;
+        }
+        if is_past_grace_period(&entry.path(), grace_period) {
+            remove_stored_file(images_dir, &name, &mut result);
+        }
+    }
+
+    // Thumbnails whose original is gone (e.g. removed by hand) are orphans too.
+    if let Ok(thumbnails) = fs::read_dir(images_dir.join(THUMBNAIL_DIR)) {
+        for entry in thumbnails.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(stem) = name.strip_suffix(".jpg") else {
+                continue;
+            };
+            let has_original = ["png", "jpg", "gif", "webp"]
+                .iter()
+                .any(|extension| images_dir.join(format!("{stem}.{extension}")).exists());
+            if !has_original {
+                let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                if fs::remove_file(entry.path()).is_ok() {
+                    result.removed_files += 1;
+                    result.freed_bytes += size;
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Deletes a no longer referenced image right away unless it was stored within the
+/// grace period, in which case an attach of the same content may be in flight and
+/// the file is left for `collect_garbage`.
+pub(crate) fn remove_if_orphaned(
+    conn: &Connection,
+    images_dir: &Path,
+    url: Option<String>,
+    grace_period: Duration,
+) -> Result<(), String> {
+    let Some(name) = url.as_deref().and_then(image_name_from_url) else {
+        return Ok(());
+    };
+    if is_past_grace_period(&images_dir.join(name), grace_period)
+        && !referenced_names(conn)?.contains(name)
+    {
+        remove_stored_file(images_dir, name, &mut ImageCollectionResult::default());
+    }
+    Ok(())
+}
+
+fn current_image(conn: &Connection, recipe_id: i64) -> Result<Option<String>, String> {
+    Ok(crate::recipe::require_recipe(conn, recipe_id)?.image_url)
+}
+
+fn set_recipe_image(conn: &Connection, recipe_id: i64, url: Option<&str>) -> Result<(), String> {
+    conn.execute(
+        "UPDATE recipes SET image_url = ?1 WHERE id = ?2",
+        params![url, recipe_id],
+    )
+    .and_then(|_| {
+        conn.execute(
+            "UPDATE favorites SET recipe_image = ?1 WHERE recipe_id = ?2",
+            params![url, recipe_id],
+        )
+    })
+    .map_err(|e| format!("Failed to update recipe image: {}", e))?;
+    Ok(())
+}
+
+/// Validates an uploaded raster image and adds it to the store. SVG is refused
+/// here because user-supplied markup is not worth the risk.
+pub(crate) fn store_upload(images_dir: &Path, bytes: &[u8]) -> Result<StoredImage, String> {
+    if bytes.is_empty() || bytes.len() > MAX_IMAGE_BYTES {
+        return Err("Image must be between 1 byte and 20 MB".to_string());
+    }
+    let format = image::guess_format(bytes).map_err(|_| "Unsupported image format".to_string())?;
+    let extension = match format {
+        ImageFormat::Png => "png",
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::WebP => "webp",
+        ImageFormat::Gif => "gif",
+        _ => return Err("Unsupported image format".to_string()),
+    };
+    let decoded = image::load_from_memory_with_format(bytes, format)
+        .map_err(|_| "Image could not be decoded".to_string())?;
+    let url = store_image(images_dir, bytes, extension)?;
+    Ok(StoredImage {
+        thumbnail_url: thumbnail_url(&url),
+        url,
+        width: decoded.width(),
+        height: decoded.height(),
+    })
+}
+
+pub(crate) fn attach_image(
+    conn: &Connection,
+    images_dir: &Path,
+    recipe_id: i64,
+    bytes: &[u8],
+) -> Result<StoredImage, String> {
+    let previous = current_image(conn, recipe_id)?;
+    let stored = store_upload(images_dir, bytes)?;
+    set_recipe_image(conn, recipe_id, Some(&stored.url))?;
+    if previous.as_deref() != Some(stored.url.as_str()) {
+        remove_if_orphaned(conn, images_dir, previous, GC_GRACE_PERIOD)?;
+    }
+    Ok(stored)
+}
+
+pub(crate) fn remove_image(
+    conn: &Connection,
+    images_dir: &Path,
+    recipe_id: i64,
+) -> Result<(), String> {
+    let previous = current_image(conn, recipe_id)?;
+    set_recipe_image(conn, recipe_id, None)?;
+    remove_if_orphaned(conn, images_dir, previous, GC_GRACE_PERIOD)
+}
+
+pub(crate) fn plain_response(status: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    response
+}
+
+/// Serves `chefmind-image://localhost/<sha256>.<ext>` and `.../thumbs/<sha256>.jpg`.
+/// Only content-addressed names are accepted, so requests cannot leave the store.
+pub(crate) fn protocol_response(
+    images_dir: &Path,
+    request: &Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    if request.method() != "GET" && request.method() != "HEAD" {
+        return plain_response(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    let path = request.uri().path().trim_start_matches('/');
+    let name = match path.strip_prefix("thumbs/") {
+        Some(thumbnail) if thumbnail.ends_with(".jpg") && is_image_name(thumbnail) => path,
+        Some(_) => return plain_response(StatusCode::NOT_FOUND),
+        None if is_image_name(path) => path,
+        None => return plain_response(StatusCode::NOT_FOUND),
+    };
+    let bytes = match fs::read(images_dir.join(name)) {
+        Ok(bytes) => bytes,
+        Err(_) => return plain_response(StatusCode::NOT_FOUND),
+    };
+    let extension = name.rsplit_once('.').map_or("", |(_, extension)| extension);
+    let body = if request.method() == "HEAD" {
+        Vec::new()
+    } else {
+        bytes
+    };
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, mime_for_extension(extension))
+        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+    if extension == "svg" {
+        builder = builder.header(
+            header::CONTENT_SECURITY_POLICY,
+            "default-src 'none'; style-src 'unsafe-inline'; sandbox",
+        );
+    }
+    builder
+        .body(body)
+        .unwrap_or_else(|_| plain_response(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+/// Takes the image as the raw request body, e.g.
+/// `invoke('recipe_attach_image', bytes, { headers: { 'recipe-id': '42' } })`, so a
+/// large photo is not encoded as a JSON number array on its way over IPC.
+#[tauri::command]
+pub(crate) fn recipe_attach_image(
+    request: tauri::ipc::Request<'_>,
+    db: State<DatabaseState>,
+) -> Result<StoredImage, String> {
+    let recipe_id = request
+        .headers()
+        .get("recipe-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok())
+        .ok_or_else(|| "Recipe id is missing".to_string())?;
+    let InvokeBody::Raw(bytes) = request.body() else {
+        return Err("Image must be sent as raw bytes".to_string());
+    };
+    let conn = db.open_connection()?;
+    attach_image(&conn, &db.images_dir(), recipe_id, bytes)
+}
+
+#[tauri::command]
+pub(crate) fn recipe_remove_image(recipe_id: i64, db: State<DatabaseState>) -> Result<(), String> {
+    let conn = db.open_connection()?;
+    remove_image(&conn, &db.images_dir(), recipe_id)
+}
+
+#[tauri::command]
+pub(crate) fn image_collect_garbage(
+    db: State<DatabaseState>,
+) -> Result<ImageCollectionResult, String> {
+    let conn = db.open_connection()?;
+    collect_garbage(&conn, &db.images_dir(), GC_GRACE_PERIOD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+    use crate::recipe::{insert_recipe, RecipeDraft};
+    use image::{ImageBuffer, Rgb};
+    use std::io::Cursor;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chefmind-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn png(width: u32, height: u32, shade: u8) -> Vec<u8> {
+        let image = ImageBuffer::from_pixel(width, height, Rgb([shade, 80, 40]));
+        let mut bytes = Cursor::new(Vec::new());
+        image
+            .write_to(&mut bytes, ImageFormat::Png)
+            .expect("encode png");
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn attaches_replaces_and_collects_images() {
+        let dir = scratch_dir("image-store");
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        let recipe_id = insert_recipe(
+            &conn,
+            &RecipeDraft {
+                title: "番茄炒蛋".to_string(),
+                ingredients: vec!["番茄".to_string()],
+                instructions: vec!["炒".to_string()],
+                ..RecipeDraft::default()
+            },
+        )
+        .expect("insert recipe");
+
+        let first = attach_image(&conn, &dir, recipe_id, &png(800, 400, 10)).expect("attach");
+        assert_eq!((first.width, first.height), (800, 400));
+        let first_name = image_name_from_url(&first.url)
+            .expect("store url")
+            .to_string();
+        let thumbnail = image::open(dir.join(thumbnail_name(&first_name))).expect("thumbnail");
+        assert_eq!((thumbnail.width(), thumbnail.height()), (320, 160));
+
+        // The replaced image is still referenced by the recipe's revision history.
+        let second = attach_image(&conn, &dir, recipe_id, &png(64, 64, 200)).expect("replace");
+        assert!(dir.join(&first_name).exists());
+        conn.execute("DELETE FROM recipe_revisions", [])
+            .expect("clear history");
+        let collected = collect_garbage(&conn, &dir, Duration::ZERO).expect("collect");
+        assert_eq!(collected.removed_files, 2);
+        assert!(!dir.join(&first_name).exists());
+
+        remove_image(&conn, &dir, recipe_id).expect("remove");
+        conn.execute("DELETE FROM recipe_revisions", [])
+            .expect("clear history");
+        collect_garbage(&conn, &dir, Duration::ZERO).expect("collect");
+        let second_name = image_name_from_url(&second.url).expect("store url");
+        assert!(!dir.join(second_name).exists());
+
+        assert!(attach_image(&conn, &dir, recipe_id, b"<svg></svg>").is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn protocol_serves_only_content_addressed_files() {
+        let dir = scratch_dir("image-protocol");
+        let url = store_image(&dir, &png(8, 8, 0), "png").expect("store");
+        let name = image_name_from_url(&url).expect("store url").to_string();
+        let get = |path: &str| {
+            let request = Request::builder()
+                .uri(format!("{IMAGE_SCHEME}://localhost/{path}"))
+                .body(Vec::new())
+                .expect("request");
+            protocol_response(&dir, &request)
+        };
+
+        let response = get(&name);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        assert_eq!(get(&thumbnail_name(&name)).status(), StatusCode::OK);
+        for path in [
+            "../chefmind.db",
+            "thumbs/../chefmind.db",
+            "%2e%2e/chefmind.db",
+        ] {
+            assert_eq!(get(path).status(), StatusCode::NOT_FOUND);
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_recently_stored_orphans_for_a_concurrent_attach() {
+        let dir = scratch_dir("image-grace");
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        let bytes = png(8, 8, 90);
+        let url = store_image(&dir, &bytes, "png").expect("store");
+        let path = resolve_image_path(&dir, &url).expect("store url");
+
+        remove_if_orphaned(&conn, &dir, Some(url.clone()), GC_GRACE_PERIOD).expect("remove");
+        assert!(path.exists());
+
+        let old = SystemTime::now() - 2 * GC_GRACE_PERIOD;
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(old))
+            .expect("age the file");
+        // Storing the same bytes again restarts the grace period.
+        store_image(&dir, &bytes, "png").expect("store again");
+        remove_if_orphaned(&conn, &dir, Some(url.clone()), GC_GRACE_PERIOD).expect("remove");
+        assert!(path.exists());
+
+        remove_if_orphaned(&conn, &dir, Some(url), Duration::ZERO).expect("remove");
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,693 @@
+use crate::recipe::{insert_recipe, leading_number, parse_quantity, RecipeDraft};
+use crate::DatabaseState;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::State;
+
+const MAX_IMPORT_BYTES: u64 = 5 * 1024 * 1024;
+
+// Heading keywords, matched against lower-cased heading text.
+const INGREDIENT_HEADINGS: &[&str] = &["ingredient", "材料", "食材", "配料", "用料", "原料"];
+const INSTRUCTION_HEADINGS: &[&str] = &[
+    "instruction",
+    "direction",
+    "method",
+    "step",
+    "preparation",
+    "做法",
+    "步骤",
+    "制作",
+    "烹饪方法",
+];
+const DESCRIPTION_HEADINGS: &[&str] = &[
+    "description",
+    "about",
+    "note",
+    "tip",
+    "简介",
+    "介绍",
+    "描述",
+    "小贴士",
+    "提示",
+    "备注",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeImportFormat {
+    JsonLd,
+    Markdown,
+    PlainText,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParsedRecipe {
+    pub format: RecipeImportFormat,
+    pub recipe: RecipeDraft,
+    /// Recipe columns the source did not provide a recognizable value for.
+    pub unrecognized_fields: Vec<String>,
+    /// Headings, metadata lines or JSON-LD properties that were skipped.
+    pub ignored_content: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeImportResult {
+    pub recipe_id: Option<i64>,
+    #[serde(flatten)]
+    pub parsed: ParsedRecipe,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Preamble,
+    Ingredients,
+    Instructions,
+    Description,
+    Unknown,
+}
+
+fn detect_format(path: &Path, text: &str) -> RecipeImportFormat {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let trimmed = text.trim_start();
+    match extension.as_str() {
+        "json" | "jsonld" | "html" | "htm" => RecipeImportFormat::JsonLd,
+        "md" | "markdown" => RecipeImportFormat::Markdown,
+        _ if trimmed.starts_with('{') || trimmed.starts_with('[') => RecipeImportFormat::JsonLd,
+        _ if trimmed.starts_with("# ") => RecipeImportFormat::Markdown,
+        _ => RecipeImportFormat::PlainText,
+    }
+}
+
+/// Converts ISO 8601 durations ("PT1H30M") and written ones ("1小时20分钟", "45 min") to minutes.
+/// A range such as "1-2 hours" counts as its lower bound, as step timers do.
+pub(crate) fn parse_minutes(text: &str) -> Option<i64> {
+    let text = text.trim().to_ascii_uppercase();
+    if let Some(rest) = text.strip_prefix('P').filter(|rest| is_iso_duration(rest)) {
+        let mut minutes = 0.0;
+        let mut number = String::new();
+        let mut in_time = false;
+        for c in rest.chars() {
+            match c {
+                '0'..='9' | '.' => number.push(c),
+                'T' => in_time = true,
+                _ => {
+                    let value = number.parse::<f64>().unwrap_or(0.0);
+                    number.clear();
+                    minutes += match (c, in_time) {
+                        ('D', false) => value * 1_440.0,
+                        ('H', true) => value * 60.0,
+                        ('M', true) => value,
+                        ('S', true) => value / 60.0,
+                        _ => 0.0,
+                    };
+                }
+            }
+        }
+        return whole_minutes(minutes);
+    }
+
+    let mut minutes = 0.0;
+    let mut found = false;
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let value = rest[..end].parse::<f64>().unwrap_or(0.0);
+        rest = rest[end..].trim_start();
+        if let Some(upper) = ["-", "–", "~", "～", "至", "TO "]
+            .iter()
+            .find_map(|separator| rest.strip_prefix(separator))
+            .map(str::trim_start)
+            .filter(|upper| upper.starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let end = upper
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(upper.len());
+            rest = upper[end..].trim_start();
+        }
+        let factor = if rest.starts_with("小时") || rest.starts_with('H') {
+            60.0
+        } else if rest.starts_with("天") || rest.starts_with("DAY") {
+            1_440.0
+        } else if rest.starts_with('秒') || rest.starts_with("SEC") {
+            1.0 / 60.0
+        } else {
+            1.0
+        };
+        minutes += value * factor;
+        found = true;
+    }
+    if found {
+        whole_minutes(minutes)
+    } else {
+        None
+    }
+}
+
+// The part after `P` of an ISO 8601 duration such as `PT1H30M` or `P1DT2H`.
+fn is_iso_duration(rest: &str) -> bool {
+    rest.starts_with(|c: char| c.is_ascii_digit() || c == 'T')
+        && (rest.contains('T') || rest.ends_with('D'))
+        && rest
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'D' | 'T' | 'H' | 'M' | 'S'))
+}
+
+// Rounds to whole minutes, keeping a short but non-zero time at one minute.
+fn whole_minutes(minutes: f64) -> Option<i64> {
+    (minutes > 0.0).then(|| minutes.round().max(1.0) as i64)
+}
+
+pub(crate) fn split_tags(text: &str) -> Vec<String> {
+    text.split([',', '，', '、', ';', '；'])
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub(crate) fn string_values(value: &serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::String(text) => split_tags(text),
+        serde_json::Value::Array(items) => items.iter().flat_map(string_values).collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub(crate) fn first_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(text) => Some(text.trim().to_string()),
+        serde_json::Value::Number(number) => Some(number.to_string()),
+        serde_json::Value::Array(items) => items.iter().find_map(first_text),
+        serde_json::Value::Object(map) => map.get("url").and_then(first_text),
+        _ => None,
+    }
+    .filter(|text| !text.is_empty())
+}
+
+fn is_recipe_node(value: &serde_json::Value) -> bool {
+    match value.get("@type") {
+        Some(serde_json::Value::String(kind)) => kind == "Recipe",
+        Some(serde_json::Value::Array(kinds)) => kinds.iter().any(|kind| kind == "Recipe"),
+        _ => false,
+    }
+}
+
+fn find_recipe_node(value: &serde_json::Value) -> Option<&serde_json::Value> {
+    if is_recipe_node(value) {
+        return Some(value);
+    }
+    match value {
+        serde_json::Value::Array(items) => items.iter().find_map(find_recipe_node),
+        serde_json::Value::Object(map) => map.get("@graph").and_then(find_recipe_node),
+        _ => None,
+    }
+}
+
+/// Saved web pages embed the JSON-LD in `<script type="application/ld+json">` blocks.
+fn json_ld_blocks(text: &str) -> Vec<&str> {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        return vec![trimmed];
+    }
+    // ASCII lower-casing keeps byte offsets aligned with the original text.
+    let lower = text.to_ascii_lowercase();
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    while let Some(found) = lower[offset..].find("application/ld+json") {
+        let tag_end = match lower[offset + found..].find('>') {
+            Some(index) => offset + found + index + 1,
+            None => break,
+        };
+        let block_end = match lower[tag_end..].find("</script") {
+            Some(index) => tag_end + index,
+            None => break,
+        };
+        blocks.push(&text[tag_end..block_end]);
+        offset = block_end;
+    }
+    blocks
+}
+
+fn instruction_steps(value: &serde_json::Value, steps: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(text) => steps.extend(
+            text.lines()
+                .map(strip_list_marker)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        ),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                instruction_steps(item, steps);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            // HowToSection nests its HowToSteps under itemListElement.
+            if let Some(items) = map.get("itemListElement") {
+                instruction_steps(items, steps);
+            } else if let Some(text) = map
+                .get("text")
+                .or_else(|| map.get("name"))
+                .and_then(serde_json::Value::as_str)
+            {
+                instruction_steps(&serde_json::Value::String(text.to_string()), steps);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn json_ld_nutrition(value: &serde_json::Value) -> Option<serde_json::Value> {
+    let mut nutrition = serde_json::Map::new();
+    for (source, target) in [
+        ("calories", "calories"),
+        ("proteinContent", "protein"),
+        ("carbohydrateContent", "carbs"),
+        ("fatContent", "fat"),
+        ("fiberContent", "fiber"),
+        ("sodiumContent", "sodium"),
+    ] {
+        if let Some(amount) = value.get(source).and_then(leading_number) {
+            nutrition.insert(target.to_string(), serde_json::Value::from(amount));
+        }
+    }
+    (!nutrition.is_empty()).then_some(serde_json::Value::Object(nutrition))
+}
+
+pub(crate) fn parse_json_ld(text: &str) -> Result<ParsedRecipe, String> {
+    let node = json_ld_blocks(text)
+        .into_iter()
+        .filter_map(|block| serde_json::from_str::<serde_json::Value>(block.trim()).ok())
+        .find_map(|value| find_recipe_node(&value).cloned())
+        .ok_or_else(|| "No schema.org Recipe was found in the file".to_string())?;
+
+    let mut recipe = RecipeDraft {
+        title: node.get("name").and_then(first_text).unwrap_or_default(),
+        description: node.get("description").and_then(first_text),
+        ingredients: node
+            .get("recipeIngredient")
+            .or_else(|| node.get("ingredients"))
+            .map(|value| match value {
+                serde_json::Value::Array(items) => items.iter().filter_map(first_text).collect(),
+                other => first_text(other).into_iter().collect(),
+            })
+            .unwrap_or_default(),
+        cooking_time: ["totalTime", "cookTime", "prepTime"]
+            .iter()
+            .find_map(|key| node.get(*key).and_then(first_text))
+            .and_then(|text| parse_minutes(&text)),
+        servings: node
+            .get("recipeYield")
+            .and_then(first_text)
+            .and_then(|text| parse_quantity(&text).0)
+            .map(|servings| servings.round() as i64),
+        category: node
+            .get("recipeCategory")
+            .map(string_values)
+            .and_then(|values| values.into_iter().next()),
+        tags: node.get("keywords").map(string_values).unwrap_or_default(),
+        nutrition_info: node.get("nutrition").and_then(json_ld_nutrition),
+        image_url: node.get("image").and_then(first_text),
+        cooking_methods: node
+            .get("cookingMethod")
+            .map(string_values)
+            .unwrap_or_default(),
+        ..RecipeDraft::default()
+    };
+    if let Some(instructions) = node.get("recipeInstructions") {
+        instruction_steps(instructions, &mut recipe.instructions);
+    }
+
+    const KNOWN_KEYS: &[&str] = &[
+        "@context",
+        "@type",
+        "@id",
+        "name",
+        "description",
+        "recipeIngredient",
+        "ingredients",
+        "recipeInstructions",
+        "totalTime",
+        "cookTime",
+        "prepTime",
+        "recipeYield",
+        "recipeCategory",
+        "keywords",
+        "nutrition",
+        "image",
+        "cookingMethod",
+    ];
+    let ignored_content = node
+        .as_object()
+        .map(|map| {
+            map.keys()
+                .filter(|key| !KNOWN_KEYS.contains(&key.as_str()))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(finish(RecipeImportFormat::JsonLd, recipe, ignored_content))
+}
+
+pub(crate) fn strip_list_marker(line: &str) -> &str {
+    let line = line.trim();
+    let line = line
+        .strip_prefix("- [ ]")
+        .or_else(|| line.strip_prefix("- [x]"))
+        .or_else(|| line.strip_prefix(['-', '*', '+', '•', '·']))
+        .unwrap_or(line)
+        .trim_start();
+    let line = line.strip_prefix('第').unwrap_or(line);
+    let line = match line.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("step ") => &line[5..],
+        _ => line,
+    };
+    let digits = line
+        .char_indices()
+        .find(|(_, c)| !c.is_ascii_digit())
+        .map(|(index, _)| index)
+        .unwrap_or(line.len());
+    if digits == 0 {
+        return line;
+    }
+    let rest = &line[digits..];
+    // "1.5 kg" is a quantity, not item one.
+    if rest.starts_with('.') && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+        return line;
+    }
+    ["步.", "步：", "步:", "步", ".", "、", ")", "）", ":", "："]
+        .iter()
+        .find_map(|marker| rest.strip_prefix(marker))
+        .map(str::trim_start)
+        .unwrap_or(line)
+}
+
+fn heading_text(line: &str, format: RecipeImportFormat) -> Option<String> {
+    let trimmed = line.trim();
+    if format == RecipeImportFormat::Markdown {
+        if let Some(text) = trimmed.strip_prefix('#') {
+            return Some(text.trim_start_matches('#').trim().to_string());
+        }
+        let bold = trimmed.trim_matches('*').trim();
+        if trimmed.starts_with("**") && trimmed.ends_with("**") && !bold.is_empty() {
+            return Some(bold.trim_end_matches([':', '：']).to_string());
+        }
+    }
+    // "食材：" or "Ingredients:" on a line of its own.
+    let text = trimmed.strip_suffix([':', '：'])?.trim();
+    (!text.is_empty() && text.chars().count() <= 20 && classify_heading(text) != Section::Unknown)
+        .then(|| text.to_string())
+}
+
+fn classify_heading(text: &str) -> Section {
+    let lower = text.to_lowercase();
+    let has = |keywords: &[&str]| keywords.iter().any(|keyword| lower.contains(keyword));
+    if has(INGREDIENT_HEADINGS) {
+        Section::Ingredients
+    } else if has(INSTRUCTION_HEADINGS) {
+        Section::Instructions
+    } else if has(DESCRIPTION_HEADINGS) {
+        Section::Description
+    } else {
+        Section::Unknown
+    }
+}
+
+/// Applies "时间：30分钟" style metadata lines; returns false when the key is not a known field.
+fn apply_metadata(recipe: &mut RecipeDraft, line: &str) -> bool {
+    let Some((key, value)) = line
+        .split_once(['：', ':'])
+        .map(|(key, value)| (key.trim().trim_matches('*').to_lowercase(), value.trim()))
+    else {
+        return false;
+    };
+    if value.is_empty() || key.chars().count() > 20 {
+        return false;
+    }
+    let has = |keywords: &[&str]| keywords.iter().any(|keyword| key.contains(keyword));
+    if has(&["时间", "耗时", "time"]) {
+        recipe.cooking_time = parse_minutes(value).or(recipe.cooking_time);
+    } else if has(&["份量", "份数", "人份", "servings", "serves", "yield"]) {
+        recipe.servings = parse_quantity(value)
+            .0
+            .map(|servings| servings.round() as i64)
+            .or(recipe.servings);
+    } else if has(&["难度", "difficulty"]) {
+        recipe.difficulty = Some(value.to_string());
+    } else if has(&["分类", "类别", "菜系", "category", "cuisine"]) {
+        recipe.category = Some(value.to_string());
+    } else if has(&["标签", "tags", "keywords"]) {
+        recipe.tags.extend(split_tags(value));
+    } else if has(&["烹饪方式", "cooking method"]) {
+        recipe.cooking_methods.extend(split_tags(value));
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Shared parser for Markdown and plain text: the first line (or `#` heading) is the
+/// title, and recognized headings switch between ingredient, step and description sections.
+pub(crate) fn parse_sectioned_text(text: &str, format: RecipeImportFormat) -> ParsedRecipe {
+    let mut recipe = RecipeDraft::default();
+    let mut ignored_content = Vec::new();
+    let mut description = Vec::new();
+    let mut section = Section::Preamble;
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(heading) = heading_text(line, format) {
+            if recipe.title.is_empty() && section == Section::Preamble && line.starts_with("# ") {
+                recipe.title = heading;
+                continue;
+            }
+            section = classify_heading(&heading);
+            if section == Section::Unknown {
+                ignored_content.push(heading);
+            }
+            continue;
+        }
+        if recipe.title.is_empty() && section == Section::Preamble {
+            recipe.title = line.trim_start_matches('#').trim().to_string();
+            continue;
+        }
+
+        match section {
+            Section::Ingredients => {
+                let item = strip_list_marker(line);
+                if !item.is_empty() {
+                    recipe.ingredients.push(item.to_string());
+                }
+            }
+            Section::Instructions => {
+                let step = strip_list_marker(line);
+                if !step.is_empty() {
+                    recipe.instructions.push(step.to_string());
+                }
+            }
+            Section::Preamble | Section::Description => {
+                if !apply_metadata(&mut recipe, strip_list_marker(line)) {
+                    description.push(line.to_string());
+                }
+            }
+            Section::Unknown => {}
+        }
+    }
+
+    if !description.is_empty() {
+        recipe.description = Some(description.join("\n"));
+    }
+    finish(format, recipe, ignored_content)
+}
+
+fn finish(
+    format: RecipeImportFormat,
+    mut recipe: RecipeDraft,
+    ignored_content: Vec<String>,
+) -> ParsedRecipe {
+    recipe.title = recipe.title.trim().to_string();
+    let checks = [
+        ("title", recipe.title.is_empty()),
+        ("description", recipe.description.is_none()),
+        ("ingredients", recipe.ingredients.is_empty()),
+        ("instructions", recipe.instructions.is_empty()),
+        ("cooking_time", recipe.cooking_time.is_none()),
+        ("difficulty", recipe.difficulty.is_none()),
+        ("servings", recipe.servings.is_none()),
+        ("category", recipe.category.is_none()),
+        ("tags", recipe.tags.is_empty()),
+        ("nutrition_info", recipe.nutrition_info.is_none()),
+        ("image_url", recipe.image_url.is_none()),
+        ("cooking_methods", recipe.cooking_methods.is_empty()),
+    ];
+    ParsedRecipe {
+        format,
+        recipe,
+        unrecognized_fields: checks
+            .iter()
+            .filter(|(_, missing)| *missing)
+            .map(|(field, _)| field.to_string())
+            .collect(),
+        ignored_content,
+    }
+}
+
+pub(crate) fn parse_recipe_file(path: &Path, text: &str) -> Result<ParsedRecipe, String> {
+    match detect_format(path, text) {
+        RecipeImportFormat::JsonLd => parse_json_ld(text),
+        format => Ok(parse_sectioned_text(text, format)),
+    }
+}
+
+pub(crate) fn read_import_file(path: &Path) -> Result<Vec<u8>, String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("Failed to read import file: {}", e))?;
+    if !metadata.is_file() || metadata.len() > MAX_IMPORT_BYTES {
+        return Err("Import file must be a regular file no larger than 5 MB".to_string());
+    }
+    fs::read(path).map_err(|e| format!("Failed to read import file: {}", e))
+}
+
+/// Parses a local recipe file. With `save` the recipe is stored once it has a title,
+/// ingredients and steps; otherwise the parsed draft is returned for review.
+#[tauri::command]
+pub(crate) fn recipe_import(
+    path: String,
+    save: Option<bool>,
+    db: State<DatabaseState>,
+) -> Result<RecipeImportResult, String> {
+    let path = Path::new(&path);
+    let bytes = read_import_file(path)?;
+    let text = String::from_utf8(bytes).map_err(|_| "Import file is not UTF-8 text".to_string())?;
+    let parsed = parse_recipe_file(path, &text)?;
+
+    let recipe_id = if save.unwrap_or(false) {
+        parsed.recipe.validate()?;
+        let conn = db.open_connection()?;
+        Some(
+            insert_recipe(&conn, &parsed.recipe)
+                .map_err(|e| format!("Failed to save recipe: {}", e))?,
+        )
+    } else {
+        None
+    };
+    Ok(RecipeImportResult { recipe_id, parsed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_json_ld_embedded_in_a_saved_page() {
+        let page = r#"<html><head>
+            <script type="application/ld+json">{"@context": "https://schema.org", "@type": "WebSite"}</script>
+            <script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [{"@type": "Recipe",
+              "name": "Classic Pancakes",
+              "recipeIngredient": ["2 cups flour", "2 eggs"],
+              "recipeInstructions": [{"@type": "HowToSection", "name": "Batter",
+                "itemListElement": [{"@type": "HowToStep", "text": "Whisk everything."}]},
+                {"@type": "HowToStep", "text": "Fry until golden."}],
+              "totalTime": "PT1H5M",
+              "recipeYield": ["4", "4 pancakes"],
+              "keywords": "breakfast, sweet",
+              "nutrition": {"@type": "NutritionInformation", "calories": "220 kcal", "sodiumContent": "310 mg"},
+              "image": {"@type": "ImageObject", "url": "https://example.com/pancakes.jpg"},
+              "author": {"@type": "Person", "name": "Sam"}}]}
+            </script></head></html>"#;
+
+        let parsed = parse_recipe_file(Path::new("pancakes.html"), page).expect("parse page");
+        assert_eq!(parsed.format, RecipeImportFormat::JsonLd);
+        assert_eq!(parsed.recipe.title, "Classic Pancakes");
+        assert_eq!(
+            parsed.recipe.instructions,
+            vec!["Whisk everything.", "Fry until golden."]
+        );
+        assert_eq!(parsed.recipe.cooking_time, Some(65));
+        assert_eq!(parsed.recipe.servings, Some(4));
+        assert_eq!(parsed.recipe.tags, vec!["breakfast", "sweet"]);
+        assert_eq!(
+            parsed.recipe.nutrition().map(|facts| facts.sodium),
+            Some(310.0)
+        );
+        assert_eq!(
+            parsed.recipe.image_url.as_deref(),
+            Some("https://example.com/pancakes.jpg")
+        );
+        assert_eq!(parsed.ignored_content, vec!["author"]);
+        assert!(parsed.unrecognized_fields.contains(&"category".to_string()));
+    }
+
+    #[test]
+    fn imports_markdown_and_plain_text_sections() {
+        let markdown = "# 番茄炒蛋\n\n家常快手菜。\n\n- 时间：15分钟\n- 份量：2人份\n\n\
+            ## 食材\n- 番茄 2个\n- 鸡蛋 3个\n\n## 做法\n1. 鸡蛋打散炒熟盛出\n2. 番茄炒软后加入鸡蛋\n\n\
+            ## 营养分析\n热量很低\n";
+        let parsed = parse_recipe_file(Path::new("番茄炒蛋.md"), markdown).expect("parse md");
+        assert_eq!(parsed.format, RecipeImportFormat::Markdown);
+        assert_eq!(parsed.recipe.title, "番茄炒蛋");
+        assert_eq!(parsed.recipe.description.as_deref(), Some("家常快手菜。"));
+        assert_eq!(parsed.recipe.ingredients, vec!["番茄 2个", "鸡蛋 3个"]);
+        assert_eq!(
+            parsed.recipe.instructions,
+            vec!["鸡蛋打散炒熟盛出", "番茄炒软后加入鸡蛋"]
+        );
+        assert_eq!(parsed.recipe.cooking_time, Some(15));
+        assert_eq!(parsed.recipe.servings, Some(2));
+        assert_eq!(parsed.ignored_content, vec!["营养分析"]);
+
+        let text = "Garlic Noodles\nCook time: 1 hour 10 min\nIngredients:\n200g noodles\n\
+            4 cloves garlic\nDirections:\nStep 1: Boil the noodles.\n2) Fry the garlic and toss.\n";
+        let parsed = parse_recipe_file(Path::new("noodles.txt"), text).expect("parse text");
+        assert_eq!(parsed.format, RecipeImportFormat::PlainText);
+        assert_eq!(parsed.recipe.title, "Garlic Noodles");
+        assert_eq!(parsed.recipe.cooking_time, Some(70));
+        assert_eq!(
+            parsed.recipe.ingredients,
+            vec!["200g noodles", "4 cloves garlic"]
+        );
+        assert_eq!(
+            parsed.recipe.instructions,
+            vec!["Boil the noodles.", "Fry the garlic and toss."]
+        );
+        assert!(parsed.recipe.validate().is_ok());
+        assert!(parsed
+            .unrecognized_fields
+            .contains(&"description".to_string()));
+    }
+
+    #[test]
+    fn keeps_decimal_quantities_and_reads_time_ranges_from_their_lower_bound() {
+        let text = "Pound Cake\nCook time: 1-2 hours\nIngredients:\n1.5 kg flour\n0.5 cups milk\n\
+            2. 3 eggs\nDirections:\n1. Mix.\n2.Bake.\n";
+        let parsed = parse_recipe_file(Path::new("cake.txt"), text).expect("parse decimals");
+        assert_eq!(parsed.recipe.cooking_time, Some(60));
+        assert_eq!(
+            parsed.recipe.ingredients,
+            vec!["1.5 kg flour", "0.5 cups milk", "3 eggs"]
+        );
+        assert_eq!(parsed.recipe.instructions, vec!["Mix.", "Bake."]);
+        assert_eq!(parse_minutes("炖1~2小时"), Some(60));
+        assert_eq!(parse_minutes("10–15 min"), Some(10));
+    }
+
+    #[test]
+    fn converts_seconds_and_only_reads_iso_shaped_durations() {
+        assert_eq!(parse_minutes("PT1H30M"), Some(90));
+        assert_eq!(parse_minutes("P1DT2H"), Some(1_560));
+        assert_eq!(parse_minutes("PT90S"), Some(2));
+        assert_eq!(parse_minutes("90 seconds"), Some(2));
+        assert_eq!(parse_minutes("5 min 30 sec"), Some(6));
+        assert_eq!(parse_minutes("焖30秒"), Some(1));
+        assert_eq!(parse_minutes("Prep 10 min"), Some(10));
+        assert_eq!(parse_minutes("Pressure cook 2 hours"), Some(120));
+        assert_eq!(parse_minutes("Pending"), None);
+    }
+}
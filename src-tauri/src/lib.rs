@@ -1,19 +1,73 @@
+use crate::provider_params::CompletionOptions;
 use keyring::Entry;
 use reqwest::{redirect::Policy, Client};
 use rusqlite::{Connection, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::net::IpAddr;
+use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Mutex, RwLock, RwLockReadGuard};
 use std::time::Duration;
 use tauri::{Emitter, Listener, Manager, PhysicalSize, State};
 use url::Url;
 
+mod bundle;
+mod cook_log;
+mod cooking;
+mod credential_health;
+mod credential_transfer;
+mod dietary;
+mod encryption;
+mod exporter;
+mod external_import;
+mod health;
+mod images;
+mod importer;
+mod matching;
+mod meal_plan;
+mod pantry;
+mod popularity;
+mod profiles;
+mod provider_chain;
+mod provider_params;
+mod recipe;
+mod recognition;
+mod revisions;
+mod search_history;
+mod step_timing;
+mod tray;
+mod variations;
+
 // Database state structure
 pub struct DatabaseState {
     _connection: Mutex<Option<Connection>>,
     db_path: PathBuf,
+    /// Key of an encrypted database, read from the keyring on first use.
+    encryption_key: Mutex<Option<String>>,
+    /// Shared by every open connection and taken exclusively while the database file
+    /// is rewritten, so nothing writes to the file that is about to be replaced.
+    rewrite_gate: RwLock<()>,
+}
+
+/// A database connection that holds the rewrite gate open until it is dropped.
+pub(crate) struct DbConnection<'a> {
+    conn: Connection,
+    _gate: RwLockReadGuard<'a, ()>,
+}
+
+impl Deref for DbConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl DerefMut for DbConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
 }
 
 // Database query structure
@@ -43,8 +97,20 @@ struct StoredProviderCredential {
     model: String,
 }
 
+/// Provider profiles whose credentials may be kept in the keyring, one per preset
+/// in the settings page. Several can be configured and chained as fallbacks.
+const PROVIDER_IDS: [&str; 7] = [
+    DEFAULT_PROVIDER_ID,
+    "deepseek",
+    "qwen",
+    "moonshot",
+    "zhipu",
+    "siliconflow",
+    "custom",
+];
+
 fn validate_provider_id(provider_id: &str) -> std::result::Result<(), String> {
-    if provider_id == DEFAULT_PROVIDER_ID {
+    if PROVIDER_IDS.contains(&provider_id) {
         Ok(())
     } else {
         Err("Unsupported AI provider credential".to_string())
@@ -76,6 +142,18 @@ fn validate_base_url(base_url: &str) -> std::result::Result<Url, String> {
     Ok(parsed)
 }
 
+/// Accepts only canonical `YYYY-MM-DD` dates so range queries can compare text directly.
+fn validate_date(conn: &Connection, date: &str) -> std::result::Result<(), String> {
+    let canonical: Option<String> = conn
+        .query_row("SELECT date(?1)", [date], |row| row.get(0))
+        .map_err(|e| format!("Failed to validate date: {}", e))?;
+    if canonical.as_deref() == Some(date) {
+        Ok(())
+    } else {
+        Err("Date must use the YYYY-MM-DD format".to_string())
+    }
+}
+
 fn completion_url(base_url: &str) -> std::result::Result<Url, String> {
     let mut url = validate_base_url(base_url)?;
     let path = url.path().trim_end_matches('/');
@@ -110,43 +188,81 @@ fn read_credential(provider_id: &str) -> std::result::Result<StoredProviderCrede
 }
 
 async fn request_completion(
+    provider_id: &str,
     credential: &StoredProviderCredential,
     prompt: &str,
-    max_tokens: u32,
-    temperature: f64,
+    options: &CompletionOptions,
 ) -> std::result::Result<String, String> {
     if prompt.trim().is_empty() || prompt.len() > 100_000 {
         return Err("Prompt is invalid".to_string());
     }
-    if max_tokens == 0 || max_tokens > 16_384 || !(0.0..=2.0).contains(&temperature) {
-        return Err("Completion options are invalid".to_string());
-    }
+    request_chat(provider_id, credential, serde_json::json!(prompt), options).await
+}
+
+/// Phrases OpenAI-compatible providers use when a text-only model is sent image parts.
+const IMAGE_INPUT_REJECTIONS: [&str; 6] = [
+    "image_url",
+    "image input",
+    "vision",
+    "multimodal",
+    "does not support image",
+    "invalid content type",
+];
+
+/// The endpoint and body for one chat request. The request fields follow the
+/// provider the credential's endpoint belongs to, falling back to `provider_id`.
+fn chat_request(
+    provider_id: &str,
+    credential: &StoredProviderCredential,
+    content: serde_json::Value,
+    options: &CompletionOptions,
+) -> std::result::Result<(Url, serde_json::Value), String> {
+    let profile = provider_params::capability_profile(provider_id, &credential.base_url);
+    let body = provider_params::chat_request_body(profile, &credential.model, content, options)?;
+    Ok((completion_url(&credential.base_url)?, body))
+}
 
-    let endpoint = completion_url(&credential.base_url)?;
+/// Sends one user message whose `content` is either a string or an array of
+/// OpenAI-compatible content parts (text and `image_url`).
+async fn request_chat(
+    provider_id: &str,
+    credential: &StoredProviderCredential,
+    content: serde_json::Value,
+    options: &CompletionOptions,
+) -> std::result::Result<String, String> {
+    let has_image = content.as_array().is_some_and(|parts| {
+        parts
+            .iter()
+            .any(|part| part.get("type").and_then(serde_json::Value::as_str) == Some("image_url"))
+    });
+
+    let (endpoint, body) = chat_request(provider_id, credential, content, options)?;
     let client = Client::builder()
         .redirect(Policy::none())
-        .timeout(Duration::from_secs(30))
+        .timeout(Duration::from_secs(if has_image { 60 } else { 30 }))
         .build()
         .map_err(|_| "Unable to create a secure HTTP client".to_string())?;
 
     let response = client
         .post(endpoint)
         .bearer_auth(&credential.api_key)
-        .json(&serde_json::json!({
-            "model": credential.model,
-            "messages": [{ "role": "user", "content": prompt }],
-            "max_tokens": max_tokens,
-            "temperature": temperature,
-        }))
+        .json(&body)
         .send()
         .await
         .map_err(|_| "Unable to reach the AI provider".to_string())?;
 
-    if !response.status().is_success() {
-        return Err(format!(
-            "AI provider request failed with HTTP {}",
-            response.status()
-        ));
+    let status = response.status();
+    if !status.is_success() {
+        if has_image && status.is_client_error() {
+            let body = response.text().await.unwrap_or_default().to_lowercase();
+            if IMAGE_INPUT_REJECTIONS
+                .iter()
+                .any(|phrase| body.contains(phrase))
+            {
+                return Err("The configured model does not support image input".to_string());
+            }
+        }
+        return Err(format!("AI provider request failed with HTTP {}", status));
     }
 
     let payload = response
@@ -170,55 +286,92 @@ impl DatabaseState {
                 .unwrap_or_else(|_| PathBuf::from("."))
                 .join("data")
         });
+        let state = Self::at_path(data_dir.join("chefmind.db"));
 
         if !data_dir.exists() {
             if let Err(e) = fs::create_dir_all(&data_dir) {
                 eprintln!("Failed to create data directory: {}", e);
-                return Self {
-                    _connection: Mutex::new(None),
-                    db_path: data_dir.join("chefmind.db"),
-                };
+                return state;
             }
         }
+        println!("Database path: {:?}", state.db_path);
 
-        let db_path = data_dir.join("chefmind.db");
-        println!("Database path: {:?}", db_path);
-
-        match Connection::open(&db_path) {
+        match state.connect() {
             Ok(conn) => {
                 println!("Database connection established");
-                if let Err(e) = conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON; PRAGMA synchronous = NORMAL; PRAGMA busy_timeout = 10000;") {
-                    eprintln!("Failed to configure database: {}", e);
-                    return Self { _connection: Mutex::new(None), db_path };
-                }
-                if let Err(e) = initialize_schema(&conn) {
-                    eprintln!("Failed to initialize database schema: {}", e);
-                    return Self {
-                        _connection: Mutex::new(None),
-                        db_path,
-                    };
-                }
-                Self {
-                    _connection: Mutex::new(Some(conn)),
-                    db_path,
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to connect to database: {}", e);
-                Self {
-                    _connection: Mutex::new(None),
-                    db_path,
-                }
+                *state._connection.lock().unwrap_or_else(|e| e.into_inner()) = Some(conn);
             }
+            Err(e) => eprintln!("Failed to initialize database: {}", e),
+        }
+        state
+    }
+
+    fn at_path(db_path: PathBuf) -> Self {
+        Self {
+            _connection: Mutex::new(None),
+            db_path,
+            encryption_key: Mutex::new(None),
+            rewrite_gate: RwLock::new(()),
         }
     }
 
-    pub fn get_connection(&self) -> Result<Connection> {
+    pub(crate) fn get_connection(&self) -> Result<DbConnection<'_>> {
+        let gate = self.rewrite_gate.read().unwrap_or_else(|e| e.into_inner());
+        Ok(DbConnection {
+            conn: self.connect()?,
+            _gate: gate,
+        })
+    }
+
+    /// Opens a connection without the rewrite gate; only for the kept-alive
+    /// connection and for the rewrite itself.
+    fn connect(&self) -> Result<Connection> {
         let conn = Connection::open(&self.db_path)?;
+        if encryption::is_encrypted(&self.db_path) {
+            let key = self
+                .database_key()
+                .ok_or_else(encryption::missing_key_error)?;
+            encryption::apply_key(&conn, &key)?;
+        }
         conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON; PRAGMA synchronous = NORMAL; PRAGMA busy_timeout = 10000;")?;
         initialize_schema(&conn)?;
         Ok(conn)
     }
+
+    fn database_key(&self) -> Option<String> {
+        let mut cached = self
+            .encryption_key
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if cached.is_none() {
+            *cached = encryption::load_key();
+        }
+        cached.clone()
+    }
+
+    fn set_encryption_key(&self, key: Option<String>) {
+        *self
+            .encryption_key
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = key;
+    }
+
+    fn open_connection(&self) -> std::result::Result<DbConnection<'_>, String> {
+        self.get_connection()
+            .map_err(|e| format!("Database connection error: {}", e))
+    }
+
+    /// Directory holding the database; app-managed files such as images live beside it.
+    fn data_dir(&self) -> PathBuf {
+        self.db_path
+            .parent()
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    fn images_dir(&self) -> PathBuf {
+        self.data_dir().join("images")
+    }
 }
 
 fn initialize_schema(conn: &Connection) -> Result<()> {
@@ -301,6 +454,98 @@ fn initialize_schema(conn: &Connection) -> Result<()> {
             expires_at DATETIME
         );
 
+        CREATE TABLE IF NOT EXISTS meal_plans (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS meal_plan_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            plan_id INTEGER NOT NULL,
+            plan_date TEXT NOT NULL,
+            meal_slot TEXT NOT NULL CHECK (meal_slot IN ('breakfast', 'lunch', 'dinner', 'snack')),
+            recipe_id INTEGER NOT NULL,
+            servings REAL NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (plan_id) REFERENCES meal_plans(id) ON DELETE CASCADE,
+            FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS ingredients_inventory (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            quantity REAL,
+            unit TEXT,
+            purchase_date TEXT,
+            expiry_date TEXT,
+            notes TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS recipe_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recipe_id INTEGER NOT NULL,
+            revision_number INTEGER NOT NULL,
+            source TEXT NOT NULL DEFAULT 'edit',
+            snapshot TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (recipe_id, revision_number),
+            FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS recipe_views (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recipe_id INTEGER NOT NULL,
+            session_id TEXT,
+            viewed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS cooking_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recipe_id INTEGER NOT NULL,
+            step_index INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'paused', 'completed', 'abandoned')),
+            started_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            finished_at DATETIME,
+            FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS cooking_timers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            cooking_session_id INTEGER NOT NULL,
+            step_index INTEGER,
+            label TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            remaining_ms INTEGER NOT NULL,
+            deadline_unix_ms INTEGER,
+            status TEXT NOT NULL CHECK (status IN ('running', 'paused', 'held', 'finished', 'dismissed')),
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (cooking_session_id) REFERENCES cooking_sessions(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS cook_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recipe_id INTEGER NOT NULL,
+            session_id TEXT NOT NULL,
+            cooked_on TEXT NOT NULL,
+            rating INTEGER CHECK (rating BETWEEN 1 AND 5),
+            notes TEXT,
+            modifications TEXT,
+            photo_url TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+        );
+
         CREATE INDEX IF NOT EXISTS idx_users_session_id ON users(session_id);
         CREATE INDEX IF NOT EXISTS idx_recipes_title ON recipes(title);
         CREATE INDEX IF NOT EXISTS idx_recipes_category ON recipes(category);
@@ -308,20 +553,184 @@ fn initialize_schema(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_favorites_session_id ON favorites(session_id);
         CREATE INDEX IF NOT EXISTS idx_favorites_recipe_id ON favorites(recipe_id);
         CREATE INDEX IF NOT EXISTS idx_search_history_session_id ON search_history(session_id);
+        CREATE INDEX IF NOT EXISTS idx_search_history_search_time ON search_history(session_id, search_time);
         CREATE INDEX IF NOT EXISTS idx_settings_key ON settings(key);
         CREATE INDEX IF NOT EXISTS idx_settings_category ON settings(category);
         CREATE INDEX IF NOT EXISTS idx_cache_key ON cache(key);
+        CREATE INDEX IF NOT EXISTS idx_meal_plans_session_id ON meal_plans(session_id);
+        CREATE INDEX IF NOT EXISTS idx_meal_plan_entries_plan_date ON meal_plan_entries(plan_id, plan_date);
+        CREATE INDEX IF NOT EXISTS idx_ingredients_inventory_session_expiry ON ingredients_inventory(session_id, expiry_date);
+        CREATE INDEX IF NOT EXISTS idx_recipe_revisions_recipe_id ON recipe_revisions(recipe_id, revision_number);
+        CREATE INDEX IF NOT EXISTS idx_recipe_views_viewed_at ON recipe_views(viewed_at, recipe_id);
+        CREATE INDEX IF NOT EXISTS idx_recipe_views_session_id ON recipe_views(session_id, viewed_at);
+        CREATE INDEX IF NOT EXISTS idx_cooking_sessions_status ON cooking_sessions(status);
+        CREATE INDEX IF NOT EXISTS idx_cooking_timers_session_id ON cooking_timers(cooking_session_id);
+        CREATE INDEX IF NOT EXISTS idx_cook_log_recipe_id ON cook_log(recipe_id, cooked_on);
+        CREATE INDEX IF NOT EXISTS idx_cook_log_session_id ON cook_log(session_id, cooked_on);
+
+        CREATE TRIGGER IF NOT EXISTS trg_recipes_revision_insert
+        AFTER INSERT ON recipes
+        BEGIN
+            INSERT INTO recipe_revisions (recipe_id, revision_number, source, snapshot)
+            VALUES (
+                NEW.id,
+                1,
+                'created',
+                json_object(
+                    'title', NEW.title,
+                    'description', NEW.description,
+                    'ingredients', NEW.ingredients,
+                    'instructions', NEW.instructions,
+                    'cooking_time', NEW.cooking_time,
+                    'difficulty', NEW.difficulty,
+                    'servings', NEW.servings,
+                    'category', NEW.category,
+                    'tags', NEW.tags,
+                    'nutrition_info', NEW.nutrition_info,
+                    'image_url', NEW.image_url,
+                    'cooking_methods', NEW.cooking_methods,
+                    'ai_provider', NEW.ai_provider,
+                    'ai_model', NEW.ai_model
+                )
+            );
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_recipes_revision_update
+        AFTER UPDATE OF title, description, ingredients, instructions, cooking_time, difficulty, servings, category, tags, nutrition_info, image_url, cooking_methods, ai_provider, ai_model ON recipes
+        WHEN NEW.title IS NOT OLD.title
+            OR NEW.description IS NOT OLD.description
+            OR NEW.ingredients IS NOT OLD.ingredients
+            OR NEW.instructions IS NOT OLD.instructions
+            OR NEW.cooking_time IS NOT OLD.cooking_time
+            OR NEW.difficulty IS NOT OLD.difficulty
+            OR NEW.servings IS NOT OLD.servings
+            OR NEW.category IS NOT OLD.category
+            OR NEW.tags IS NOT OLD.tags
+            OR NEW.nutrition_info IS NOT OLD.nutrition_info
+            OR NEW.image_url IS NOT OLD.image_url
+            OR NEW.cooking_methods IS NOT OLD.cooking_methods
+            OR NEW.ai_provider IS NOT OLD.ai_provider
+            OR NEW.ai_model IS NOT OLD.ai_model
+        BEGIN
+            INSERT INTO recipe_revisions (recipe_id, revision_number, source, snapshot)
+            VALUES (
+                NEW.id,
+                (SELECT COALESCE(MAX(revision_number), 0) + 1 FROM recipe_revisions WHERE recipe_id = NEW.id),
+                'edit',
+                json_object(
+                    'title', NEW.title,
+                    'description', NEW.description,
+                    'ingredients', NEW.ingredients,
+                    'instructions', NEW.instructions,
+                    'cooking_time', NEW.cooking_time,
+                    'difficulty', NEW.difficulty,
+                    'servings', NEW.servings,
+                    'category', NEW.category,
+                    'tags', NEW.tags,
+                    'nutrition_info', NEW.nutrition_info,
+                    'image_url', NEW.image_url,
+                    'cooking_methods', NEW.cooking_methods,
+                    'ai_provider', NEW.ai_provider,
+                    'ai_model', NEW.ai_model
+                )
+            );
+            UPDATE recipes SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+        END;
+
+        -- Recipes saved before revision tracking existed get their current state as revision 1.
+        INSERT INTO recipe_revisions (recipe_id, revision_number, source, snapshot)
+        SELECT
+            id,
+            1,
+            'created',
+            json_object(
+                    'title', title,
+                    'description', description,
+                    'ingredients', ingredients,
+                    'instructions', instructions,
+                    'cooking_time', cooking_time,
+                    'difficulty', difficulty,
+                    'servings', servings,
+                    'category', category,
+                    'tags', tags,
+                    'nutrition_info', nutrition_info,
+                    'image_url', image_url,
+                    'cooking_methods', cooking_methods,
+                    'ai_provider', ai_provider,
+                    'ai_model', ai_model
+                )
+        FROM recipes
+        WHERE NOT EXISTS (SELECT 1 FROM recipe_revisions WHERE recipe_revisions.recipe_id = recipes.id);
         "#,
+    )?;
+
+    ensure_column(
+        conn,
+        "recipes",
+        "parent_recipe_id",
+        "INTEGER REFERENCES recipes(id) ON DELETE SET NULL",
+    )?;
+    ensure_column(conn, "recipes", "variation_request", "TEXT")?;
+    ensure_column(conn, "users", "display_name", "TEXT")?;
+    ensure_column(conn, "users", "pin_hash", "TEXT")?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_recipes_parent_recipe_id ON recipes(parent_recipe_id);",
     )
 }
 
+/// Adds a column to a table created by an earlier release; `CREATE TABLE IF NOT EXISTS`
+/// leaves existing tables untouched.
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{table}') WHERE name = ?1"),
+        [column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN {column} {definition};"
+        ))?;
+    }
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .setup(|app| {
             println!("=== ChefMind Tauri App Starting ===");
             let db_state = DatabaseState::new(app);
+            let profile_state = profiles::ProfileState::default();
+            match db_state
+                .open_connection()
+                .and_then(|conn| profiles::restore(&conn))
+            {
+                Ok(active) => profile_state.set_active(active),
+                Err(e) => eprintln!("Failed to restore the active profile: {}", e),
+            }
             app.manage(db_state);
+            app.manage(profile_state);
+            app.manage(cooking::CookingState::default());
+            app.manage(tray::TrayState::default());
+            #[cfg(not(mobile))]
+            {
+                if let Err(e) = tray::build(app.handle()) {
+                    eprintln!("Failed to create tray icon: {}", e);
+                }
+            }
+            cooking::start(app.handle());
+            credential_health::start(app.handle());
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let db = handle.state::<DatabaseState>();
+                let result = db.open_connection().and_then(|conn| {
+                    images::collect_garbage(&conn, &db.images_dir(), images::GC_GRACE_PERIOD)
+                });
+                if let Err(e) = result {
+                    eprintln!("Failed to collect orphaned images: {}", e);
+                }
+            });
 
             if let Some(window) = app.get_webview_window("main") {
                 #[cfg(not(mobile))]
@@ -367,6 +776,12 @@ pub fn run() {
             println!("Tauri app setup completed successfully");
             Ok(())
         })
+        .register_uri_scheme_protocol(images::IMAGE_SCHEME, |ctx, request| {
+            match ctx.app_handle().try_state::<DatabaseState>() {
+                Some(db) => images::protocol_response(&db.images_dir(), &request),
+                None => images::plain_response(tauri::http::StatusCode::SERVICE_UNAVAILABLE),
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             log_message,
             get_app_info,
@@ -381,9 +796,93 @@ pub fn run() {
             test_provider_configuration,
             database_query,
             database_query_one,
-            database_execute
+            database_execute,
+            health::health_profile_save,
+            health::health_profile_get,
+            health::health_metrics,
+            meal_plan::meal_plan_create,
+            meal_plan::meal_plan_list,
+            meal_plan::meal_plan_delete,
+            meal_plan::meal_plan_entries,
+            meal_plan::meal_plan_add_entry,
+            meal_plan::meal_plan_move_entry,
+            meal_plan::meal_plan_remove_entry,
+            meal_plan::meal_plan_weekly_summary,
+            meal_plan::meal_plan_shopping_list,
+            pantry::pantry_list,
+            pantry::pantry_add,
+            pantry::pantry_update,
+            pantry::pantry_remove,
+            pantry::pantry_expiring,
+            pantry::pantry_recipe_coverage,
+            matching::recipe_match_ingredients,
+            dietary::recipe_check_dietary,
+            dietary::recipe_save_generated,
+            revisions::recipe_update,
+            revisions::recipe_history,
+            revisions::recipe_revert,
+            revisions::recipe_diff,
+            variations::ai_generate_variation,
+            variations::recipe_family,
+            importer::recipe_import,
+            exporter::recipe_export,
+            bundle::bundle_export,
+            bundle::bundle_import,
+            external_import::recipe_import_external,
+            images::recipe_attach_image,
+            images::recipe_remove_image,
+            images::image_collect_garbage,
+            recognition::ai_recognize_ingredients,
+            search_history::search_history_record,
+            search_history::search_history_frequent_ingredients,
+            search_history::search_history_recent,
+            search_history::search_history_zero_results,
+            search_history::ingredient_suggest,
+            search_history::search_history_retention_get,
+            search_history::search_history_retention_set,
+            popularity::recipe_viewed,
+            popularity::recipe_recently_viewed,
+            popularity::recipe_trending,
+            cooking::cooking_start,
+            cooking::cooking_current,
+            cooking::cooking_go_to_step,
+            cooking::cooking_next_step,
+            cooking::cooking_previous_step,
+            cooking::cooking_pause,
+            cooking::cooking_resume,
+            cooking::cooking_finish,
+            cooking::cooking_abandon,
+            cooking::timer_start,
+            cooking::timer_pause,
+            cooking::timer_resume,
+            cooking::timer_snooze,
+            cooking::timer_dismiss,
+            step_timing::recipe_step_timings,
+            step_timing::cooking_seed_step_timers,
+            cook_log::cook_log_add,
+            cook_log::cook_log_list,
+            cook_log::cook_log_remove,
+            cook_log::cook_log_last_cooked,
+            profiles::profile_list,
+            profiles::profile_active,
+            profiles::profile_create,
+            profiles::profile_switch,
+            profiles::profile_rename,
+            profiles::profile_set_pin,
+            profiles::profile_delete,
+            encryption::database_encryption_status,
+            encryption::database_encrypt,
+            encryption::database_decrypt,
+            encryption::database_recover_key,
+            encryption::database_set_aside_unreadable,
+            credential_transfer::credential_export,
+            credential_transfer::credential_import,
+            credential_health::credential_status,
+            provider_chain::ai_provider_chain_get,
+            provider_chain::ai_provider_chain_set
         ])
         .plugin(tauri_plugin_devtools::init())
+        .plugin(tauri_plugin_notification::init())
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -446,37 +945,64 @@ fn toggle_dev_tools(window: tauri::WebviewWindow) -> Result<bool, String> {
     Ok(true)
 }
 
-#[tauri::command]
-fn credential_store(
-    provider_id: String,
-    api_key: String,
-    base_url: String,
-    model: String,
-) -> std::result::Result<(), String> {
-    validate_provider_id(&provider_id)?;
-    validate_base_url(&base_url)?;
-    if api_key.trim().is_empty() || model.trim().is_empty() || model.len() > 256 {
+/// Checks the base URL, API key and model of a credential about to be saved.
+fn validate_credential(credential: &StoredProviderCredential) -> std::result::Result<(), String> {
+    validate_base_url(&credential.base_url)?;
+    if credential.api_key.trim().is_empty()
+        || credential.model.trim().is_empty()
+        || credential.model.len() > 256
+    {
         return Err("Provider credential is incomplete".to_string());
     }
+    Ok(())
+}
+
+/// Validates and normalises a credential, then saves it in the keyring.
+fn save_credential(
+    provider_id: &str,
+    credential: StoredProviderCredential,
+) -> std::result::Result<(), String> {
+    validate_provider_id(provider_id)?;
+    validate_credential(&credential)?;
 
     let serialized = serde_json::to_string(&StoredProviderCredential {
-        api_key,
-        base_url: base_url.trim().trim_end_matches('/').to_string(),
-        model: model.trim().to_string(),
+        api_key: credential.api_key.clone(),
+        base_url: credential.base_url.trim().trim_end_matches('/').to_string(),
+        model: credential.model.trim().to_string(),
     })
     .map_err(|_| "Unable to prepare the provider credential".to_string())?;
 
-    credential_entry(&provider_id)?
+    credential_entry(provider_id)?
         .set_password(&serialized)
         .map_err(|_| {
             "Unable to save the provider credential in the operating system store".to_string()
-        })
+        })?;
+    credential_health::record_saved(provider_id, &credential.api_key);
+    Ok(())
+}
+
+#[tauri::command]
+fn credential_store(
+    provider_id: String,
+    api_key: String,
+    base_url: String,
+    model: String,
+) -> std::result::Result<(), String> {
+    save_credential(
+        &provider_id,
+        StoredProviderCredential {
+            api_key,
+            base_url,
+            model,
+        },
+    )
 }
 
 #[tauri::command]
 fn credential_delete(provider_id: String) -> std::result::Result<(), String> {
     let entry = credential_entry(&provider_id)?;
     let _ = entry.delete_credential();
+    credential_health::forget(&provider_id);
     Ok(())
 }
 
@@ -484,11 +1010,22 @@ fn credential_delete(provider_id: String) -> std::result::Result<(), String> {
 async fn ai_chat_completion(
     provider_id: String,
     prompt: String,
-    max_tokens: u32,
-    temperature: f64,
-) -> std::result::Result<String, String> {
-    let credential = read_credential(&provider_id)?;
-    request_completion(&credential, &prompt, max_tokens, temperature).await
+    options: CompletionOptions,
+    app: tauri::AppHandle,
+    db: State<'_, DatabaseState>,
+) -> std::result::Result<provider_chain::ProviderAnswer<provider_chain::ChatCompletion>, String> {
+    validate_provider_id(&provider_id)?;
+    let chain = provider_chain::load_chain(&*db.open_connection()?)?;
+    let order = provider_chain::attempt_order(&provider_id, &chain);
+    provider_chain::complete_with_fallback(&app, &order, |provider_id, credential| {
+        let (prompt, options) = (&prompt, &options);
+        async move {
+            request_completion(&provider_id, &credential, prompt, options)
+                .await
+                .map(|content| provider_chain::ChatCompletion { content })
+        }
+    })
+    .await
 }
 
 #[tauri::command]
@@ -496,15 +1033,32 @@ async fn test_provider_configuration(
     api_key: String,
     base_url: String,
     model: String,
+    provider_id: Option<String>,
 ) -> std::result::Result<(), String> {
+    let provider_id =
+        provider_id.unwrap_or_else(|| provider_params::GENERIC_PROVIDER_ID.to_string());
+    validate_provider_id(&provider_id)?;
     let credential = StoredProviderCredential {
         api_key,
         base_url,
         model,
     };
-    request_completion(&credential, "请只回复“连接成功”。", 16, 0.0)
-        .await
-        .map(|_| ())
+    check_credential(&provider_id, &credential).await
+}
+
+/// Sends the smallest useful request; shared with the background credential check.
+async fn check_credential(
+    provider_id: &str,
+    credential: &StoredProviderCredential,
+) -> std::result::Result<(), String> {
+    request_completion(
+        provider_id,
+        credential,
+        "请只回复“连接成功”。",
+        &CompletionOptions::new(16, 0.0),
+    )
+    .await
+    .map(|_| ())
 }
 
 #[tauri::command]
@@ -857,7 +1411,11 @@ fn database_execute(
 
 #[cfg(test)]
 mod tests {
-    use super::{completion_url, initialize_schema, validate_base_url};
+    use super::{
+        chat_request, completion_url, initialize_schema, validate_base_url,
+        StoredProviderCredential,
+    };
+    use crate::provider_params::CompletionOptions;
     use rusqlite::Connection;
 
     #[test]
@@ -865,6 +1423,7 @@ mod tests {
         let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
 
         initialize_schema(&conn).expect("initialize schema");
+        initialize_schema(&conn).expect("re-run schema migrations");
 
         for table_name in [
             "users",
@@ -873,6 +1432,14 @@ mod tests {
             "search_history",
             "settings",
             "cache",
+            "meal_plans",
+            "meal_plan_entries",
+            "ingredients_inventory",
+            "recipe_revisions",
+            "recipe_views",
+            "cooking_sessions",
+            "cooking_timers",
+            "cook_log",
         ] {
             let count: i64 = conn
                 .query_row(
@@ -886,6 +1453,7 @@ mod tests {
 
         for index_name in [
             "idx_recipes_created_at",
+            "idx_recipes_parent_recipe_id",
             "idx_favorites_session_id",
             "idx_settings_key",
             "idx_cache_key",
@@ -966,4 +1534,52 @@ mod tests {
             "https://api.example.com/v1/chat/completions"
         );
     }
+
+    #[test]
+    fn shapes_requests_for_the_provider_behind_the_credential() {
+        let options = CompletionOptions {
+            seed: Some(7),
+            ..CompletionOptions::new(100_000, 1.8)
+        };
+        let deepseek = StoredProviderCredential {
+            api_key: "sk-test".to_string(),
+            base_url: "https://api.deepseek.com/v1".to_string(),
+            model: "deepseek-chat".to_string(),
+        };
+        // Older builds stored every key as "openai"; the endpoint still decides.
+        for provider_id in ["deepseek", "openai", "custom"] {
+            let (endpoint, body) =
+                chat_request(provider_id, &deepseek, serde_json::json!("你好"), &options)
+                    .expect("build request");
+            assert_eq!(
+                endpoint.as_str(),
+                "https://api.deepseek.com/v1/chat/completions"
+            );
+            assert_eq!(body["max_tokens"], 8_192, "{provider_id}");
+            assert!(body.get("seed").is_none(), "{provider_id}");
+        }
+
+        let zhipu = StoredProviderCredential {
+            base_url: "https://open.bigmodel.cn/api/paas/v4".to_string(),
+            model: "glm-4-flash".to_string(),
+            ..deepseek
+        };
+        let (_, body) = chat_request("zhipu", &zhipu, serde_json::json!("你好"), &options)
+            .expect("build request");
+        assert_eq!(body["max_tokens"], 4_095);
+        assert_eq!(body["temperature"], 1.0);
+
+        let proxy = StoredProviderCredential {
+            base_url: "https://llm.example.com/v1".to_string(),
+            model: "glm-4-flash".to_string(),
+            ..zhipu
+        };
+        let (_, body) = chat_request("zhipu", &proxy, serde_json::json!("你好"), &options)
+            .expect("build request");
+        assert_eq!(body["max_tokens"], 4_095);
+        let (_, body) = chat_request("custom", &proxy, serde_json::json!("你好"), &options)
+            .expect("build request");
+        assert_eq!(body["max_tokens"], 16_384);
+        assert_eq!(body["seed"], 7);
+    }
 }
@@ -0,0 +1,278 @@
+use crate::dietary::{check_ingredients, DietaryRules};
+use crate::recipe::{load_all_recipes, StoredRecipe};
+use crate::DatabaseState;
+use serde::Serialize;
+use tauri::State;
+
+// Each group lists interchangeable names; the first entry is the canonical one.
+const SYNONYM_GROUPS: &[&[&str]] = &[
+    &["番茄", "西红柿", "tomato"],
+    &["土豆", "马铃薯", "洋芋", "potato"],
+    &["鸡蛋", "鸡子", "egg", "eggs"],
+    &["红薯", "地瓜", "番薯", "甘薯", "sweet potato"],
+    &["玉米", "苞米", "包谷", "corn"],
+    &["花生", "落花生", "peanut", "peanuts"],
+    &["香菜", "芫荽", "coriander", "cilantro"],
+    &["茄子", "矮瓜", "eggplant", "aubergine"],
+    &["青椒", "甜椒", "菜椒", "柿子椒", "bell pepper"],
+    &["卷心菜", "包菜", "圆白菜", "洋白菜", "甘蓝", "cabbage"],
+    &["西兰花", "西蓝花", "绿花菜", "broccoli"],
+    &["黄瓜", "青瓜", "cucumber"],
+    &["胡萝卜", "红萝卜", "carrot"],
+    &["白萝卜", "萝卜", "radish"],
+    &["洋葱", "圆葱", "onion"],
+    &["蘑菇", "口蘑", "mushroom"],
+    &["豆腐", "tofu"],
+    &["猪肉", "pork"],
+    &["牛肉", "beef"],
+    &["鸡肉", "chicken"],
+    &["虾仁", "虾", "shrimp", "prawn"],
+    &["酱油", "生抽", "soy sauce"],
+    &["白糖", "砂糖", "白砂糖", "sugar"],
+    &["大米", "米饭", "rice"],
+];
+
+// Seasonings most kitchens keep on hand; they never count as missing.
+const PANTRY_STAPLES: &[&str] = &[
+    "盐",
+    "食盐",
+    "油",
+    "食用油",
+    "植物油",
+    "水",
+    "清水",
+    "白糖",
+    "酱油",
+    "醋",
+    "料酒",
+    "胡椒粉",
+    "味精",
+    "鸡精",
+    "淀粉",
+    "葱",
+    "姜",
+    "蒜",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeMatch {
+    pub recipe_id: i64,
+    pub title: String,
+    pub score: f64,
+    pub matched: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Maps a free-form ingredient name onto its canonical synonym, e.g. "新鲜西红柿" -> "番茄".
+pub(crate) fn canonical_ingredient(name: &str) -> String {
+    let normalized = normalize(name);
+    let mut best: Option<(&str, usize)> = None;
+    for group in SYNONYM_GROUPS {
+        for term in group.iter() {
+            let term_normalized = normalize(term);
+            let length = term_normalized.chars().count();
+            let hit = normalized == term_normalized
+                || (length >= 2 && normalized.contains(&term_normalized));
+            if hit && best.is_none_or(|(_, best_length)| length > best_length) {
+                best = Some((group[0], length));
+            }
+        }
+    }
+    best.map(|(canonical, _)| canonical.to_string())
+        .unwrap_or(normalized)
+}
+
+/// Synonym-aware name comparison: "西红柿" matches "番茄", "鸡蛋" matches "土鸡蛋",
+/// but single-character names such as "盐" must match exactly.
+pub(crate) fn ingredient_names_match(left: &str, right: &str) -> bool {
+    let (left, right) = (canonical_ingredient(left), canonical_ingredient(right));
+    if left.is_empty() || right.is_empty() {
+        return false;
+    }
+    if left == right {
+        return true;
+    }
+    let (shorter, longer) = if left.chars().count() <= right.chars().count() {
+        (&left, &right)
+    } else {
+        (&right, &left)
+    };
+    shorter.chars().count() >= 2 && longer.contains(shorter.as_str())
+}
+
+fn is_pantry_staple(name: &str) -> bool {
+    let normalized = normalize(name);
+    PANTRY_STAPLES
+        .iter()
+        .any(|staple| normalized == *staple || canonical_ingredient(&normalized) == *staple)
+}
+
+fn violates_rules(recipe: &StoredRecipe, rules: &DietaryRules) -> bool {
+    if rules.is_empty() {
+        return false;
+    }
+    let names: Vec<String> = recipe
+        .ingredient_list()
+        .into_iter()
+        .map(|ingredient| ingredient.name)
+        .collect();
+    let sodium = recipe.nutrition().map(|facts| facts.sodium);
+    !check_ingredients(rules, &names, sodium).is_empty()
+}
+
+pub(crate) fn match_recipes(
+    recipes: &[StoredRecipe],
+    available: &[String],
+    dietary_restrictions: &[String],
+    allergens: &[String],
+    limit: usize,
+) -> Vec<RecipeMatch> {
+    let rules = DietaryRules::from_labels(allergens, dietary_restrictions);
+
+    let mut matches: Vec<RecipeMatch> = recipes
+        .iter()
+        .filter(|recipe| !violates_rules(recipe, &rules))
+        .filter_map(|recipe| {
+            let mut matched = Vec::new();
+            let mut missing = Vec::new();
+            for ingredient in recipe.ingredient_list() {
+                if available
+                    .iter()
+                    .any(|have| ingredient_names_match(have, &ingredient.name))
+                {
+                    matched.push(ingredient.name);
+                } else if !is_pantry_staple(&ingredient.name) {
+                    missing.push(ingredient.name);
+                }
+            }
+            if matched.is_empty() {
+                return None;
+            }
+            Some(RecipeMatch {
+                recipe_id: recipe.id,
+                title: recipe.title.clone(),
+                score: matched.len() as f64 / (matched.len() + missing.len()) as f64,
+                matched,
+                missing,
+            })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.matched.len().cmp(&a.matched.len()))
+            .then(a.recipe_id.cmp(&b.recipe_id))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+/// Scores stored recipes against the ingredients on hand so the UI only needs to call
+/// the AI provider when nothing suitable is already saved.
+#[tauri::command]
+pub(crate) fn recipe_match_ingredients(
+    ingredients: Vec<String>,
+    dietary_restrictions: Option<Vec<String>>,
+    allergens: Option<Vec<String>>,
+    limit: Option<usize>,
+    db: State<DatabaseState>,
+) -> Result<Vec<RecipeMatch>, String> {
+    let available: Vec<String> = ingredients
+        .into_iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if available.is_empty() || available.len() > 100 {
+        return Err("Provide between 1 and 100 ingredients".to_string());
+    }
+
+    let conn = db.open_connection()?;
+    let recipes = load_all_recipes(&conn)?;
+    Ok(match_recipes(
+        &recipes,
+        &available,
+        &dietary_restrictions.unwrap_or_default(),
+        &allergens.unwrap_or_default(),
+        limit.unwrap_or(10).min(50),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(id: i64, title: &str, ingredients: &str) -> StoredRecipe {
+        StoredRecipe {
+            id,
+            title: title.to_string(),
+            description: None,
+            ingredients: ingredients.to_string(),
+            instructions: "[]".to_string(),
+            cooking_time: None,
+            difficulty: None,
+            servings: None,
+            category: None,
+            tags: None,
+            nutrition_info: None,
+            image_url: None,
+            cooking_methods: None,
+            ai_provider: None,
+            ai_model: None,
+        }
+    }
+
+    #[test]
+    fn matches_synonyms_in_both_directions() {
+        assert_eq!(canonical_ingredient("新鲜西红柿"), "番茄");
+        assert!(ingredient_names_match("西红柿", "番茄 2个"));
+        assert!(ingredient_names_match("马铃薯", "土豆"));
+        assert!(ingredient_names_match("鸡蛋", "土鸡蛋"));
+        assert!(!ingredient_names_match("盐", "盐焗鸡"));
+        assert!(!ingredient_names_match("番茄", "土豆"));
+    }
+
+    #[test]
+    fn ranks_recipes_and_reports_missing_ingredients() {
+        let recipes = vec![
+            recipe(1, "番茄炒蛋", r#"["番茄 2个", "鸡蛋 3个", "盐 适量"]"#),
+            recipe(2, "土豆烧牛肉", r#"["土豆 2个", "牛肉 300g", "生抽 1勺"]"#),
+            recipe(
+                3,
+                "花生酱拌面",
+                r#"["面条 200g", "花生酱 2勺", "鸡蛋 1个"]"#,
+            ),
+        ];
+        let available = vec![
+            "西红柿".to_string(),
+            "鸡蛋".to_string(),
+            "马铃薯".to_string(),
+        ];
+
+        let ranked = match_recipes(&recipes, &available, &[], &[], 10);
+        let ids: Vec<_> = ranked.iter().map(|candidate| candidate.recipe_id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(ranked[0].score, 1.0);
+        assert_eq!(ranked[1].missing, vec!["牛肉"]);
+
+        let filtered = match_recipes(
+            &recipes,
+            &available,
+            &["素食".to_string()],
+            &["花生".to_string()],
+            10,
+        );
+        let ids: Vec<_> = filtered
+            .iter()
+            .map(|candidate| candidate.recipe_id)
+            .collect();
+        assert_eq!(ids, vec![1]);
+    }
+}
@@ -0,0 +1,566 @@
+use crate::health::{calculate_health_metrics, load_health_profile, MacroTarget};
+use crate::profiles::{ensure_owned, ProfileState};
+use crate::recipe::{require_recipe, NutritionFacts, StoredRecipe, RECIPE_COLUMNS};
+use crate::{validate_date, DatabaseState};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+
+const MEAL_SLOTS: [&str; 4] = ["breakfast", "lunch", "dinner", "snack"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MealPlan {
+    pub id: i64,
+    pub session_id: String,
+    pub name: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MealPlanEntry {
+    pub id: i64,
+    pub plan_id: i64,
+    pub plan_date: String,
+    pub meal_slot: String,
+    pub recipe_id: i64,
+    pub recipe_title: String,
+    pub servings: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyNutrition {
+    pub date: String,
+    pub totals: NutritionFacts,
+    pub entries_without_nutrition: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WeeklyNutritionSummary {
+    pub plan_id: i64,
+    pub week_start: String,
+    pub week_end: String,
+    pub days: Vec<DailyNutrition>,
+    pub weekly_totals: NutritionFacts,
+    pub daily_average: NutritionFacts,
+    pub daily_target: Option<MacroTarget>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShoppingListItem {
+    pub name: String,
+    pub amount: Option<f64>,
+    pub unit: Option<String>,
+    pub recipes: Vec<String>,
+}
+
+fn validate_meal_slot(meal_slot: &str) -> Result<(), String> {
+    if MEAL_SLOTS.contains(&meal_slot) {
+        Ok(())
+    } else {
+        Err("Meal slot must be breakfast, lunch, dinner or snack".to_string())
+    }
+}
+
+fn validate_servings(servings: f64) -> Result<(), String> {
+    if servings.is_finite() && servings > 0.0 && servings <= 100.0 {
+        Ok(())
+    } else {
+        Err("Servings must be between 0 and 100".to_string())
+    }
+}
+
+fn week_end(conn: &Connection, week_start: &str) -> Result<String, String> {
+    conn.query_row("SELECT date(?1, '+6 days')", [week_start], |row| row.get(0))
+        .map_err(|e| format!("Failed to compute week range: {}", e))
+}
+
+fn require_plan(conn: &Connection, plan_id: i64) -> Result<MealPlan, String> {
+    conn.query_row(
+        "SELECT id, session_id, name, created_at, updated_at FROM meal_plans WHERE id = ?1",
+        [plan_id],
+        |row| {
+            Ok(MealPlan {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                name: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load meal plan: {}", e))?
+    .ok_or_else(|| "Meal plan not found".to_string())
+}
+
+fn touch_plan(conn: &Connection, plan_id: i64) -> Result<(), String> {
+    conn.execute(
+        "UPDATE meal_plans SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        [plan_id],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to update meal plan: {}", e))
+}
+
+pub(crate) fn create_plan(
+    conn: &Connection,
+    session_id: &str,
+    name: &str,
+) -> Result<MealPlan, String> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > 128 {
+        return Err("Meal plan name is invalid".to_string());
+    }
+    conn.execute(
+        "INSERT INTO meal_plans (session_id, name) VALUES (?1, ?2)",
+        params![session_id, name],
+    )
+    .map_err(|e| format!("Failed to create meal plan: {}", e))?;
+    require_plan(conn, conn.last_insert_rowid())
+}
+
+fn list_plans(conn: &Connection, session_id: &str) -> Result<Vec<MealPlan>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, session_id, name, created_at, updated_at FROM meal_plans
+             WHERE session_id = ?1 ORDER BY updated_at DESC, id DESC",
+        )
+        .map_err(|e| format!("Failed to list meal plans: {}", e))?;
+    let plans = stmt
+        .query_map([session_id], |row| {
+            Ok(MealPlan {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                name: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to list meal plans: {}", e))?;
+    Ok(plans)
+}
+
+pub(crate) fn add_entry(
+    conn: &Connection,
+    plan_id: i64,
+    plan_date: &str,
+    meal_slot: &str,
+    recipe_id: i64,
+    servings: f64,
+) -> Result<MealPlanEntry, String> {
+    require_plan(conn, plan_id)?;
+    validate_date(conn, plan_date)?;
+    validate_meal_slot(meal_slot)?;
+    validate_servings(servings)?;
+    require_recipe(conn, recipe_id)?;
+
+    conn.execute(
+        "INSERT INTO meal_plan_entries (plan_id, plan_date, meal_slot, recipe_id, servings)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![plan_id, plan_date, meal_slot, recipe_id, servings],
+    )
+    .map_err(|e| format!("Failed to add meal plan entry: {}", e))?;
+    let entry_id = conn.last_insert_rowid();
+    touch_plan(conn, plan_id)?;
+    require_entry(conn, entry_id)
+}
+
+fn require_entry(conn: &Connection, entry_id: i64) -> Result<MealPlanEntry, String> {
+    conn.query_row(
+        "SELECT e.id, e.plan_id, e.plan_date, e.meal_slot, e.recipe_id, r.title, e.servings
+         FROM meal_plan_entries e JOIN recipes r ON r.id = e.recipe_id
+         WHERE e.id = ?1",
+        [entry_id],
+        entry_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load meal plan entry: {}", e))?
+    .ok_or_else(|| "Meal plan entry not found".to_string())
+}
+
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<MealPlanEntry> {
+    Ok(MealPlanEntry {
+        id: row.get(0)?,
+        plan_id: row.get(1)?,
+        plan_date: row.get(2)?,
+        meal_slot: row.get(3)?,
+        recipe_id: row.get(4)?,
+        recipe_title: row.get(5)?,
+        servings: row.get(6)?,
+    })
+}
+
+fn move_entry(
+    conn: &Connection,
+    entry_id: i64,
+    plan_date: &str,
+    meal_slot: &str,
+    servings: Option<f64>,
+) -> Result<MealPlanEntry, String> {
+    let entry = require_entry(conn, entry_id)?;
+    validate_date(conn, plan_date)?;
+    validate_meal_slot(meal_slot)?;
+    let servings = servings.unwrap_or(entry.servings);
+    validate_servings(servings)?;
+
+    conn.execute(
+        "UPDATE meal_plan_entries
+         SET plan_date = ?1, meal_slot = ?2, servings = ?3, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?4",
+        params![plan_date, meal_slot, servings, entry_id],
+    )
+    .map_err(|e| format!("Failed to move meal plan entry: {}", e))?;
+    touch_plan(conn, entry.plan_id)?;
+    require_entry(conn, entry_id)
+}
+
+pub(crate) fn entries_between(
+    conn: &Connection,
+    plan_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<MealPlanEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT e.id, e.plan_id, e.plan_date, e.meal_slot, e.recipe_id, r.title, e.servings
+             FROM meal_plan_entries e JOIN recipes r ON r.id = e.recipe_id
+             WHERE e.plan_id = ?1 AND e.plan_date BETWEEN ?2 AND ?3
+             ORDER BY e.plan_date,
+                 CASE e.meal_slot WHEN 'breakfast' THEN 0 WHEN 'lunch' THEN 1 WHEN 'dinner' THEN 2 ELSE 3 END,
+                 e.id",
+        )
+        .map_err(|e| format!("Failed to load meal plan entries: {}", e))?;
+    let entries = stmt
+        .query_map(params![plan_id, start_date, end_date], entry_from_row)
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load meal plan entries: {}", e))?;
+    Ok(entries)
+}
+
+fn planned_recipes(
+    conn: &Connection,
+    entries: &[MealPlanEntry],
+) -> Result<BTreeMap<i64, StoredRecipe>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {RECIPE_COLUMNS} FROM recipes WHERE id = ?1"
+        ))
+        .map_err(|e| format!("Failed to load planned recipes: {}", e))?;
+    let mut recipes = BTreeMap::new();
+    for entry in entries {
+        if recipes.contains_key(&entry.recipe_id) {
+            continue;
+        }
+        let recipe = stmt
+            .query_row([entry.recipe_id], StoredRecipe::from_row)
+            .map_err(|e| format!("Failed to load planned recipes: {}", e))?;
+        recipes.insert(entry.recipe_id, recipe);
+    }
+    Ok(recipes)
+}
+
+pub(crate) fn weekly_summary(
+    conn: &Connection,
+    plan_id: i64,
+    week_start: &str,
+) -> Result<WeeklyNutritionSummary, String> {
+    let plan = require_plan(conn, plan_id)?;
+    validate_date(conn, week_start)?;
+    let week_end = week_end(conn, week_start)?;
+    let entries = entries_between(conn, plan_id, week_start, &week_end)?;
+    let recipes = planned_recipes(conn, &entries)?;
+
+    let mut days: Vec<DailyNutrition> = Vec::new();
+    let mut weekly_totals = NutritionFacts::default();
+    for entry in &entries {
+        if days.last().map(|day| day.date.as_str()) != Some(entry.plan_date.as_str()) {
+            days.push(DailyNutrition {
+                date: entry.plan_date.clone(),
+                totals: NutritionFacts::default(),
+                entries_without_nutrition: 0,
+            });
+        }
+        let day = days.last_mut().expect("day was just pushed");
+        let recipe = &recipes[&entry.recipe_id];
+        match recipe.nutrition() {
+            // `nutrition_info` is per serving, unlike ingredient amounts which are
+            // for `base_servings`.
+            Some(per_serving) => {
+                let planned = per_serving.scaled(entry.servings);
+                day.totals.add(planned);
+                weekly_totals.add(planned);
+            }
+            None => day.entries_without_nutrition += 1,
+        }
+    }
+
+    let daily_target = load_health_profile(conn, &plan.session_id)?.map(|profile| {
+        let metrics = calculate_health_metrics(&profile);
+        metrics
+            .macro_targets
+            .into_iter()
+            .find(|target| target.goal == metrics.primary_goal)
+            .expect("every goal has a macro target")
+    });
+
+    Ok(WeeklyNutritionSummary {
+        plan_id,
+        week_start: week_start.to_string(),
+        week_end,
+        daily_average: weekly_totals.scaled(1.0 / 7.0),
+        days,
+        weekly_totals,
+        daily_target,
+    })
+}
+
+pub(crate) fn shopping_list(
+    conn: &Connection,
+    plan_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<ShoppingListItem>, String> {
+    require_plan(conn, plan_id)?;
+    validate_date(conn, start_date)?;
+    validate_date(conn, end_date)?;
+    let entries = entries_between(conn, plan_id, start_date, end_date)?;
+    let recipes = planned_recipes(conn, &entries)?;
+
+    // Ingredients merge only when both the name and the unit agree; "适量" style
+    // quantities have no amount and simply collapse into a single line.
+    let mut items: BTreeMap<(String, Option<String>), ShoppingListItem> = BTreeMap::new();
+    for entry in &entries {
+        let recipe = &recipes[&entry.recipe_id];
+        let factor = entry.servings / recipe.base_servings();
+        for ingredient in recipe.ingredient_list() {
+            let key = (ingredient.name.clone(), ingredient.unit.clone());
+            let item = items.entry(key).or_insert_with(|| ShoppingListItem {
+                name: ingredient.name.clone(),
+                amount: None,
+                unit: ingredient.unit.clone(),
+                recipes: Vec::new(),
+            });
+            if let Some(amount) = ingredient.amount {
+                let scaled = (amount * factor * 100.0).round() / 100.0;
+                item.amount = Some(item.amount.unwrap_or(0.0) + scaled);
+            }
+            if !item.recipes.contains(&recipe.title) {
+                item.recipes.push(recipe.title.clone());
+            }
+        }
+    }
+
+    Ok(items.into_values().collect())
+}
+
+const PLAN_OWNER: &str = "SELECT session_id FROM meal_plans WHERE id = ?1";
+const ENTRY_OWNER: &str = "SELECT p.session_id FROM meal_plan_entries e
+     JOIN meal_plans p ON p.id = e.plan_id WHERE e.id = ?1";
+
+fn require_own_plan(
+    conn: &Connection,
+    profiles: &ProfileState,
+    plan_id: i64,
+) -> Result<(), String> {
+    ensure_owned(
+        conn,
+        PLAN_OWNER,
+        plan_id,
+        &profiles.session_id()?,
+        "Meal plan not found",
+    )
+}
+
+fn require_own_entry(
+    conn: &Connection,
+    profiles: &ProfileState,
+    entry_id: i64,
+) -> Result<(), String> {
+    ensure_owned(
+        conn,
+        ENTRY_OWNER,
+        entry_id,
+        &profiles.session_id()?,
+        "Meal plan entry not found",
+    )
+}
+
+#[tauri::command]
+pub(crate) fn meal_plan_create(
+    name: String,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<MealPlan, String> {
+    let conn = db.open_connection()?;
+    create_plan(&conn, &profiles.session_id()?, &name)
+}
+
+#[tauri::command]
+pub(crate) fn meal_plan_list(
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<MealPlan>, String> {
+    let conn = db.open_connection()?;
+    list_plans(&conn, &profiles.session_id()?)
+}
+
+#[tauri::command]
+pub(crate) fn meal_plan_delete(
+    plan_id: i64,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<(), String> {
+    let conn = db.open_connection()?;
+    require_own_plan(&conn, &profiles, plan_id)?;
+    conn.execute("DELETE FROM meal_plans WHERE id = ?1", [plan_id])
+        .map(|_| ())
+        .map_err(|e| format!("Failed to delete meal plan: {}", e))
+}
+
+#[tauri::command]
+pub(crate) fn meal_plan_entries(
+    plan_id: i64,
+    start_date: String,
+    end_date: String,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<MealPlanEntry>, String> {
+    let conn = db.open_connection()?;
+    require_own_plan(&conn, &profiles, plan_id)?;
+    validate_date(&conn, &start_date)?;
+    validate_date(&conn, &end_date)?;
+    entries_between(&conn, plan_id, &start_date, &end_date)
+}
+
+#[tauri::command]
+pub(crate) fn meal_plan_add_entry(
+    plan_id: i64,
+    plan_date: String,
+    meal_slot: String,
+    recipe_id: i64,
+    servings: f64,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<MealPlanEntry, String> {
+    let conn = db.open_connection()?;
+    require_own_plan(&conn, &profiles, plan_id)?;
+    add_entry(&conn, plan_id, &plan_date, &meal_slot, recipe_id, servings)
+}
+
+#[tauri::command]
+pub(crate) fn meal_plan_move_entry(
+    entry_id: i64,
+    plan_date: String,
+    meal_slot: String,
+    servings: Option<f64>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<MealPlanEntry, String> {
+    let conn = db.open_connection()?;
+    require_own_entry(&conn, &profiles, entry_id)?;
+    move_entry(&conn, entry_id, &plan_date, &meal_slot, servings)
+}
+
+#[tauri::command]
+pub(crate) fn meal_plan_remove_entry(
+    entry_id: i64,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<(), String> {
+    let conn = db.open_connection()?;
+    require_own_entry(&conn, &profiles, entry_id)?;
+    let entry = require_entry(&conn, entry_id)?;
+    conn.execute("DELETE FROM meal_plan_entries WHERE id = ?1", [entry_id])
+        .map_err(|e| format!("Failed to remove meal plan entry: {}", e))?;
+    touch_plan(&conn, entry.plan_id)
+}
+
+#[tauri::command]
+pub(crate) fn meal_plan_weekly_summary(
+    plan_id: i64,
+    week_start: String,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<WeeklyNutritionSummary, String> {
+    let conn = db.open_connection()?;
+    require_own_plan(&conn, &profiles, plan_id)?;
+    weekly_summary(&conn, plan_id, &week_start)
+}
+
+#[tauri::command]
+pub(crate) fn meal_plan_shopping_list(
+    plan_id: i64,
+    start_date: String,
+    end_date: String,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<ShoppingListItem>, String> {
+    let conn = db.open_connection()?;
+    require_own_plan(&conn, &profiles, plan_id)?;
+    shopping_list(&conn, plan_id, &start_date, &end_date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+
+    fn seeded_connection() -> Connection {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        conn.execute(
+            "INSERT INTO recipes (id, title, ingredients, instructions, servings, nutrition_info)
+             VALUES (1, '番茄炒蛋', '[\"番茄 2个\",\"鸡蛋 3个\",\"盐 适量\"]', '[\"炒\"]', 2,
+                     '{\"calories\": 200, \"protein\": 12, \"carbs\": 8, \"fat\": 14}'),
+                    (2, '番茄蛋汤', '[\"番茄 1个\",\"鸡蛋 1个\"]', '[\"煮\"]', 1, NULL)",
+            [],
+        )
+        .expect("insert recipes");
+        conn
+    }
+
+    #[test]
+    fn moves_entries_and_totals_weekly_nutrition() {
+        let conn = seeded_connection();
+        let plan = create_plan(&conn, "s1", "本周").expect("create plan");
+
+        add_entry(&conn, plan.id, "2026-06-08", "lunch", 1, 2.0).expect("add lunch");
+        let dinner = add_entry(&conn, plan.id, "2026-06-08", "dinner", 2, 1.0).expect("add dinner");
+        add_entry(&conn, plan.id, "2026-06-15", "lunch", 1, 1.0).expect("add next week");
+        move_entry(&conn, dinner.id, "2026-06-09", "breakfast", None).expect("move entry");
+
+        assert!(add_entry(&conn, plan.id, "2026-6-8", "lunch", 1, 1.0).is_err());
+        assert!(add_entry(&conn, plan.id, "2026-06-08", "brunch", 1, 1.0).is_err());
+
+        let summary = weekly_summary(&conn, plan.id, "2026-06-08").expect("summary");
+        assert_eq!(summary.week_end, "2026-06-14");
+        assert_eq!(summary.days.len(), 2);
+        // 200 kcal per serving, two servings planned; the recipe itself serves two.
+        assert_eq!(summary.days[0].totals.calories, 400.0);
+        assert_eq!(summary.days[1].entries_without_nutrition, 1);
+        assert_eq!(summary.weekly_totals.protein, 24.0);
+        assert!(summary.daily_target.is_none());
+    }
+
+    #[test]
+    fn consolidates_shopping_list_by_name_and_unit() {
+        let conn = seeded_connection();
+        let plan = create_plan(&conn, "s1", "本周").expect("create plan");
+        add_entry(&conn, plan.id, "2026-06-08", "lunch", 1, 4.0).expect("add lunch");
+        add_entry(&conn, plan.id, "2026-06-09", "dinner", 2, 1.0).expect("add dinner");
+
+        let items = shopping_list(&conn, plan.id, "2026-06-08", "2026-06-14").expect("list");
+        let tomato = items
+            .iter()
+            .find(|item| item.name == "番茄")
+            .expect("tomato");
+        assert_eq!(tomato.amount, Some(5.0));
+        assert_eq!(tomato.unit.as_deref(), Some("个"));
+        assert_eq!(tomato.recipes, vec!["番茄炒蛋", "番茄蛋汤"]);
+        let salt = items.iter().find(|item| item.name == "盐").expect("salt");
+        assert_eq!(salt.amount, None);
+    }
+}
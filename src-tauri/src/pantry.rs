@@ -0,0 +1,404 @@
+use crate::matching::ingredient_names_match;
+use crate::profiles::{ensure_owned, ProfileState};
+use crate::recipe::load_all_recipes;
+use crate::{validate_date, DatabaseState};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+const PANTRY_COLUMNS: &str = "id, session_id, name, quantity, unit, purchase_date, expiry_date, notes, created_at, updated_at, CAST(julianday(expiry_date) - julianday(date('now')) AS INTEGER)";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PantryItemInput {
+    pub name: String,
+    pub quantity: Option<f64>,
+    pub unit: Option<String>,
+    pub purchase_date: Option<String>,
+    pub expiry_date: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PantryItem {
+    pub id: i64,
+    pub session_id: String,
+    pub name: String,
+    pub quantity: Option<f64>,
+    pub unit: Option<String>,
+    pub purchase_date: Option<String>,
+    pub expiry_date: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub days_until_expiry: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PantryRecipeCoverage {
+    pub recipe_id: i64,
+    pub title: String,
+    pub coverage: f64,
+    pub covered: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+fn item_from_row(row: &rusqlite::Row) -> rusqlite::Result<PantryItem> {
+    Ok(PantryItem {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        name: row.get(2)?,
+        quantity: row.get(3)?,
+        unit: row.get(4)?,
+        purchase_date: row.get(5)?,
+        expiry_date: row.get(6)?,
+        notes: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+        days_until_expiry: row.get(10)?,
+    })
+}
+
+fn validate_item(conn: &Connection, item: &PantryItemInput) -> Result<(), String> {
+    let name = item.name.trim();
+    if name.is_empty() || name.len() > 128 {
+        return Err("Ingredient name is invalid".to_string());
+    }
+    if let Some(quantity) = item.quantity {
+        if !quantity.is_finite() || quantity < 0.0 {
+            return Err("Quantity must be a non-negative number".to_string());
+        }
+    }
+    for date in [&item.purchase_date, &item.expiry_date]
+        .into_iter()
+        .flatten()
+    {
+        validate_date(conn, date)?;
+    }
+    if let (Some(purchased), Some(expires)) = (&item.purchase_date, &item.expiry_date) {
+        if expires < purchased {
+            return Err("Expiry date must not be before the purchase date".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn trimmed(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn require_item(conn: &Connection, item_id: i64) -> Result<PantryItem, String> {
+    conn.query_row(
+        &format!("SELECT {PANTRY_COLUMNS} FROM ingredients_inventory WHERE id = ?1"),
+        [item_id],
+        item_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load pantry item: {}", e))?
+    .ok_or_else(|| "Pantry item not found".to_string())
+}
+
+pub(crate) fn add_item(
+    conn: &Connection,
+    session_id: &str,
+    item: &PantryItemInput,
+) -> Result<PantryItem, String> {
+    validate_item(conn, item)?;
+    conn.execute(
+        "INSERT INTO ingredients_inventory (session_id, name, quantity, unit, purchase_date, expiry_date, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            session_id,
+            item.name.trim(),
+            item.quantity,
+            trimmed(&item.unit),
+            item.purchase_date,
+            item.expiry_date,
+            trimmed(&item.notes),
+        ],
+    )
+    .map_err(|e| format!("Failed to add pantry item: {}", e))?;
+    require_item(conn, conn.last_insert_rowid())
+}
+
+fn update_item(
+    conn: &Connection,
+    item_id: i64,
+    item: &PantryItemInput,
+) -> Result<PantryItem, String> {
+    validate_item(conn, item)?;
+    let changed = conn
+        .execute(
+            "UPDATE ingredients_inventory
+             SET name = ?1, quantity = ?2, unit = ?3, purchase_date = ?4, expiry_date = ?5,
+                 notes = ?6, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?7",
+            params![
+                item.name.trim(),
+                item.quantity,
+                trimmed(&item.unit),
+                item.purchase_date,
+                item.expiry_date,
+                trimmed(&item.notes),
+                item_id,
+            ],
+        )
+        .map_err(|e| format!("Failed to update pantry item: {}", e))?;
+    if changed == 0 {
+        return Err("Pantry item not found".to_string());
+    }
+    require_item(conn, item_id)
+}
+
+fn query_items(
+    conn: &Connection,
+    filter: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<PantryItem>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {PANTRY_COLUMNS} FROM ingredients_inventory WHERE {filter}"
+        ))
+        .map_err(|e| format!("Failed to load pantry: {}", e))?;
+    let items = stmt
+        .query_map(params, item_from_row)
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load pantry: {}", e))?;
+    Ok(items)
+}
+
+pub(crate) fn list_items(conn: &Connection, session_id: &str) -> Result<Vec<PantryItem>, String> {
+    query_items(
+        conn,
+        "session_id = ?1 ORDER BY expiry_date IS NULL, expiry_date, name COLLATE NOCASE",
+        [session_id],
+    )
+}
+
+/// Items expiring within `within_days`, including ones that have already expired.
+pub(crate) fn expiring_items(
+    conn: &Connection,
+    session_id: &str,
+    within_days: u32,
+) -> Result<Vec<PantryItem>, String> {
+    query_items(
+        conn,
+        "session_id = ?1 AND expiry_date IS NOT NULL
+         AND expiry_date <= date('now', '+' || ?2 || ' days')
+         AND (quantity IS NULL OR quantity > 0)
+         ORDER BY expiry_date, name COLLATE NOCASE",
+        params![session_id, within_days],
+    )
+}
+
+/// Ranks stored recipes by the share of their ingredients the pantry currently holds.
+/// Expired and used-up items don't count as available.
+pub(crate) fn recipe_coverage(
+    conn: &Connection,
+    session_id: &str,
+    limit: usize,
+) -> Result<Vec<PantryRecipeCoverage>, String> {
+    let available: Vec<String> = query_items(
+        conn,
+        "session_id = ?1 AND (quantity IS NULL OR quantity > 0)
+         AND (expiry_date IS NULL OR expiry_date >= date('now'))",
+        [session_id],
+    )?
+    .into_iter()
+    .map(|item| item.name)
+    .collect();
+    if available.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut ranked: Vec<PantryRecipeCoverage> = load_all_recipes(conn)?
+        .iter()
+        .filter_map(|recipe| {
+            let ingredients = recipe.ingredient_list();
+            if ingredients.is_empty() {
+                return None;
+            }
+            let (covered, missing): (Vec<_>, Vec<_>) = ingredients
+                .into_iter()
+                .map(|item| item.name)
+                .partition(|name| {
+                    available
+                        .iter()
+                        .any(|stocked| ingredient_names_match(stocked, name))
+                });
+            if covered.is_empty() {
+                return None;
+            }
+            Some(PantryRecipeCoverage {
+                recipe_id: recipe.id,
+                title: recipe.title.clone(),
+                coverage: covered.len() as f64 / (covered.len() + missing.len()) as f64,
+                covered,
+                missing,
+            })
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.coverage
+            .total_cmp(&a.coverage)
+            .then(a.missing.len().cmp(&b.missing.len()))
+            .then(a.recipe_id.cmp(&b.recipe_id))
+    });
+    ranked.truncate(limit);
+    Ok(ranked)
+}
+
+fn require_own_item(
+    conn: &Connection,
+    profiles: &ProfileState,
+    item_id: i64,
+) -> Result<(), String> {
+    ensure_owned(
+        conn,
+        "SELECT session_id FROM ingredients_inventory WHERE id = ?1",
+        item_id,
+        &profiles.session_id()?,
+        "Pantry item not found",
+    )
+}
+
+#[tauri::command]
+pub(crate) fn pantry_list(
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<PantryItem>, String> {
+    let conn = db.open_connection()?;
+    list_items(&conn, &profiles.session_id()?)
+}
+
+#[tauri::command]
+pub(crate) fn pantry_add(
+    item: PantryItemInput,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<PantryItem, String> {
+    let conn = db.open_connection()?;
+    add_item(&conn, &profiles.session_id()?, &item)
+}
+
+#[tauri::command]
+pub(crate) fn pantry_update(
+    item_id: i64,
+    item: PantryItemInput,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<PantryItem, String> {
+    let conn = db.open_connection()?;
+    require_own_item(&conn, &profiles, item_id)?;
+    update_item(&conn, item_id, &item)
+}
+
+#[tauri::command]
+pub(crate) fn pantry_remove(
+    item_id: i64,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<(), String> {
+    let conn = db.open_connection()?;
+    require_own_item(&conn, &profiles, item_id)?;
+    conn.execute("DELETE FROM ingredients_inventory WHERE id = ?1", [item_id])
+        .map(|_| ())
+        .map_err(|e| format!("Failed to remove pantry item: {}", e))
+}
+
+#[tauri::command]
+pub(crate) fn pantry_expiring(
+    within_days: Option<u32>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<PantryItem>, String> {
+    let conn = db.open_connection()?;
+    expiring_items(
+        &conn,
+        &profiles.session_id()?,
+        within_days.unwrap_or(3).min(365),
+    )
+}
+
+#[tauri::command]
+pub(crate) fn pantry_recipe_coverage(
+    limit: Option<usize>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<PantryRecipeCoverage>, String> {
+    let conn = db.open_connection()?;
+    recipe_coverage(&conn, &profiles.session_id()?, limit.unwrap_or(20).min(100))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+
+    fn item(name: &str, expiry_offset_days: Option<i64>) -> PantryItemInput {
+        PantryItemInput {
+            name: name.to_string(),
+            quantity: Some(1.0),
+            unit: Some("个".to_string()),
+            purchase_date: None,
+            expiry_date: expiry_offset_days.map(|days| {
+                Connection::open_in_memory()
+                    .expect("open")
+                    .query_row("SELECT date('now', ?1 || ' days')", [days], |row| {
+                        row.get(0)
+                    })
+                    .expect("date")
+            }),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn lists_items_expiring_soon_including_expired() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+
+        add_item(&conn, "s1", &item("牛奶", Some(-1))).expect("add expired");
+        add_item(&conn, "s1", &item("鸡蛋", Some(2))).expect("add soon");
+        add_item(&conn, "s1", &item("大米", Some(90))).expect("add later");
+        add_item(&conn, "s1", &item("盐", None)).expect("add no expiry");
+        add_item(&conn, "s2", &item("豆腐", Some(1))).expect("add other session");
+
+        let expiring = expiring_items(&conn, "s1", 3).expect("expiring");
+        let names: Vec<_> = expiring.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, vec!["牛奶", "鸡蛋"]);
+        assert_eq!(expiring[0].days_until_expiry, Some(-1));
+        assert_eq!(list_items(&conn, "s1").expect("list").len(), 4);
+
+        let mut invalid = item("面粉", None);
+        invalid.expiry_date = Some("2026/01/01".to_string());
+        assert!(add_item(&conn, "s1", &invalid).is_err());
+    }
+
+    #[test]
+    fn ranks_recipes_by_pantry_coverage() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        conn.execute(
+            "INSERT INTO recipes (id, title, ingredients, instructions)
+             VALUES (1, '番茄炒蛋', '[\"番茄 2个\",\"鸡蛋 3个\"]', '[]'),
+                    (2, '青椒肉丝', '[\"青椒 2个\",\"猪肉 200g\",\"鸡蛋 1个\"]', '[]'),
+                    (3, '清蒸鱼', '[\"鲈鱼 1条\"]', '[]')",
+            [],
+        )
+        .expect("insert recipes");
+        add_item(&conn, "s1", &item("鸡蛋", Some(5))).expect("add eggs");
+        add_item(&conn, "s1", &item("番茄", Some(5))).expect("add tomato");
+        add_item(&conn, "s1", &item("猪肉", Some(-2))).expect("add expired pork");
+
+        let ranked = recipe_coverage(&conn, "s1", 10).expect("coverage");
+        let ids: Vec<_> = ranked.iter().map(|entry| entry.recipe_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(ranked[0].coverage, 1.0);
+        assert_eq!(ranked[1].missing, vec!["青椒", "猪肉"]);
+    }
+}
@@ -0,0 +1,311 @@
+use crate::profiles::ProfileState;
+use crate::recipe::require_recipe;
+use crate::DatabaseState;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::State;
+
+/// Views older than this no longer count towards trending.
+const VIEW_WINDOW_DAYS: i64 = 60;
+const VIEW_HALF_LIFE_DAYS: f64 = 7.0;
+const FAVORITE_HALF_LIFE_DAYS: f64 = 30.0;
+const FAVORITE_WEIGHT: f64 = 3.0;
+const RATING_WEIGHT: f64 = 2.0;
+/// Ratings are pulled towards a neutral 3 stars as if five such votes existed, so
+/// one 5-star rating does not outrank a recipe with fifty 4.5-star ratings.
+const RATING_PRIOR: f64 = 3.0;
+const RATING_PRIOR_VOTES: f64 = 5.0;
+const MAX_RESULTS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecentlyViewedRecipe {
+    pub recipe_id: i64,
+    pub title: String,
+    pub image_url: Option<String>,
+    pub last_viewed_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrendingRecipe {
+    pub recipe_id: i64,
+    pub title: String,
+    pub image_url: Option<String>,
+    pub category: Option<String>,
+    pub score: f64,
+    pub recent_views: i64,
+    pub view_count: i64,
+    pub favorite_count: i64,
+    pub average_rating: f64,
+    pub rating_count: i64,
+}
+
+/// Bumps `recipes.view_count` and logs the view in one transaction; returns the new count.
+pub(crate) fn record_view(
+    conn: &mut Connection,
+    recipe_id: i64,
+    session_id: Option<&str>,
+) -> Result<i64, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    require_recipe(&tx, recipe_id)?;
+    let view_count: i64 = tx
+        .query_row(
+            "UPDATE recipes SET view_count = COALESCE(view_count, 0) + 1 WHERE id = ?1 RETURNING view_count",
+            [recipe_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to record recipe view: {}", e))?;
+    tx.execute(
+        "INSERT INTO recipe_views (recipe_id, session_id) VALUES (?1, ?2)",
+        params![recipe_id, session_id],
+    )
+    .map_err(|e| format!("Failed to record recipe view: {}", e))?;
+    tx.commit()
+        .map_err(|e| format!("Failed to record recipe view: {}", e))?;
+    Ok(view_count)
+}
+
+pub(crate) fn recently_viewed(
+    conn: &Connection,
+    session_id: &str,
+    limit: usize,
+) -> Result<Vec<RecentlyViewedRecipe>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.id, r.title, r.image_url, MAX(v.viewed_at) AS last_viewed_at
+             FROM recipe_views v JOIN recipes r ON r.id = v.recipe_id
+             WHERE v.session_id = ?1
+             GROUP BY r.id
+             ORDER BY last_viewed_at DESC, MAX(v.id) DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| format!("Failed to load viewed recipes: {}", e))?;
+    stmt.query_map(params![session_id, limit as i64], |row| {
+        Ok(RecentlyViewedRecipe {
+            recipe_id: row.get(0)?,
+            title: row.get(1)?,
+            image_url: row.get(2)?,
+            last_viewed_at: row.get(3)?,
+        })
+    })
+    .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+    .map_err(|e| format!("Failed to load viewed recipes: {}", e))
+}
+
+fn decayed(age_days: f64, half_life: f64) -> f64 {
+    0.5_f64.powf(age_days.max(0.0) / half_life)
+}
+
+/// Sums `(recipe_id, age in days)` rows into a decayed weight and a raw count per recipe.
+fn decayed_totals(
+    conn: &Connection,
+    sql: &str,
+    half_life: f64,
+) -> rusqlite::Result<HashMap<i64, (f64, i64)>> {
+    let mut totals: HashMap<i64, (f64, i64)> = HashMap::new();
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let recipe_id: i64 = row.get(0)?;
+        let age: f64 = row.get::<_, Option<f64>>(1)?.unwrap_or(0.0);
+        let entry = totals.entry(recipe_id).or_default();
+        entry.0 += decayed(age, half_life);
+        entry.1 += 1;
+    }
+    Ok(totals)
+}
+
+fn rating_adjustment(average_rating: f64, rating_count: i64) -> f64 {
+    let count = rating_count.max(0) as f64;
+    let bayesian =
+        (average_rating * count + RATING_PRIOR * RATING_PRIOR_VOTES) / (count + RATING_PRIOR_VOTES);
+    bayesian - RATING_PRIOR
+}
+
+/// Ranks recipes by views halving in weight every week, favorites halving every
+/// month, and a Bayesian rating bonus. Recipes without any signal are left out.
+pub(crate) fn trending_recipes(
+    conn: &Connection,
+    limit: usize,
+) -> Result<Vec<TrendingRecipe>, String> {
+    let views = decayed_totals(
+        conn,
+        &format!(
+            "SELECT recipe_id, julianday('now') - julianday(viewed_at) FROM recipe_views
+             WHERE viewed_at >= datetime('now', '-{VIEW_WINDOW_DAYS} days')"
+        ),
+        VIEW_HALF_LIFE_DAYS,
+    )
+    .map_err(|e| format!("Failed to load recipe views: {}", e))?;
+    let favorites = decayed_totals(
+        conn,
+        "SELECT recipe_id, julianday('now') - julianday(created_at) FROM favorites",
+        FAVORITE_HALF_LIFE_DAYS,
+    )
+    .map_err(|e| format!("Failed to load favorites: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, image_url, category, COALESCE(view_count, 0),
+                    COALESCE(average_rating, 0), COALESCE(rating_count, 0)
+             FROM recipes",
+        )
+        .map_err(|e| format!("Failed to load recipes: {}", e))?;
+    let mut ranked = stmt
+        .query_map([], |row| {
+            let recipe_id: i64 = row.get(0)?;
+            let average_rating: f64 = row.get(5)?;
+            let rating_count: i64 = row.get(6)?;
+            let (view_score, recent_views) = views.get(&recipe_id).copied().unwrap_or_default();
+            let (favorite_score, favorite_count) =
+                favorites.get(&recipe_id).copied().unwrap_or_default();
+            Ok(TrendingRecipe {
+                recipe_id,
+                title: row.get(1)?,
+                image_url: row.get(2)?,
+                category: row.get(3)?,
+                score: view_score
+                    + FAVORITE_WEIGHT * favorite_score
+                    + RATING_WEIGHT * rating_adjustment(average_rating, rating_count),
+                recent_views,
+                view_count: row.get(4)?,
+                favorite_count,
+                average_rating,
+                rating_count,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load recipes: {}", e))?;
+
+    ranked.retain(|recipe| recipe.score > 0.0);
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.view_count.cmp(&a.view_count))
+            .then_with(|| a.recipe_id.cmp(&b.recipe_id))
+    });
+    ranked.truncate(limit);
+    Ok(ranked)
+}
+
+fn result_limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(10).clamp(1, MAX_RESULTS)
+}
+
+#[tauri::command]
+pub(crate) fn recipe_viewed(
+    recipe_id: i64,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<i64, String> {
+    let session_id = profiles.active().map(|profile| profile.session_id);
+    let mut conn = db.open_connection()?;
+    record_view(&mut conn, recipe_id, session_id.as_deref())
+}
+
+#[tauri::command]
+pub(crate) fn recipe_recently_viewed(
+    limit: Option<usize>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<RecentlyViewedRecipe>, String> {
+    let conn = db.open_connection()?;
+    recently_viewed(&conn, &profiles.session_id()?, result_limit(limit))
+}
+
+#[tauri::command]
+pub(crate) fn recipe_trending(
+    limit: Option<usize>,
+    db: State<DatabaseState>,
+) -> Result<Vec<TrendingRecipe>, String> {
+    let conn = db.open_connection()?;
+    trending_recipes(&conn, result_limit(limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+
+    fn insert_recipe(conn: &Connection, title: &str) -> i64 {
+        conn.execute(
+            "INSERT INTO recipes (title, ingredients, instructions) VALUES (?1, '[]', '[]')",
+            [title],
+        )
+        .unwrap();
+        conn.last_insert_rowid()
+    }
+
+    #[test]
+    fn records_views_atomically_and_lists_recent_ones() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        let soup = insert_recipe(&conn, "番茄蛋汤");
+        let noodles = insert_recipe(&conn, "炸酱面");
+
+        assert_eq!(record_view(&mut conn, soup, Some("s1")).unwrap(), 1);
+        assert_eq!(record_view(&mut conn, noodles, Some("s1")).unwrap(), 1);
+        assert_eq!(record_view(&mut conn, soup, Some("s1")).unwrap(), 2);
+        assert_eq!(record_view(&mut conn, noodles, None).unwrap(), 2);
+        assert_eq!(
+            record_view(&mut conn, 999, Some("s1")).unwrap_err(),
+            "Recipe not found"
+        );
+
+        let recent = recently_viewed(&conn, "s1", 10).unwrap();
+        let ids: Vec<_> = recent.iter().map(|item| item.recipe_id).collect();
+        assert_eq!(ids, [soup, noodles]);
+        let logged: i64 = conn
+            .query_row("SELECT COUNT(*) FROM recipe_views", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(logged, 4);
+        let revisions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM recipe_revisions", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(revisions, 2, "view counts must not create revisions");
+    }
+
+    #[test]
+    fn trending_weighs_recency_favorites_and_ratings() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        let stale = insert_recipe(&conn, "旧爱");
+        let fresh = insert_recipe(&conn, "新宠");
+        let loved = insert_recipe(&conn, "收藏之选");
+        let rated = insert_recipe(&conn, "高分菜");
+        insert_recipe(&conn, "无人问津");
+
+        for _ in 0..6 {
+            record_view(&mut conn, stale, None).unwrap();
+        }
+        conn.execute(
+            "UPDATE recipe_views SET viewed_at = datetime('now', '-28 days') WHERE recipe_id = ?1",
+            [stale],
+        )
+        .unwrap();
+        for _ in 0..2 {
+            record_view(&mut conn, fresh, None).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO favorites (session_id, recipe_id) VALUES ('s1', ?1)",
+            [loved],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE recipes SET average_rating = 4.8, rating_count = 20 WHERE id = ?1",
+            [rated],
+        )
+        .unwrap();
+
+        let trending = trending_recipes(&conn, 10).unwrap();
+        let ids: Vec<_> = trending.iter().map(|item| item.recipe_id).collect();
+        assert_eq!(ids, [loved, rated, fresh, stale]);
+        assert_eq!(trending[3].recent_views, 6);
+        assert_eq!(trending[3].view_count, 6);
+        assert_eq!(trending[0].favorite_count, 1);
+    }
+}
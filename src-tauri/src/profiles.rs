@@ -0,0 +1,647 @@
+use crate::cook_log::refresh_rating;
+use crate::images::sha256_hex;
+use crate::DatabaseState;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+const ACTIVE_PROFILE_KEY: &str = "active_profile_id";
+const DEFAULT_PROFILE_NAME: &str = "默认";
+const MAX_NAME_CHARS: usize = 40;
+const MAX_PIN_ATTEMPTS: u32 = 5;
+const PIN_LOCKOUT: Duration = Duration::from_secs(30);
+
+/// Per-profile data keyed by `session_id`. Rows are deleted with the profile, except
+/// recipe views, which are kept anonymously because they feed trending.
+const PROFILE_TABLES: &[&str] = &[
+    "favorites",
+    "search_history",
+    "meal_plans",
+    "ingredients_inventory",
+    "cook_log",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Profile {
+    pub id: i64,
+    pub name: String,
+    pub has_pin: bool,
+    pub is_active: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ActiveProfile {
+    pub id: i64,
+    pub session_id: String,
+}
+
+/// The profile every scoped command runs as. Profiles are rows of `users`; their
+/// `session_id` keys favorites, search history, meal plans, pantry and cook log,
+/// so the frontend never passes a session id of its own.
+#[derive(Default)]
+pub struct ProfileState {
+    active: Mutex<Option<ActiveProfile>>,
+    failed_pins: Mutex<HashMap<i64, (u32, Instant)>>,
+}
+
+impl ProfileState {
+    pub(crate) fn active(&self) -> Option<ActiveProfile> {
+        self.active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub(crate) fn session_id(&self) -> Result<String, String> {
+        self.active()
+            .map(|profile| profile.session_id)
+            .ok_or_else(|| "No profile is selected".to_string())
+    }
+
+    pub(crate) fn set_active(&self, profile: Option<ActiveProfile>) {
+        *self.active.lock().unwrap_or_else(|e| e.into_inner()) = profile;
+    }
+
+    /// Checks a PIN attempt, locking the profile for a while after repeated failures.
+    fn verify_pin(
+        &self,
+        profile_id: i64,
+        stored: Option<&str>,
+        pin: Option<&str>,
+    ) -> Result<(), String> {
+        let Some(stored) = stored else {
+            return Ok(());
+        };
+        let mut failed = self.failed_pins.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((count, last)) = failed.get(&profile_id) {
+            if *count >= MAX_PIN_ATTEMPTS && last.elapsed() < PIN_LOCKOUT {
+                return Err("Too many incorrect PIN attempts; try again later".to_string());
+            }
+        }
+        if pin.is_some_and(|pin| pin_matches(stored, pin)) {
+            failed.remove(&profile_id);
+            return Ok(());
+        }
+        let entry = failed.entry(profile_id).or_insert((0, Instant::now()));
+        if entry.0 >= MAX_PIN_ATTEMPTS {
+            entry.0 = 0;
+        }
+        entry.0 += 1;
+        entry.1 = Instant::now();
+        Err("Incorrect PIN".to_string())
+    }
+}
+
+fn validate_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err("Profile name must be between 1 and 40 characters".to_string());
+    }
+    Ok(name)
+}
+
+fn validate_pin(pin: &str) -> Result<(), String> {
+    if (4..=8).contains(&pin.len()) && pin.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err("PIN must be 4 to 8 digits".to_string())
+    }
+}
+
+/// A PIN keeps family members out of each other's profiles, not out of the database
+/// file, so a salted SHA-256 is enough.
+fn hash_pin(conn: &Connection, pin: &str) -> Result<String, String> {
+    validate_pin(pin)?;
+    let salt: String = conn
+        .query_row("SELECT lower(hex(randomblob(16)))", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to set PIN: {}", e))?;
+    Ok(format!(
+        "{salt}${}",
+        sha256_hex(format!("{salt}{pin}").as_bytes())
+    ))
+}
+
+fn pin_matches(stored: &str, pin: &str) -> bool {
+    stored
+        .split_once('$')
+        .is_some_and(|(salt, hash)| sha256_hex(format!("{salt}{pin}").as_bytes()) == hash)
+}
+
+fn profile_from_row(row: &rusqlite::Row, active_id: Option<i64>) -> rusqlite::Result<Profile> {
+    let id: i64 = row.get(0)?;
+    Ok(Profile {
+        id,
+        name: row.get(1)?,
+        has_pin: row.get::<_, Option<String>>(2)?.is_some(),
+        is_active: active_id == Some(id),
+        created_at: row.get(3)?,
+    })
+}
+
+pub(crate) fn list_profiles(
+    conn: &Connection,
+    active_id: Option<i64>,
+) -> Result<Vec<Profile>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, display_name, pin_hash, created_at FROM users
+             WHERE display_name IS NOT NULL ORDER BY id",
+        )
+        .map_err(|e| format!("Failed to load profiles: {}", e))?;
+    stmt.query_map([], |row| profile_from_row(row, active_id))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load profiles: {}", e))
+}
+
+/// Returns the profile with its session id and stored PIN hash.
+fn require_profile(
+    conn: &Connection,
+    profile_id: i64,
+) -> Result<(ActiveProfile, Option<String>), String> {
+    conn.query_row(
+        "SELECT id, session_id, pin_hash FROM users WHERE id = ?1 AND display_name IS NOT NULL",
+        [profile_id],
+        |row| {
+            Ok((
+                ActiveProfile {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                },
+                row.get(2)?,
+            ))
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load profile: {}", e))?
+    .ok_or_else(|| "Profile not found".to_string())
+}
+
+fn ensure_unique_name(conn: &Connection, name: &str, except: Option<i64>) -> Result<(), String> {
+    let taken: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM users WHERE display_name = ?1 AND id IS NOT ?2",
+            params![name, except],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to check profile name: {}", e))?;
+    if taken {
+        Err("A profile with this name already exists".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+pub(crate) fn create_profile(
+    conn: &Connection,
+    name: &str,
+    pin: Option<&str>,
+) -> Result<Profile, String> {
+    let name = validate_name(name)?;
+    ensure_unique_name(conn, name, None)?;
+    let pin_hash = pin.map(|pin| hash_pin(conn, pin)).transpose()?;
+    conn.execute(
+        "INSERT INTO users (session_id, display_name, pin_hash)
+         VALUES ('profile-' || lower(hex(randomblob(16))), ?1, ?2)",
+        params![name, pin_hash],
+    )
+    .map_err(|e| format!("Failed to create profile: {}", e))?;
+    let id = conn.last_insert_rowid();
+    list_profiles(conn, None)?
+        .into_iter()
+        .find(|profile| profile.id == id)
+        .ok_or_else(|| "Profile not found".to_string())
+}
+
+/// Desktop installs used to run as one anonymous web session. The first time
+/// profiles are needed, the most recently used session becomes the default
+/// profile so its favorites and history carry over.
+pub(crate) fn ensure_default_profile(conn: &Connection) -> Result<(), String> {
+    let has_profile: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM users WHERE display_name IS NOT NULL",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to load profiles: {}", e))?;
+    if has_profile {
+        return Ok(());
+    }
+    let legacy: Option<String> = conn
+        .query_row(
+            "SELECT session_id FROM (
+                 SELECT session_id, updated_at AS seen FROM users
+                 UNION ALL SELECT session_id, updated_at FROM favorites
+                 UNION ALL SELECT session_id, search_time FROM search_history
+             ) ORDER BY seen DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load legacy sessions: {}", e))?;
+    match legacy {
+        Some(session_id) => conn.execute(
+            "INSERT INTO users (session_id, display_name) VALUES (?1, ?2)
+             ON CONFLICT(session_id) DO UPDATE SET display_name = excluded.display_name, updated_at = CURRENT_TIMESTAMP",
+            params![session_id, DEFAULT_PROFILE_NAME],
+        ),
+        None => conn.execute(
+            "INSERT INTO users (session_id, display_name)
+             VALUES ('profile-' || lower(hex(randomblob(16))), ?1)",
+            [DEFAULT_PROFILE_NAME],
+        ),
+    }
+    .map(|_| ())
+    .map_err(|e| format!("Failed to create the default profile: {}", e))
+}
+
+/// Picks the profile to start as: the last active one unless it is PIN protected,
+/// otherwise the first profile without a PIN. With only protected profiles nothing
+/// is selected until `profile_switch` is called with a PIN.
+pub(crate) fn restore(conn: &Connection) -> Result<Option<ActiveProfile>, String> {
+    ensure_default_profile(conn)?;
+    let last: Option<i64> = conn
+        .query_row(
+            "SELECT CAST(value AS INTEGER) FROM settings WHERE key = ?1",
+            [ACTIVE_PROFILE_KEY],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load the active profile: {}", e))?;
+    conn.query_row(
+        "SELECT id, session_id FROM users
+         WHERE display_name IS NOT NULL AND pin_hash IS NULL
+         ORDER BY id = ?1 DESC, id LIMIT 1",
+        [last],
+        |row| {
+            Ok(ActiveProfile {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load the active profile: {}", e))
+}
+
+fn remember_active(conn: &Connection, profile_id: i64) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO settings (key, value, category) VALUES (?1, ?2, 'profiles')
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+        params![ACTIVE_PROFILE_KEY, profile_id.to_string()],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to save the active profile: {}", e))
+}
+
+pub(crate) fn switch_profile(
+    conn: &Connection,
+    profiles: &ProfileState,
+    profile_id: i64,
+    pin: Option<&str>,
+) -> Result<Profile, String> {
+    let (profile, pin_hash) = require_profile(conn, profile_id)?;
+    if profiles
+        .active()
+        .is_none_or(|active| active.id != profile_id)
+    {
+        profiles.verify_pin(profile_id, pin_hash.as_deref(), pin)?;
+    }
+    remember_active(conn, profile_id)?;
+    profiles.set_active(Some(profile));
+    list_profiles(conn, Some(profile_id))?
+        .into_iter()
+        .find(|profile| profile.is_active)
+        .ok_or_else(|| "Profile not found".to_string())
+}
+
+/// Deletes a profile and everything it owns. The last remaining profile cannot be
+/// deleted, so there is always one to fall back to.
+pub(crate) fn delete_profile(
+    conn: &mut Connection,
+    profiles: &ProfileState,
+    profile_id: i64,
+    pin: Option<&str>,
+) -> Result<(), String> {
+    let (profile, pin_hash) = require_profile(conn, profile_id)?;
+    if list_profiles(conn, None)?.len() <= 1 {
+        return Err("The last profile cannot be deleted".to_string());
+    }
+    profiles.verify_pin(profile_id, pin_hash.as_deref(), pin)?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    // Ratings from the deleted cook log entries must drop out of the recipe averages.
+    let rated_recipe_ids = tx
+        .prepare("SELECT DISTINCT recipe_id FROM cook_log WHERE session_id = ?1")
+        .and_then(|mut stmt| {
+            stmt.query_map([&profile.session_id], |row| row.get::<_, i64>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| format!("Failed to delete profile data: {}", e))?;
+    for table in PROFILE_TABLES {
+        tx.execute(
+            &format!("DELETE FROM {table} WHERE session_id = ?1"),
+            [&profile.session_id],
+        )
+        .map_err(|e| format!("Failed to delete profile data: {}", e))?;
+    }
+    tx.execute(
+        "UPDATE recipe_views SET session_id = NULL WHERE session_id = ?1",
+        [&profile.session_id],
+    )
+    .and_then(|_| tx.execute("DELETE FROM users WHERE id = ?1", [profile_id]))
+    .map_err(|e| format!("Failed to delete profile: {}", e))?;
+    for recipe_id in rated_recipe_ids {
+        refresh_rating(&tx, recipe_id)?;
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to delete profile: {}", e))?;
+
+    if profiles
+        .active()
+        .is_some_and(|active| active.id == profile_id)
+    {
+        profiles.set_active(None);
+    }
+    Ok(())
+}
+
+/// Resolves to "not found" when the row belongs to another profile, so ids from one
+/// profile cannot be used to read or change another's data. `owner_sql` selects the
+/// owning `session_id` for the id bound to `?1`.
+pub(crate) fn ensure_owned(
+    conn: &Connection,
+    owner_sql: &str,
+    id: i64,
+    session_id: &str,
+    not_found: &str,
+) -> Result<(), String> {
+    let owner: Option<String> = conn
+        .query_row(owner_sql, [id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to check ownership: {}", e))?;
+    if owner.as_deref() == Some(session_id) {
+        Ok(())
+    } else {
+        Err(not_found.to_string())
+    }
+}
+
+#[tauri::command]
+pub(crate) fn profile_list(
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<Profile>, String> {
+    let conn = db.open_connection()?;
+    list_profiles(&conn, profiles.active().map(|profile| profile.id))
+}
+
+#[tauri::command]
+pub(crate) fn profile_active(
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Option<Profile>, String> {
+    let Some(active) = profiles.active() else {
+        return Ok(None);
+    };
+    let conn = db.open_connection()?;
+    Ok(list_profiles(&conn, Some(active.id))?
+        .into_iter()
+        .find(|profile| profile.is_active))
+}
+
+#[tauri::command]
+pub(crate) fn profile_create(
+    name: String,
+    pin: Option<String>,
+    db: State<DatabaseState>,
+) -> Result<Profile, String> {
+    let conn = db.open_connection()?;
+    create_profile(&conn, &name, pin.as_deref())
+}
+
+#[tauri::command]
+pub(crate) fn profile_switch(
+    profile_id: i64,
+    pin: Option<String>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Profile, String> {
+    let conn = db.open_connection()?;
+    switch_profile(&conn, &profiles, profile_id, pin.as_deref())
+}
+
+#[tauri::command]
+pub(crate) fn profile_rename(
+    name: String,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<(), String> {
+    let active = profiles
+        .active()
+        .ok_or_else(|| "No profile is selected".to_string())?;
+    let name = validate_name(&name)?;
+    let conn = db.open_connection()?;
+    ensure_unique_name(&conn, name, Some(active.id))?;
+    conn.execute(
+        "UPDATE users SET display_name = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![name, active.id],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to rename profile: {}", e))
+}
+
+/// Sets, changes or (with no `new_pin`) removes the active profile's PIN.
+#[tauri::command]
+pub(crate) fn profile_set_pin(
+    current_pin: Option<String>,
+    new_pin: Option<String>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<(), String> {
+    let active = profiles
+        .active()
+        .ok_or_else(|| "No profile is selected".to_string())?;
+    let conn = db.open_connection()?;
+    let (_, stored) = require_profile(&conn, active.id)?;
+    profiles.verify_pin(active.id, stored.as_deref(), current_pin.as_deref())?;
+    let pin_hash = new_pin
+        .as_deref()
+        .map(|pin| hash_pin(&conn, pin))
+        .transpose()?;
+    conn.execute(
+        "UPDATE users SET pin_hash = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![pin_hash, active.id],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to update PIN: {}", e))
+}
+
+#[tauri::command]
+pub(crate) fn profile_delete(
+    profile_id: i64,
+    pin: Option<String>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<(), String> {
+    let mut conn = db.open_connection()?;
+    delete_profile(&mut conn, &profiles, profile_id, pin.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+
+    #[test]
+    fn adopts_the_legacy_session_and_switches_with_pins() {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO search_history (session_id, ingredients, search_time)
+             VALUES ('old', '[]', '2025-01-01'), ('web-123', '[]', '2025-06-01')",
+            [],
+        )
+        .unwrap();
+
+        let state = ProfileState::default();
+        state.set_active(restore(&conn).unwrap());
+        assert_eq!(state.session_id().unwrap(), "web-123");
+        let default_id = state.active().unwrap().id;
+
+        let kid = create_profile(&conn, " 小明 ", Some("2468")).unwrap();
+        assert_eq!((kid.name.as_str(), kid.has_pin), ("小明", true));
+        assert_eq!(
+            create_profile(&conn, "小明", None).unwrap_err(),
+            "A profile with this name already exists"
+        );
+        assert_eq!(
+            create_profile(&conn, "x", Some("12a4")).unwrap_err(),
+            "PIN must be 4 to 8 digits"
+        );
+
+        assert_eq!(
+            switch_profile(&conn, &state, kid.id, None).unwrap_err(),
+            "Incorrect PIN"
+        );
+        assert_eq!(state.session_id().unwrap(), "web-123");
+        let switched = switch_profile(&conn, &state, kid.id, Some("2468")).unwrap();
+        assert!(switched.is_active);
+        assert!(state.session_id().unwrap().starts_with("profile-"));
+
+        // A protected profile is not resumed on startup without its PIN.
+        assert_eq!(restore(&conn).unwrap().unwrap().id, default_id);
+
+        for _ in 0..MAX_PIN_ATTEMPTS {
+            state.set_active(None);
+            let _ = switch_profile(&conn, &state, kid.id, Some("0000"));
+        }
+        assert_eq!(
+            switch_profile(&conn, &state, kid.id, Some("2468")).unwrap_err(),
+            "Too many incorrect PIN attempts; try again later"
+        );
+    }
+
+    #[test]
+    fn deletes_profile_data_and_checks_ownership() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        ensure_default_profile(&conn).unwrap();
+        let state = ProfileState::default();
+        let guest = create_profile(&conn, "客人", None).unwrap();
+        let guest_session = switch_profile(&conn, &state, guest.id, None)
+            .map(|_| state.session_id().unwrap())
+            .unwrap();
+        conn.execute(
+            "INSERT INTO ingredients_inventory (session_id, name) VALUES (?1, '鸡蛋')",
+            [&guest_session],
+        )
+        .unwrap();
+        let item_id = conn.last_insert_rowid();
+        let owner_sql = "SELECT session_id FROM ingredients_inventory WHERE id = ?1";
+        assert!(ensure_owned(
+            &conn,
+            owner_sql,
+            item_id,
+            &guest_session,
+            "Pantry item not found"
+        )
+        .is_ok());
+        assert_eq!(
+            ensure_owned(
+                &conn,
+                owner_sql,
+                item_id,
+                "someone-else",
+                "Pantry item not found"
+            )
+            .unwrap_err(),
+            "Pantry item not found"
+        );
+
+        delete_profile(&mut conn, &state, guest.id, None).unwrap();
+        assert!(state.active().is_none());
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM ingredients_inventory", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(remaining, 0);
+        let only = list_profiles(&conn, None).unwrap();
+        assert_eq!(only.len(), 1);
+        assert_eq!(
+            delete_profile(&mut conn, &state, only[0].id, None).unwrap_err(),
+            "The last profile cannot be deleted"
+        );
+    }
+
+    #[test]
+    fn deleting_a_profile_refreshes_recipe_ratings() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        ensure_default_profile(&conn).unwrap();
+        let state = ProfileState::default();
+        let guest = create_profile(&conn, "客人", None).unwrap();
+        let recipe_id = crate::recipe::insert_recipe(
+            &conn,
+            &crate::recipe::RecipeDraft {
+                title: "红烧肉".to_string(),
+                ingredients: vec!["五花肉".to_string()],
+                instructions: vec!["炖".to_string()],
+                ..crate::recipe::RecipeDraft::default()
+            },
+        )
+        .unwrap();
+        let guest_session: String = conn
+            .query_row(
+                "SELECT session_id FROM users WHERE id = ?1",
+                [guest.id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        for (session_id, rating) in [("someone-else", 3), (guest_session.as_str(), 5)] {
+            conn.execute(
+                "INSERT INTO cook_log (recipe_id, session_id, cooked_on, rating)
+                 VALUES (?1, ?2, '2024-05-01', ?3)",
+                params![recipe_id, session_id, rating],
+            )
+            .unwrap();
+        }
+        assert_eq!(refresh_rating(&conn, recipe_id).unwrap().rating_count, 2);
+
+        delete_profile(&mut conn, &state, guest.id, None).unwrap();
+        let (average, count): (f64, i64) = conn
+            .query_row(
+                "SELECT average_rating, rating_count FROM recipes WHERE id = ?1",
+                [recipe_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((average, count), (3.0, 1));
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{
+    credential_health, read_credential, validate_provider_id, DatabaseState,
+    StoredProviderCredential, PROVIDER_IDS,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::future::Future;
+use tauri::{AppHandle, State};
+
+const CHAIN_KEY: &str = "ai_provider_chain";
+const HTTP_FAILURE_PREFIX: &str = "AI provider request failed with HTTP ";
+/// Timeouts, rate limits and exhausted quota: another provider may well succeed
+/// where this one did not. Server errors (5xx) are retried in `should_fall_back`.
+const RETRYABLE_STATUSES: [u16; 3] = [402, 408, 429];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderFailure {
+    pub provider_id: String,
+    pub error: String,
+}
+
+/// Which provider profile produced an answer, and the ones tried before it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderAnswer<T> {
+    pub provider_id: String,
+    pub model: String,
+    pub fallbacks: Vec<ProviderFailure>,
+    #[serde(flatten)]
+    pub value: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChatCompletion {
+    pub content: String,
+}
+
+/// Whether a failed call should move on to the next provider. Anything else, such
+/// as a rejected prompt or an unsupported model, would fail the same way elsewhere
+/// or needs the user's attention.
+pub(crate) fn should_fall_back(error: &str) -> bool {
+    if error == "Unable to reach the AI provider" {
+        return true;
+    }
+    http_status(error).is_some_and(|code| RETRYABLE_STATUSES.contains(&code) || code >= 500)
+}
+
+/// The HTTP status of a failed provider request, when the provider answered at all.
+pub(crate) fn http_status(error: &str) -> Option<u16> {
+    error
+        .strip_prefix(HTTP_FAILURE_PREFIX)
+        .and_then(|status| status.get(..3))
+        .and_then(|code| code.parse::<u16>().ok())
+}
+
+pub(crate) fn load_chain(conn: &Connection) -> Result<Vec<String>, String> {
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [CHAIN_KEY],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load the provider chain: {}", e))?;
+    // Profiles removed from a later build are dropped rather than failing every call.
+    Ok(stored
+        .and_then(|value| serde_json::from_str::<Vec<String>>(&value).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|provider_id| PROVIDER_IDS.contains(&provider_id.as_str()))
+        .collect())
+}
+
+fn save_chain(conn: &Connection, provider_ids: &[String]) -> Result<(), String> {
+    for (index, provider_id) in provider_ids.iter().enumerate() {
+        validate_provider_id(provider_id)?;
+        if provider_ids[..index].contains(provider_id) {
+            return Err("A provider can only appear once in the chain".to_string());
+        }
+    }
+    let value = serde_json::to_string(provider_ids)
+        .map_err(|_| "Unable to prepare the provider chain".to_string())?;
+    conn.execute(
+        "INSERT INTO settings (key, value, category) VALUES (?1, ?2, 'ai_config')
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+        params![CHAIN_KEY, value],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to save the provider chain: {}", e))
+}
+
+/// The requested provider goes first, followed by the rest of the saved chain.
+pub(crate) fn attempt_order(primary: &str, chain: &[String]) -> Vec<String> {
+    std::iter::once(primary.to_string())
+        .chain(chain.iter().filter(|id| *id != primary).cloned())
+        .collect()
+}
+
+/// Runs `call` against each provider in `order` until one answers. Providers
+/// without a usable credential are skipped; a failure that is not worth retrying
+/// elsewhere is returned straight away. Every outcome feeds the credential health
+/// metadata.
+pub(crate) async fn complete_with_fallback<T, F, Fut>(
+    app: &AppHandle,
+    order: &[String],
+    mut call: F,
+) -> Result<ProviderAnswer<T>, String>
+where
+    F: FnMut(String, StoredProviderCredential) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let mut fallbacks = Vec::new();
+    for provider_id in order {
+        let credential = match read_credential(provider_id) {
+            Ok(credential) => credential,
+            Err(error) => {
+                fallbacks.push(ProviderFailure {
+                    provider_id: provider_id.clone(),
+                    error,
+                });
+                continue;
+            }
+        };
+        let model = credential.model.clone();
+        let result = call(provider_id.clone(), credential).await;
+        credential_health::report(
+            app,
+            provider_id,
+            &result.as_ref().map(|_| ()).map_err(Clone::clone),
+        );
+        match result {
+            Ok(value) => {
+                return Ok(ProviderAnswer {
+                    provider_id: provider_id.clone(),
+                    model,
+                    fallbacks,
+                    value,
+                })
+            }
+            Err(error) if should_fall_back(&error) => fallbacks.push(ProviderFailure {
+                provider_id: provider_id.clone(),
+                error,
+            }),
+            Err(error) => return Err(error),
+        }
+    }
+    Err(exhausted_error(fallbacks))
+}
+
+fn exhausted_error(fallbacks: Vec<ProviderFailure>) -> String {
+    match fallbacks.as_slice() {
+        [] => "No AI provider is configured".to_string(),
+        [only] => only.error.clone(),
+        _ => format!(
+            "All AI providers failed: {}",
+            fallbacks
+                .iter()
+                .map(|failure| format!("{} ({})", failure.provider_id, failure.error))
+                .collect::<Vec<_>>()
+                .join("; ")
+        ),
+    }
+}
+
+#[tauri::command]
+pub(crate) fn ai_provider_chain_get(db: State<DatabaseState>) -> Result<Vec<String>, String> {
+    let conn = db.open_connection()?;
+    load_chain(&conn)
+}
+
+#[tauri::command]
+pub(crate) fn ai_provider_chain_set(
+    provider_ids: Vec<String>,
+    db: State<DatabaseState>,
+) -> Result<Vec<String>, String> {
+    let conn = db.open_connection()?;
+    save_chain(&conn, &provider_ids)?;
+    load_chain(&conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+
+    #[test]
+    fn falls_back_only_on_retryable_or_quota_failures() {
+        for error in [
+            "Unable to reach the AI provider",
+            "AI provider request failed with HTTP 429 Too Many Requests",
+            "AI provider request failed with HTTP 402 Payment Required",
+            "AI provider request failed with HTTP 503 Service Unavailable",
+            "AI provider request failed with HTTP 408 Request Timeout",
+        ] {
+            assert!(should_fall_back(error), "{error}");
+        }
+        for error in [
+            "AI provider request failed with HTTP 400 Bad Request",
+            "AI provider request failed with HTTP 401 Unauthorized",
+            "Prompt is invalid",
+            "The configured model does not support image input",
+            "AI provider returned an invalid response",
+        ] {
+            assert!(!should_fall_back(error), "{error}");
+        }
+
+        let chain = vec![
+            "deepseek".to_string(),
+            "openai".to_string(),
+            "qwen".to_string(),
+        ];
+        assert_eq!(
+            attempt_order("openai", &chain),
+            ["openai", "deepseek", "qwen"]
+        );
+        assert_eq!(attempt_order("moonshot", &[]), ["moonshot"]);
+        assert_eq!(exhausted_error(Vec::new()), "No AI provider is configured");
+        assert_eq!(
+            exhausted_error(vec![
+                ProviderFailure {
+                    provider_id: "openai".to_string(),
+                    error: "AI provider request failed with HTTP 429 Too Many Requests".to_string(),
+                },
+                ProviderFailure {
+                    provider_id: "deepseek".to_string(),
+                    error: "Unable to reach the AI provider".to_string(),
+                },
+            ]),
+            "All AI providers failed: openai (AI provider request failed with HTTP 429 Too Many Requests); deepseek (Unable to reach the AI provider)"
+        );
+    }
+
+    #[test]
+    fn stores_an_ordered_chain_of_known_providers() {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        assert!(load_chain(&conn).unwrap().is_empty());
+
+        let chain = vec!["deepseek".to_string(), "openai".to_string()];
+        save_chain(&conn, &chain).unwrap();
+        assert_eq!(load_chain(&conn).unwrap(), chain);
+
+        assert_eq!(
+            save_chain(&conn, &["openai".to_string(), "openai".to_string()]).unwrap_err(),
+            "A provider can only appear once in the chain"
+        );
+        assert_eq!(
+            save_chain(&conn, &["elsewhere".to_string()]).unwrap_err(),
+            "Unsupported AI provider credential"
+        );
+        conn.execute(
+            "UPDATE settings SET value = '[\"retired\", \"qwen\"]' WHERE key = ?1",
+            [CHAIN_KEY],
+        )
+        .unwrap();
+        assert_eq!(load_chain(&conn).unwrap(), ["qwen"]);
+    }
+}
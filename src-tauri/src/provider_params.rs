@@ -0,0 +1,454 @@
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Profile used when the provider is unknown, e.g. when testing a configuration
+/// before it is saved; it sends plain OpenAI Chat Completions fields.
+pub(crate) const GENERIC_PROVIDER_ID: &str = "custom";
+/// Sanity bound on what a caller may ask for before it is fitted to the provider.
+const MAX_REQUESTED_TOKENS: u32 = 128_000;
+const MAX_STOP_SEQUENCES: usize = 16;
+const MAX_STOP_CHARS: usize = 64;
+
+/// Provider-neutral sampling options; `chat_request_body` maps them onto the fields
+/// each provider accepts.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CompletionOptions {
+    pub max_tokens: u32,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub seed: Option<i64>,
+    #[serde(default)]
+    pub stop: Vec<String>,
+    pub presence_penalty: Option<f64>,
+}
+
+impl CompletionOptions {
+    pub(crate) fn new(max_tokens: u32, temperature: f64) -> Self {
+        Self {
+            max_tokens,
+            temperature: Some(temperature),
+            ..Self::default()
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let in_range = |value: Option<f64>, low: f64, high: f64| {
+            value.is_none_or(|value| (low..=high).contains(&value))
+        };
+        if self.max_tokens == 0
+            || self.max_tokens > MAX_REQUESTED_TOKENS
+            || !in_range(self.temperature, 0.0, 2.0)
+            || !in_range(self.top_p, 0.0, 1.0)
+            || !in_range(self.presence_penalty, -2.0, 2.0)
+            || self.stop.len() > MAX_STOP_SEQUENCES
+            || self
+                .stop
+                .iter()
+                .any(|stop| stop.is_empty() || stop.chars().count() > MAX_STOP_CHARS)
+        {
+            return Err("Completion options are invalid".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenField {
+    MaxTokens,
+    /// OpenAI reasoning models reject `max_tokens`.
+    MaxCompletionTokens,
+}
+
+impl TokenField {
+    fn name(self) -> &'static str {
+        match self {
+            Self::MaxTokens => "max_tokens",
+            Self::MaxCompletionTokens => "max_completion_tokens",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ProviderCapabilities {
+    pub token_field: TokenField,
+    /// Reasoning models spend part of the budget thinking, so tiny budgets are
+    /// raised to this floor to leave room for a visible answer.
+    pub min_output_tokens: u32,
+    pub max_output_tokens: u32,
+    /// Highest accepted `temperature`, or `None` when the model rejects it.
+    pub max_temperature: Option<f64>,
+    pub top_p: bool,
+    pub seed: bool,
+    pub presence_penalty: bool,
+    /// How many stop sequences are accepted; zero when `stop` is unsupported.
+    pub max_stop: usize,
+}
+
+const OPENAI_COMPATIBLE: ProviderCapabilities = ProviderCapabilities {
+    token_field: TokenField::MaxTokens,
+    min_output_tokens: 1,
+    max_output_tokens: 16_384,
+    max_temperature: Some(2.0),
+    top_p: true,
+    seed: true,
+    presence_penalty: true,
+    max_stop: 4,
+};
+
+const OPENAI_REASONING: ProviderCapabilities = ProviderCapabilities {
+    token_field: TokenField::MaxCompletionTokens,
+    min_output_tokens: 1_024,
+    max_output_tokens: 100_000,
+    max_temperature: None,
+    top_p: false,
+    seed: true,
+    presence_penalty: false,
+    max_stop: 0,
+};
+
+const DEEPSEEK_REASONING: ProviderCapabilities = ProviderCapabilities {
+    token_field: TokenField::MaxTokens,
+    min_output_tokens: 1_024,
+    max_output_tokens: 32_768,
+    max_temperature: None,
+    top_p: false,
+    seed: false,
+    presence_penalty: false,
+    max_stop: 16,
+};
+
+/// One row per preset in the settings page.
+const PROVIDER_CAPABILITIES: [(&str, ProviderCapabilities); 7] = [
+    ("openai", OPENAI_COMPATIBLE),
+    (
+        "deepseek",
+        ProviderCapabilities {
+            max_output_tokens: 8_192,
+            seed: false,
+            max_stop: 16,
+            ..OPENAI_COMPATIBLE
+        },
+    ),
+    (
+        "qwen",
+        ProviderCapabilities {
+            max_output_tokens: 8_192,
+            // DashScope requires temperature < 2.
+            max_temperature: Some(1.99),
+            ..OPENAI_COMPATIBLE
+        },
+    ),
+    (
+        "moonshot",
+        ProviderCapabilities {
+            max_output_tokens: 8_192,
+            max_temperature: Some(1.0),
+            seed: false,
+            max_stop: 5,
+            ..OPENAI_COMPATIBLE
+        },
+    ),
+    (
+        "zhipu",
+        ProviderCapabilities {
+            max_output_tokens: 4_095,
+            max_temperature: Some(1.0),
+            seed: false,
+            presence_penalty: false,
+            max_stop: 1,
+            ..OPENAI_COMPATIBLE
+        },
+    ),
+    (
+        "siliconflow",
+        ProviderCapabilities {
+            max_output_tokens: 16_384,
+            seed: false,
+            presence_penalty: false,
+            ..OPENAI_COMPATIBLE
+        },
+    ),
+    (GENERIC_PROVIDER_ID, OPENAI_COMPATIBLE),
+];
+
+/// Hosts of the preset endpoints. A credential pointing at one of them gets that
+/// preset's limits whichever profile it is stored under, so a DeepSeek key saved
+/// as "openai" or "custom" is still sent DeepSeek-shaped requests.
+const PROVIDER_HOSTS: [(&str, &str); 6] = [
+    ("api.openai.com", "openai"),
+    ("api.deepseek.com", "deepseek"),
+    ("dashscope.aliyuncs.com", "qwen"),
+    ("api.moonshot.cn", "moonshot"),
+    ("open.bigmodel.cn", "zhipu"),
+    ("api.siliconflow.cn", "siliconflow"),
+];
+
+/// Models whose limits differ from their provider's, matched by name prefix so
+/// dated snapshots and OpenAI-compatible proxies are covered too.
+const MODEL_CAPABILITIES: [(&str, ProviderCapabilities); 5] = [
+    ("o1", OPENAI_REASONING),
+    ("o3", OPENAI_REASONING),
+    ("o4", OPENAI_REASONING),
+    ("gpt-5", OPENAI_REASONING),
+    ("deepseek-reasoner", DEEPSEEK_REASONING),
+];
+
+/// The preset whose limits apply to a credential: the one its endpoint belongs to,
+/// or else the profile it is stored under.
+pub(crate) fn capability_profile<'a>(provider_id: &'a str, base_url: &str) -> &'a str {
+    let host = url::Url::parse(base_url.trim())
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase));
+    host.and_then(|host| {
+        PROVIDER_HOSTS
+            .iter()
+            .find(|(preset_host, _)| {
+                host == *preset_host || host.ends_with(&format!(".{preset_host}"))
+            })
+            .map(|(_, preset)| *preset)
+    })
+    .unwrap_or(provider_id)
+}
+
+pub(crate) fn capabilities(provider_id: &str, model: &str) -> ProviderCapabilities {
+    let model = model.trim().to_ascii_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    MODEL_CAPABILITIES
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .or_else(|| {
+            PROVIDER_CAPABILITIES
+                .iter()
+                .find(|(id, _)| *id == provider_id)
+        })
+        .map(|(_, capabilities)| *capabilities)
+        .unwrap_or(OPENAI_COMPATIBLE)
+}
+
+/// Builds a Chat Completions body for one user message. Options the provider or
+/// model does not accept are left out, and numeric ones are fitted to its limits,
+/// so the same options can be sent down a fallback chain of different providers.
+pub(crate) fn chat_request_body(
+    provider_id: &str,
+    model: &str,
+    content: Value,
+    options: &CompletionOptions,
+) -> Result<Value, String> {
+    options.validate()?;
+    let capabilities = capabilities(provider_id, model);
+    let mut body = Map::new();
+    body.insert("model".to_string(), json!(model));
+    body.insert(
+        "messages".to_string(),
+        json!([{ "role": "user", "content": content }]),
+    );
+    body.insert(
+        capabilities.token_field.name().to_string(),
+        json!(options.max_tokens.clamp(
+            capabilities.min_output_tokens,
+            capabilities.max_output_tokens
+        )),
+    );
+    if let (Some(temperature), Some(max)) = (options.temperature, capabilities.max_temperature) {
+        body.insert("temperature".to_string(), json!(temperature.min(max)));
+    }
+    if let Some(top_p) = options.top_p.filter(|_| capabilities.top_p) {
+        body.insert("top_p".to_string(), json!(top_p));
+    }
+    if let Some(seed) = options.seed.filter(|_| capabilities.seed) {
+        body.insert("seed".to_string(), json!(seed));
+    }
+    let stop: Vec<&String> = options.stop.iter().take(capabilities.max_stop).collect();
+    if !stop.is_empty() {
+        body.insert("stop".to_string(), json!(stop));
+    }
+    if let Some(penalty) = options
+        .presence_penalty
+        .filter(|_| capabilities.presence_penalty)
+    {
+        body.insert("presence_penalty".to_string(), json!(penalty));
+    }
+    Ok(Value::Object(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PROVIDER_IDS;
+
+    fn all_options() -> CompletionOptions {
+        CompletionOptions {
+            max_tokens: 20_000,
+            temperature: Some(1.5),
+            top_p: Some(0.9),
+            seed: Some(42),
+            stop: (1..=6).map(|n| format!("STOP{n}")).collect(),
+            presence_penalty: Some(0.5),
+        }
+    }
+
+    fn fields(body: &Value) -> Vec<&str> {
+        let mut fields: Vec<&str> = body
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        fields.sort_unstable();
+        fields
+    }
+
+    /// Preset, its default model, max tokens, temperature, stop count and the
+    /// optional fields that survive.
+    type Expectation = (
+        &'static str,
+        &'static str,
+        u32,
+        f64,
+        usize,
+        &'static [&'static str],
+    );
+
+    #[test]
+    fn maps_options_onto_every_preset() {
+        let expected: [Expectation; 7] = [
+            (
+                "openai",
+                "gpt-4o-mini",
+                16_384,
+                1.5,
+                4,
+                &["presence_penalty", "seed", "top_p"],
+            ),
+            (
+                "deepseek",
+                "deepseek-chat",
+                8_192,
+                1.5,
+                6,
+                &["presence_penalty", "top_p"],
+            ),
+            (
+                "qwen",
+                "qwen-plus",
+                8_192,
+                1.5,
+                4,
+                &["presence_penalty", "seed", "top_p"],
+            ),
+            (
+                "moonshot",
+                "moonshot-v1-8k",
+                8_192,
+                1.0,
+                5,
+                &["presence_penalty", "top_p"],
+            ),
+            ("zhipu", "glm-4-flash", 4_095, 1.0, 1, &["top_p"]),
+            (
+                "siliconflow",
+                "Qwen/Qwen2.5-7B-Instruct",
+                16_384,
+                1.5,
+                4,
+                &["top_p"],
+            ),
+            (
+                "custom",
+                "llama-3.1-70b",
+                16_384,
+                1.5,
+                4,
+                &["presence_penalty", "seed", "top_p"],
+            ),
+        ];
+        assert_eq!(
+            expected.map(|(provider_id, ..)| provider_id),
+            PROVIDER_IDS,
+            "every preset needs a row"
+        );
+
+        for (provider_id, model, max_tokens, temperature, stops, extra) in expected {
+            let body =
+                chat_request_body(provider_id, model, json!("你好"), &all_options()).unwrap();
+            assert_eq!(body["model"], model);
+            assert_eq!(body["messages"][0]["content"], "你好");
+            assert_eq!(body["max_tokens"], max_tokens, "{provider_id}");
+            assert_eq!(body["temperature"], temperature, "{provider_id}");
+            assert_eq!(
+                body["stop"].as_array().unwrap().len(),
+                stops,
+                "{provider_id}"
+            );
+
+            let mut wanted = vec!["max_tokens", "messages", "model", "stop", "temperature"];
+            wanted.extend_from_slice(extra);
+            wanted.sort_unstable();
+            assert_eq!(fields(&body), wanted, "{provider_id}");
+
+            let minimal = chat_request_body(
+                provider_id,
+                model,
+                json!("你好"),
+                &CompletionOptions::new(16, 0.0),
+            )
+            .unwrap();
+            assert_eq!(
+                fields(&minimal),
+                ["max_tokens", "messages", "model", "temperature"],
+                "{provider_id}"
+            );
+        }
+    }
+
+    #[test]
+    fn adapts_reasoning_models_and_rejects_invalid_options() {
+        for (provider_id, model) in [
+            ("openai", "o3-mini"),
+            ("openai", "gpt-5"),
+            ("custom", "openai/o1-2024-12-17"),
+        ] {
+            let body = chat_request_body(provider_id, model, json!("hi"), &all_options()).unwrap();
+            assert_eq!(
+                fields(&body),
+                ["max_completion_tokens", "messages", "model", "seed"],
+                "{model}"
+            );
+            assert_eq!(body["max_completion_tokens"], 20_000);
+            let small = chat_request_body(
+                provider_id,
+                model,
+                json!("hi"),
+                &CompletionOptions::new(16, 0.0),
+            )
+            .unwrap();
+            assert_eq!(small["max_completion_tokens"], 1_024);
+        }
+
+        let reasoner =
+            chat_request_body("deepseek", "deepseek-reasoner", json!("hi"), &all_options())
+                .unwrap();
+        assert_eq!(
+            fields(&reasoner),
+            ["max_tokens", "messages", "model", "stop"]
+        );
+
+        for invalid in [
+            CompletionOptions::new(0, 0.7),
+            CompletionOptions::new(16, 2.5),
+            CompletionOptions {
+                top_p: Some(1.5),
+                ..CompletionOptions::new(16, 0.7)
+            },
+            CompletionOptions {
+                stop: vec![String::new()],
+                ..CompletionOptions::new(16, 0.7)
+            },
+        ] {
+            assert_eq!(
+                chat_request_body("openai", "gpt-4o-mini", json!("hi"), &invalid).unwrap_err(),
+                "Completion options are invalid"
+            );
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+// Column list shared by every native reader of the recipes table.
+pub(crate) const RECIPE_COLUMNS: &str = "id, title, description, ingredients, instructions, cooking_time, difficulty, servings, category, tags, nutrition_info, image_url, cooking_methods, ai_provider, ai_model";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredRecipe {
+    pub id: i64,
+    pub title: String,
+    pub description: Option<String>,
+    pub ingredients: String,
+    pub instructions: String,
+    pub cooking_time: Option<i64>,
+    pub difficulty: Option<String>,
+    pub servings: Option<i64>,
+    pub category: Option<String>,
+    pub tags: Option<String>,
+    pub nutrition_info: Option<String>,
+    pub image_url: Option<String>,
+    pub cooking_methods: Option<String>,
+    pub ai_provider: Option<String>,
+    pub ai_model: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipeIngredient {
+    pub name: String,
+    pub amount: Option<f64>,
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct NutritionFacts {
+    pub calories: f64,
+    pub protein: f64,
+    pub carbs: f64,
+    pub fat: f64,
+    pub fiber: f64,
+    pub sodium: f64,
+}
+
+impl NutritionFacts {
+    pub(crate) fn scaled(self, factor: f64) -> Self {
+        Self {
+            calories: self.calories * factor,
+            protein: self.protein * factor,
+            carbs: self.carbs * factor,
+            fat: self.fat * factor,
+            fiber: self.fiber * factor,
+            sodium: self.sodium * factor,
+        }
+    }
+
+    pub(crate) fn add(&mut self, other: NutritionFacts) {
+        self.calories += other.calories;
+        self.protein += other.protein;
+        self.carbs += other.carbs;
+        self.fat += other.fat;
+        self.fiber += other.fiber;
+        self.sodium += other.sodium;
+    }
+}
+
+impl StoredRecipe {
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            description: row.get(2)?,
+            ingredients: row.get(3)?,
+            instructions: row.get(4)?,
+            cooking_time: row.get(5)?,
+            difficulty: row.get(6)?,
+            servings: row.get(7)?,
+            category: row.get(8)?,
+            tags: row.get(9)?,
+            nutrition_info: row.get(10)?,
+            image_url: row.get(11)?,
+            cooking_methods: row.get(12)?,
+            ai_provider: row.get(13)?,
+            ai_model: row.get(14)?,
+        })
+    }
+
+    pub(crate) fn ingredient_list(&self) -> Vec<RecipeIngredient> {
+        parse_ingredients(&self.ingredients)
+    }
+
+    pub(crate) fn step_list(&self) -> Vec<String> {
+        parse_steps(&self.instructions)
+    }
+
+    /// Nutrition for one serving, as AI providers and schema.org
+    /// `NutritionInformation` report it; scale by the planned servings, never by
+    /// `base_servings`.
+    pub(crate) fn nutrition(&self) -> Option<NutritionFacts> {
+        let value =
+            serde_json::from_str::<serde_json::Value>(self.nutrition_info.as_deref()?).ok()?;
+        nutrition_from_value(&value)
+    }
+
+    /// Servings the stored ingredient amounts refer to. Nutrition is per serving and
+    /// does not depend on it.
+    pub(crate) fn base_servings(&self) -> f64 {
+        self.servings.filter(|servings| *servings > 0).unwrap_or(1) as f64
+    }
+}
+
+/// A recipe that has not been stored yet, in the shape the frontend `Recipe` model uses.
+/// Ingredients may arrive as plain strings or `{ name, amount, unit }` objects.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecipeDraft {
+    pub title: String,
+    pub description: Option<String>,
+    #[serde(deserialize_with = "deserialize_ingredient_lines")]
+    pub ingredients: Vec<String>,
+    #[serde(deserialize_with = "deserialize_step_lines")]
+    pub instructions: Vec<String>,
+    pub cooking_time: Option<i64>,
+    pub difficulty: Option<String>,
+    pub servings: Option<i64>,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    pub nutrition_info: Option<serde_json::Value>,
+    pub image_url: Option<String>,
+    pub cooking_methods: Vec<String>,
+    pub ai_provider: Option<String>,
+    pub ai_model: Option<String>,
+}
+
+impl RecipeDraft {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.title.trim().is_empty() || self.title.len() > 200 {
+            return Err("Recipe title is invalid".to_string());
+        }
+        if self.ingredients.is_empty() || self.instructions.is_empty() {
+            return Err("Recipe must include ingredients and instructions".to_string());
+        }
+        if self
+            .servings
+            .is_some_and(|servings| !(1..=100).contains(&servings))
+        {
+            return Err("Servings must be between 1 and 100".to_string());
+        }
+        if self
+            .cooking_time
+            .is_some_and(|minutes| !(0..=10_080).contains(&minutes))
+        {
+            return Err("Cooking time is invalid".to_string());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn ingredient_list(&self) -> Vec<RecipeIngredient> {
+        self.ingredients
+            .iter()
+            .map(|line| parse_ingredient_text(line))
+            .filter(|ingredient| !ingredient.name.is_empty())
+            .collect()
+    }
+
+    pub(crate) fn nutrition(&self) -> Option<NutritionFacts> {
+        nutrition_from_value(self.nutrition_info.as_ref()?)
+    }
+}
+
+fn ingredient_line(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(text) => Some(text.trim().to_string()),
+        serde_json::Value::Object(map) => {
+            let name = map.get("name").and_then(serde_json::Value::as_str)?.trim();
+            let amount = match map.get("amount").or_else(|| map.get("quantity")) {
+                Some(serde_json::Value::String(text)) => text.trim().to_string(),
+                Some(serde_json::Value::Number(number)) => number.to_string(),
+                _ => String::new(),
+            };
+            let unit = map
+                .get("unit")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or("")
+                .trim();
+            Some(format!("{name} {amount}{unit}").trim().to_string())
+        }
+        _ => None,
+    }
+    .filter(|line| !line.is_empty())
+}
+
+fn step_line(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(text) => Some(text.trim().to_string()),
+        serde_json::Value::Object(map) => ["description", "text", "step"]
+            .iter()
+            .find_map(|key| map.get(*key).and_then(serde_json::Value::as_str))
+            .map(|text| text.trim().to_string()),
+        _ => None,
+    }
+    .filter(|line| !line.is_empty())
+}
+
+fn deserialize_ingredient_lines<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values = Vec::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(values.iter().filter_map(ingredient_line).collect())
+}
+
+fn deserialize_step_lines<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values = Vec::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(values.iter().filter_map(step_line).collect())
+}
+
+fn json_array(values: &[String]) -> String {
+    serde_json::Value::from(values.to_vec()).to_string()
+}
+
+pub(crate) fn insert_recipe(conn: &Connection, draft: &RecipeDraft) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO recipes (title, description, ingredients, instructions, cooking_time, difficulty,
+             servings, category, tags, nutrition_info, image_url, cooking_methods, ai_provider, ai_model)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            draft.title.trim(),
+            draft.description,
+            json_array(&draft.ingredients),
+            json_array(&draft.instructions),
+            draft.cooking_time,
+            draft.difficulty,
+            draft.servings.unwrap_or(4),
+            draft.category,
+            json_array(&draft.tags),
+            draft.nutrition_info.as_ref().map(serde_json::Value::to_string),
+            draft.image_url,
+            json_array(&draft.cooking_methods),
+            draft.ai_provider,
+            draft.ai_model,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub(crate) fn load_recipe(
+    conn: &Connection,
+    recipe_id: i64,
+) -> rusqlite::Result<Option<StoredRecipe>> {
+    conn.query_row(
+        &format!("SELECT {RECIPE_COLUMNS} FROM recipes WHERE id = ?1"),
+        [recipe_id],
+        StoredRecipe::from_row,
+    )
+    .optional()
+}
+
+pub(crate) fn load_all_recipes(conn: &Connection) -> Result<Vec<StoredRecipe>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {RECIPE_COLUMNS} FROM recipes ORDER BY id"))
+        .map_err(|e| format!("Failed to load recipes: {}", e))?;
+    let recipes = stmt
+        .query_map([], StoredRecipe::from_row)
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load recipes: {}", e))?;
+    Ok(recipes)
+}
+
+pub(crate) fn require_recipe(conn: &Connection, recipe_id: i64) -> Result<StoredRecipe, String> {
+    load_recipe(conn, recipe_id)
+        .map_err(|e| format!("Failed to load recipe: {}", e))?
+        .ok_or_else(|| "Recipe not found".to_string())
+}
+
+fn split_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses the `instructions` column: a JSON array of strings or `{ description }`
+/// objects, or plain text with one step per line.
+pub(crate) fn parse_steps(raw: &str) -> Vec<String> {
+    match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(serde_json::Value::Array(items)) => items.iter().filter_map(step_line).collect(),
+        _ => split_lines(raw),
+    }
+}
+
+/// Parses the `ingredients` column into display lines such as `"番茄 2个"`.
+pub(crate) fn parse_ingredient_lines(raw: &str) -> Vec<String> {
+    match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(serde_json::Value::Array(items)) => items.iter().filter_map(ingredient_line).collect(),
+        _ => split_lines(raw),
+    }
+}
+
+/// Parses the `ingredients` column, which holds either strings such as `"番茄 2个"` or
+/// `{ name, amount, unit }` objects depending on which code path saved the recipe.
+pub(crate) fn parse_ingredients(raw: &str) -> Vec<RecipeIngredient> {
+    let items = match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(serde_json::Value::Array(items)) => items,
+        _ => {
+            return split_lines(raw)
+                .iter()
+                .map(|line| parse_ingredient_text(line))
+                .collect()
+        }
+    };
+
+    items
+        .iter()
+        .filter_map(|item| match item {
+            serde_json::Value::String(text) => Some(parse_ingredient_text(text)),
+            serde_json::Value::Object(map) => {
+                let name = map.get("name").and_then(serde_json::Value::as_str)?.trim();
+                if name.is_empty() {
+                    return None;
+                }
+                let quantity = map.get("amount").or_else(|| map.get("quantity"));
+                let unit = map
+                    .get("unit")
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::trim)
+                    .filter(|unit| !unit.is_empty())
+                    .map(str::to_string);
+                let (amount, parsed_unit) = match quantity {
+                    Some(serde_json::Value::String(text)) => parse_quantity(text),
+                    Some(value) => (value.as_f64(), None),
+                    None => (None, None),
+                };
+                Some(RecipeIngredient {
+                    name: name.to_string(),
+                    amount,
+                    unit: unit.or(parsed_unit),
+                })
+            }
+            _ => None,
+        })
+        .filter(|ingredient| !ingredient.name.is_empty())
+        .collect()
+}
+
+pub(crate) fn parse_ingredient_text(text: &str) -> RecipeIngredient {
+    let text = text.trim();
+    // "200g 猪肉" puts the quantity first, "番茄 2个" and "番茄2个" put it last.
+    if text.starts_with(|c: char| c.is_ascii_digit()) {
+        if let Some((quantity, name)) = text.split_once(char::is_whitespace) {
+            let (amount, unit) = parse_quantity(quantity);
+            return RecipeIngredient {
+                name: name.trim().to_string(),
+                amount,
+                unit,
+            };
+        }
+    }
+
+    let split_at = text
+        .char_indices()
+        .find(|(_, c)| c.is_ascii_digit() || matches!(c, '半' | '适' | '少'))
+        .map(|(index, _)| index)
+        .filter(|index| *index > 0);
+    match split_at {
+        Some(index) => {
+            let (name, quantity) = text.split_at(index);
+            let (amount, unit) = parse_quantity(quantity);
+            RecipeIngredient {
+                name: name.trim().trim_end_matches([':', '：']).trim().to_string(),
+                amount,
+                unit,
+            }
+        }
+        None => RecipeIngredient {
+            name: text.to_string(),
+            amount: None,
+            unit: None,
+        },
+    }
+}
+
+/// Splits `"1-2勺"` into `(Some(2.0), Some("勺"))`; ranges resolve to their upper bound.
+pub(crate) fn parse_quantity(text: &str) -> (Option<f64>, Option<String>) {
+    let text = text.trim();
+    if let Some(rest) = text.strip_prefix('半') {
+        return (Some(0.5), non_empty(rest));
+    }
+
+    let numeric_end = text
+        .char_indices()
+        .find(|(_, c)| !(c.is_ascii_digit() || matches!(c, '.' | '/' | '-' | '~' | ' ')))
+        .map(|(index, _)| index)
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(numeric_end);
+    let amount = number
+        .split(['-', '~'])
+        .filter_map(parse_number)
+        .fold(None, |max: Option<f64>, value| {
+            Some(max.map_or(value, |max| max.max(value)))
+        });
+
+    match amount {
+        Some(amount) => (Some(amount), non_empty(unit)),
+        None => (None, non_empty(text)),
+    }
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    match text.split_once('/') {
+        Some((numerator, denominator)) => {
+            let denominator = denominator.trim().parse::<f64>().ok()?;
+            (denominator != 0.0).then_some(numerator.trim().parse::<f64>().ok()? / denominator)
+        }
+        None => text.parse::<f64>().ok(),
+    }
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+pub(crate) fn nutrition_from_value(value: &serde_json::Value) -> Option<NutritionFacts> {
+    let field = |key: &str| value.get(key).and_then(leading_number).unwrap_or(0.0);
+    let facts = NutritionFacts {
+        calories: field("calories"),
+        protein: field("protein"),
+        carbs: field("carbs"),
+        fat: field("fat"),
+        fiber: field("fiber"),
+        sodium: field("sodium"),
+    };
+    (facts != NutritionFacts::default()).then_some(facts)
+}
+
+// AI responses sometimes send "350 kcal" or "12g" instead of bare numbers.
+pub(crate) fn leading_number(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Number(number) => number.as_f64(),
+        serde_json::Value::String(text) => parse_quantity(text).0,
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_string_and_object_ingredients() {
+        let ingredients = parse_ingredients(
+            r#"["番茄 2个", "鸡蛋3个", "200g 猪肉", "盐 适量", "生抽 1-2勺", {"name": "葱", "amount": 1, "unit": "根"}]"#,
+        );
+
+        let summary: Vec<(&str, Option<f64>, Option<&str>)> = ingredients
+            .iter()
+            .map(|item| (item.name.as_str(), item.amount, item.unit.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("番茄", Some(2.0), Some("个")),
+                ("鸡蛋", Some(3.0), Some("个")),
+                ("猪肉", Some(200.0), Some("g")),
+                ("盐", None, Some("适量")),
+                ("生抽", Some(2.0), Some("勺")),
+                ("葱", Some(1.0), Some("根")),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_nutrition_with_unit_suffixes() {
+        let recipe = StoredRecipe {
+            id: 1,
+            title: "番茄炒蛋".to_string(),
+            description: None,
+            ingredients: "[]".to_string(),
+            instructions: "[]".to_string(),
+            cooking_time: Some(15),
+            difficulty: None,
+            servings: Some(2),
+            category: None,
+            tags: None,
+            nutrition_info: Some(
+                r#"{"calories": "320 kcal", "protein": 18, "fat": "12g"}"#.to_string(),
+            ),
+            image_url: None,
+            cooking_methods: None,
+            ai_provider: None,
+            ai_model: None,
+        };
+
+        let nutrition = recipe.nutrition().expect("nutrition");
+        assert_eq!(nutrition.calories, 320.0);
+        assert_eq!(nutrition.protein, 18.0);
+        assert_eq!(nutrition.fat, 12.0);
+        assert_eq!(recipe.base_servings(), 2.0);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::images::MAX_IMAGE_BYTES;
+use crate::provider_params::CompletionOptions;
+use crate::{read_credential, request_chat};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use image::{codecs::jpeg::JpegEncoder, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Longest edge sent to the provider; larger photos cost tokens without helping.
+const RECOGNITION_MAX_EDGE: u32 = 1024;
+const RECOGNITION_JPEG_QUALITY: u8 = 85;
+const RECOGNITION_MAX_TOKENS: u32 = 1_024;
+const RECOGNITION_TEMPERATURE: f64 = 0.2;
+const INGREDIENT_CATEGORIES: [&str; 10] = [
+    "蔬菜", "肉类", "海鲜", "蛋奶", "谷物", "调料", "水果", "坚果", "豆类", "其他",
+];
+/// Models that are known to be text-only, checked before any upload.
+const TEXT_ONLY_MODEL_PREFIXES: [&str; 6] = [
+    "gpt-3.5",
+    "o1-mini",
+    "o3-mini",
+    "deepseek-chat",
+    "deepseek-reasoner",
+    "text-",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecognizedIngredient {
+    pub name: String,
+    pub confidence: f64,
+    pub category: String,
+    pub quantity: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IngredientRecognition {
+    pub ingredients: Vec<RecognizedIngredient>,
+    pub model: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawIngredient {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    confidence: Option<f64>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    quantity: Option<String>,
+}
+
+pub(crate) fn supports_image_input(model: &str) -> bool {
+    let model = model.trim().to_ascii_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    !TEXT_ONLY_MODEL_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+/// Decodes the upload, shrinks it to `RECOGNITION_MAX_EDGE` and re-encodes it as
+/// JPEG, which also drops EXIF data such as GPS coordinates.
+pub(crate) fn prepare_image(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if bytes.is_empty() || bytes.len() > MAX_IMAGE_BYTES {
+        return Err("Image must be between 1 byte and 20 MB".to_string());
+    }
+    let format = image::guess_format(bytes).map_err(|_| "Unsupported image format".to_string())?;
+    if !matches!(
+        format,
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP | ImageFormat::Gif
+    ) {
+        return Err("Unsupported image format".to_string());
+    }
+    let decoded = image::load_from_memory_with_format(bytes, format)
+        .map_err(|_| "Image could not be decoded".to_string())?;
+    let resized = if decoded.width().max(decoded.height()) > RECOGNITION_MAX_EDGE {
+        decoded.thumbnail(RECOGNITION_MAX_EDGE, RECOGNITION_MAX_EDGE)
+    } else {
+        decoded
+    };
+    let mut encoded = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded, RECOGNITION_JPEG_QUALITY)
+        .encode_image(&resized.to_rgb8())
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(encoded)
+}
+
+pub(crate) fn recognition_content(jpeg: &[u8]) -> serde_json::Value {
+    let prompt = format!(
+        "请识别这张图片中可以用于烹饪的所有食材。\n\
+         只返回 JSON 数组，每一项包含：name（中文食材名）、confidence（0 到 1 之间的置信度）、\
+         category（取值为 {}）、quantity（估计的数量，无法判断时为 null）。\n\
+         如果图片中没有食材，返回 []。不要包含任何解释文字。",
+        INGREDIENT_CATEGORIES.join("、")
+    );
+    serde_json::json!([
+        { "type": "text", "text": prompt },
+        {
+            "type": "image_url",
+            "image_url": {
+                "url": format!("data:image/jpeg;base64,{}", STANDARD.encode(jpeg)),
+                "detail": "auto",
+            },
+        },
+    ])
+}
+
+/// Accepts a bare array or an `{"ingredients": [...]}` object, optionally wrapped
+/// in code fences. Confidence given as a percentage is scaled down, names are
+/// de-duplicated and the list is ordered from most to least certain.
+pub(crate) fn parse_recognition(content: &str) -> Result<Vec<RecognizedIngredient>, String> {
+    let start = content.find(['[', '{']);
+    let end = content.rfind([']', '}']);
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => return Err("AI provider did not return an ingredient list".to_string()),
+    };
+    let value: serde_json::Value = serde_json::from_str(json)
+        .map_err(|_| "AI provider returned an unreadable ingredient list".to_string())?;
+    let items = match value {
+        serde_json::Value::Object(mut object) => object
+            .remove("ingredients")
+            .unwrap_or(serde_json::Value::Array(Vec::new())),
+        other => other,
+    };
+    let raw: Vec<RawIngredient> = serde_json::from_value(items)
+        .map_err(|_| "AI provider returned an unreadable ingredient list".to_string())?;
+
+    let mut seen = HashSet::new();
+    let mut ingredients: Vec<RecognizedIngredient> = raw
+        .into_iter()
+        .filter_map(|item| {
+            let name = item.name.trim().to_string();
+            if name.is_empty() || !seen.insert(name.clone()) {
+                return None;
+            }
+            let confidence = match item.confidence {
+                Some(value) if value > 1.0 && value <= 100.0 => value / 100.0,
+                Some(value) if value.is_finite() => value.clamp(0.0, 1.0),
+                _ => 0.5,
+            };
+            let category = item
+                .category
+                .map(|category| category.trim().to_string())
+                .filter(|category| INGREDIENT_CATEGORIES.contains(&category.as_str()))
+                .unwrap_or_else(|| "其他".to_string());
+            let quantity = item
+                .quantity
+                .map(|quantity| quantity.trim().to_string())
+                .filter(|quantity| !quantity.is_empty());
+            Some(RecognizedIngredient {
+                name,
+                confidence,
+                category,
+                quantity,
+            })
+        })
+        .collect();
+    ingredients.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    Ok(ingredients)
+}
+
+/// Identifies the ingredients in a photo with the configured vision model.
+#[tauri::command]
+pub(crate) async fn ai_recognize_ingredients(
+    provider_id: String,
+    bytes: Vec<u8>,
+) -> Result<IngredientRecognition, String> {
+    let credential = read_credential(&provider_id)?;
+    if !supports_image_input(&credential.model) {
+        return Err("The configured model does not support image input".to_string());
+    }
+    let jpeg = tauri::async_runtime::spawn_blocking(move || prepare_image(&bytes))
+        .await
+        .map_err(|_| "Failed to prepare image".to_string())??;
+
+    let content = request_chat(
+        &provider_id,
+        &credential,
+        recognition_content(&jpeg),
+        &CompletionOptions::new(RECOGNITION_MAX_TOKENS, RECOGNITION_TEMPERATURE),
+    )
+    .await?;
+    Ok(IngredientRecognition {
+        ingredients: parse_recognition(&content)?,
+        model: credential.model,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, RgbaImage};
+    use std::io::Cursor;
+
+    #[test]
+    fn prepares_downscaled_jpeg_data_uri() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::new(2048, 1024))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let jpeg = prepare_image(&png).unwrap();
+        let decoded = image::load_from_memory_with_format(&jpeg, ImageFormat::Jpeg).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (1024, 512));
+
+        let content = recognition_content(&jpeg);
+        let url = content
+            .pointer("/1/image_url/url")
+            .and_then(serde_json::Value::as_str)
+            .unwrap();
+        assert!(url.starts_with("data:image/jpeg;base64,/9j/"));
+        assert_eq!(
+            prepare_image(b"not an image").unwrap_err(),
+            "Unsupported image format"
+        );
+
+        assert!(supports_image_input("gpt-4o-mini"));
+        assert!(!supports_image_input("deepseek-chat"));
+        assert!(!supports_image_input("openai/gpt-3.5-turbo"));
+    }
+
+    #[test]
+    fn parses_ingredient_lists() {
+        let content = "```json\n{\"ingredients\": [\
+            {\"name\": \"番茄\", \"confidence\": 0.6, \"category\": \"蔬菜\", \"quantity\": \"2个\"},\
+            {\"name\": \"鸡蛋\", \"confidence\": 95, \"category\": \"蛋奶\"},\
+            {\"name\": \"番茄\", \"confidence\": 0.9},\
+            {\"name\": \" \", \"confidence\": 1},\
+            {\"name\": \"神秘粉末\", \"category\": \"未知\", \"quantity\": \"\"}\
+        ]}\n```";
+        let ingredients = parse_recognition(content).unwrap();
+        let names: Vec<_> = ingredients.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["鸡蛋", "番茄", "神秘粉末"]);
+        assert_eq!(ingredients[0].confidence, 0.95);
+        assert_eq!(ingredients[1].quantity.as_deref(), Some("2个"));
+        assert_eq!(ingredients[2].category, "其他");
+        assert_eq!(ingredients[2].quantity, None);
+
+        assert!(parse_recognition("[]").unwrap().is_empty());
+        assert!(parse_recognition("抱歉，我无法识别。").is_err());
+    }
+}
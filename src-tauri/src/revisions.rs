@@ -0,0 +1,380 @@
+use crate::recipe::{require_recipe, RecipeDraft};
+use crate::DatabaseState;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::State;
+
+// Fields captured in every snapshot, in display order. List-valued columns hold JSON text.
+const SNAPSHOT_FIELDS: [&str; 14] = [
+    "title",
+    "description",
+    "ingredients",
+    "instructions",
+    "cooking_time",
+    "difficulty",
+    "servings",
+    "category",
+    "tags",
+    "nutrition_info",
+    "image_url",
+    "cooking_methods",
+    "ai_provider",
+    "ai_model",
+];
+const LIST_FIELDS: [&str; 4] = ["ingredients", "instructions", "tags", "cooking_methods"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeRevision {
+    pub id: i64,
+    pub recipe_id: i64,
+    pub revision_number: i64,
+    pub source: String,
+    pub snapshot: serde_json::Value,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+fn revision_from_row(row: &rusqlite::Row) -> rusqlite::Result<RecipeRevision> {
+    let snapshot: String = row.get(4)?;
+    Ok(RecipeRevision {
+        id: row.get(0)?,
+        recipe_id: row.get(1)?,
+        revision_number: row.get(2)?,
+        source: row.get(3)?,
+        snapshot: serde_json::from_str(&snapshot).unwrap_or(serde_json::Value::Null),
+        created_at: row.get(5)?,
+    })
+}
+
+pub(crate) fn list_revisions(
+    conn: &Connection,
+    recipe_id: i64,
+) -> Result<Vec<RecipeRevision>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, recipe_id, revision_number, source, snapshot, created_at
+             FROM recipe_revisions WHERE recipe_id = ?1 ORDER BY revision_number",
+        )
+        .map_err(|e| format!("Failed to load recipe history: {}", e))?;
+    let revisions = stmt
+        .query_map([recipe_id], revision_from_row)
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load recipe history: {}", e))?;
+    Ok(revisions)
+}
+
+fn require_revision(
+    conn: &Connection,
+    recipe_id: i64,
+    revision_id: i64,
+) -> Result<RecipeRevision, String> {
+    conn.query_row(
+        "SELECT id, recipe_id, revision_number, source, snapshot, created_at
+         FROM recipe_revisions WHERE id = ?1 AND recipe_id = ?2",
+        params![revision_id, recipe_id],
+        revision_from_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load recipe revision: {}", e))?
+    .ok_or_else(|| "Recipe revision not found".to_string())
+}
+
+fn latest_revision(conn: &Connection, recipe_id: i64) -> Result<RecipeRevision, String> {
+    list_revisions(conn, recipe_id)?
+        .pop()
+        .ok_or_else(|| "Recipe has no recorded revisions".to_string())
+}
+
+/// Applies a native edit. The `recipe_revisions` triggers snapshot the new state,
+/// so edits made through `database_execute` are tracked the same way.
+pub(crate) fn update_recipe(
+    conn: &Connection,
+    recipe_id: i64,
+    draft: &RecipeDraft,
+) -> Result<(), String> {
+    draft.validate()?;
+    require_recipe(conn, recipe_id)?;
+    let json_array = |values: &[String]| serde_json::Value::from(values.to_vec()).to_string();
+    conn.execute(
+        "UPDATE recipes SET title = ?1, description = ?2, ingredients = ?3, instructions = ?4,
+             cooking_time = ?5, difficulty = ?6, servings = ?7, category = ?8, tags = ?9,
+             nutrition_info = ?10, image_url = ?11, cooking_methods = ?12
+         WHERE id = ?13",
+        params![
+            draft.title.trim(),
+            draft.description,
+            json_array(&draft.ingredients),
+            json_array(&draft.instructions),
+            draft.cooking_time,
+            draft.difficulty,
+            draft.servings.unwrap_or(4),
+            draft.category,
+            json_array(&draft.tags),
+            draft
+                .nutrition_info
+                .as_ref()
+                .map(serde_json::Value::to_string),
+            draft.image_url,
+            json_array(&draft.cooking_methods),
+            recipe_id,
+        ],
+    )
+    .map_err(|e| format!("Failed to update recipe: {}", e))?;
+    Ok(())
+}
+
+pub(crate) fn revert_recipe(
+    conn: &Connection,
+    recipe_id: i64,
+    revision_id: i64,
+) -> Result<RecipeRevision, String> {
+    let target = require_revision(conn, recipe_id, revision_id)?;
+    let field = |name: &str| target.snapshot.get(name).cloned().unwrap_or_default();
+    let sql_value = |value: serde_json::Value| -> rusqlite::types::Value {
+        match value {
+            serde_json::Value::Null => rusqlite::types::Value::Null,
+            serde_json::Value::Number(number) => number
+                .as_i64()
+                .map(rusqlite::types::Value::Integer)
+                .unwrap_or_else(|| rusqlite::types::Value::Real(number.as_f64().unwrap_or(0.0))),
+            serde_json::Value::String(text) => rusqlite::types::Value::Text(text),
+            other => rusqlite::types::Value::Text(other.to_string()),
+        }
+    };
+
+    let before = latest_revision(conn, recipe_id)?;
+    let assignments = SNAPSHOT_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(index, name)| format!("{name} = ?{}", index + 1))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut values: Vec<rusqlite::types::Value> = SNAPSHOT_FIELDS
+        .iter()
+        .map(|name| sql_value(field(name)))
+        .collect();
+    values.push(rusqlite::types::Value::Integer(recipe_id));
+    conn.execute(
+        &format!(
+            "UPDATE recipes SET {assignments} WHERE id = ?{}",
+            SNAPSHOT_FIELDS.len() + 1
+        ),
+        rusqlite::params_from_iter(values),
+    )
+    .map_err(|e| format!("Failed to revert recipe: {}", e))?;
+
+    let after = latest_revision(conn, recipe_id)?;
+    if after.id == before.id {
+        // The recipe already matched the target revision, so no trigger fired.
+        return Ok(after);
+    }
+    conn.execute(
+        "UPDATE recipe_revisions SET source = 'revert:' || ?1 WHERE id = ?2",
+        params![target.revision_number, after.id],
+    )
+    .map_err(|e| format!("Failed to record recipe revert: {}", e))?;
+    require_revision(conn, recipe_id, after.id)
+}
+
+fn list_entries(value: &serde_json::Value) -> Vec<String> {
+    let parsed = match value {
+        serde_json::Value::String(text) => serde_json::from_str::<serde_json::Value>(text)
+            .unwrap_or_else(|_| serde_json::Value::String(text.clone())),
+        other => other.clone(),
+    };
+    match parsed {
+        serde_json::Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                serde_json::Value::String(text) => text,
+                other => other.to_string(),
+            })
+            .collect(),
+        serde_json::Value::Null => Vec::new(),
+        serde_json::Value::String(text) => vec![text],
+        other => vec![other.to_string()],
+    }
+}
+
+/// Field-level differences between two snapshots; list columns also report the
+/// individual entries that were added or removed.
+pub(crate) fn diff_snapshots(
+    before: &serde_json::Value,
+    after: &serde_json::Value,
+) -> Vec<FieldChange> {
+    SNAPSHOT_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let old = before.get(*field).cloned().unwrap_or_default();
+            let new = after.get(*field).cloned().unwrap_or_default();
+            if old == new {
+                return None;
+            }
+            let (added, removed) = if LIST_FIELDS.contains(field) {
+                let old_items = list_entries(&old);
+                let new_items = list_entries(&new);
+                (
+                    new_items
+                        .iter()
+                        .filter(|item| !old_items.contains(item))
+                        .cloned()
+                        .collect(),
+                    old_items
+                        .iter()
+                        .filter(|item| !new_items.contains(item))
+                        .cloned()
+                        .collect(),
+                )
+            } else {
+                (Vec::new(), Vec::new())
+            };
+            Some(FieldChange {
+                field: field.to_string(),
+                before: old,
+                after: new,
+                added,
+                removed,
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub(crate) fn recipe_update(
+    recipe_id: i64,
+    recipe: RecipeDraft,
+    db: State<DatabaseState>,
+) -> Result<(), String> {
+    let conn = db.open_connection()?;
+    update_recipe(&conn, recipe_id, &recipe)
+}
+
+#[tauri::command]
+pub(crate) fn recipe_history(
+    recipe_id: i64,
+    db: State<DatabaseState>,
+) -> Result<Vec<RecipeRevision>, String> {
+    let conn = db.open_connection()?;
+    list_revisions(&conn, recipe_id)
+}
+
+#[tauri::command]
+pub(crate) fn recipe_revert(
+    recipe_id: i64,
+    revision_id: i64,
+    db: State<DatabaseState>,
+) -> Result<RecipeRevision, String> {
+    let conn = db.open_connection()?;
+    revert_recipe(&conn, recipe_id, revision_id)
+}
+
+/// Compares two revisions. Defaults to the original (usually the model's output)
+/// against the current state of the recipe.
+#[tauri::command]
+pub(crate) fn recipe_diff(
+    recipe_id: i64,
+    from_revision_id: Option<i64>,
+    to_revision_id: Option<i64>,
+    db: State<DatabaseState>,
+) -> Result<Vec<FieldChange>, String> {
+    let conn = db.open_connection()?;
+    let revisions = list_revisions(&conn, recipe_id)?;
+    let pick = |revision_id: Option<i64>, newest: bool| {
+        let found = match revision_id {
+            Some(id) => revisions.iter().find(|revision| revision.id == id),
+            None if newest => revisions.last(),
+            None => revisions.first(),
+        };
+        found.ok_or_else(|| "Recipe revision not found".to_string())
+    };
+    let from = pick(from_revision_id, false)?;
+    let to = pick(to_revision_id, true)?;
+    Ok(diff_snapshots(&from.snapshot, &to.snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+    use crate::recipe::insert_recipe;
+
+    fn draft(title: &str, ingredients: &[&str]) -> RecipeDraft {
+        RecipeDraft {
+            title: title.to_string(),
+            ingredients: ingredients.iter().map(|item| item.to_string()).collect(),
+            instructions: vec!["炒".to_string()],
+            servings: Some(2),
+            ..RecipeDraft::default()
+        }
+    }
+
+    #[test]
+    fn records_every_change_including_raw_sql_updates() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        let recipe_id =
+            insert_recipe(&conn, &draft("番茄炒蛋", &["番茄 2个", "鸡蛋 3个"])).expect("insert");
+
+        update_recipe(
+            &conn,
+            recipe_id,
+            &draft("番茄炒蛋", &["番茄 3个", "鸡蛋 3个", "葱 1根"]),
+        )
+        .expect("update");
+        conn.execute(
+            "UPDATE recipes SET view_count = view_count + 1 WHERE id = ?1",
+            [recipe_id],
+        )
+        .expect("bump views");
+        conn.execute(
+            "UPDATE recipes SET title = '西红柿炒鸡蛋' WHERE id = ?1",
+            [recipe_id],
+        )
+        .expect("raw rename");
+
+        let history = list_revisions(&conn, recipe_id).expect("history");
+        let sources: Vec<_> = history
+            .iter()
+            .map(|revision| revision.source.as_str())
+            .collect();
+        assert_eq!(sources, vec!["created", "edit", "edit"]);
+        assert_eq!(history[2].snapshot["title"], "西红柿炒鸡蛋");
+
+        let changes = diff_snapshots(&history[0].snapshot, &history[2].snapshot);
+        let fields: Vec<_> = changes.iter().map(|change| change.field.as_str()).collect();
+        assert_eq!(fields, vec!["title", "ingredients"]);
+        assert_eq!(changes[1].added, vec!["番茄 3个", "葱 1根"]);
+        assert_eq!(changes[1].removed, vec!["番茄 2个"]);
+    }
+
+    #[test]
+    fn reverts_to_an_earlier_revision_as_a_new_revision() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        let recipe_id = insert_recipe(&conn, &draft("原版", &["番茄 2个"])).expect("insert");
+        update_recipe(&conn, recipe_id, &draft("改版", &["番茄 5个"])).expect("update");
+        let original = list_revisions(&conn, recipe_id).expect("history")[0].clone();
+
+        let reverted = revert_recipe(&conn, recipe_id, original.id).expect("revert");
+        assert_eq!(reverted.revision_number, 3);
+        assert_eq!(reverted.source, "revert:1");
+        assert!(diff_snapshots(&original.snapshot, &reverted.snapshot).is_empty());
+
+        let title: String = conn
+            .query_row(
+                "SELECT title FROM recipes WHERE id = ?1",
+                [recipe_id],
+                |row| row.get(0),
+            )
+            .expect("title");
+        assert_eq!(title, "原版");
+    }
+}
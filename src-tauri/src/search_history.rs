@@ -0,0 +1,525 @@
+use crate::importer::split_tags;
+use crate::matching::canonical_ingredient;
+use crate::profiles::ProfileState;
+use crate::DatabaseState;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tauri::State;
+
+const RETENTION_SETTING_KEY: &str = "search_history_retention_days";
+const DEFAULT_RETENTION_DAYS: u32 = 365;
+const MAX_RETENTION_DAYS: u32 = 3_650;
+/// Only the newest searches feed the analytics; older ones add little signal.
+const ANALYTICS_WINDOW: i64 = 5_000;
+/// A search this many days old counts half as much as one made today.
+const SUGGESTION_HALF_LIFE_DAYS: f64 = 30.0;
+const MAX_RESULTS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SearchRecordInput {
+    pub ingredients: Vec<String>,
+    #[serde(default)]
+    pub cooking_methods: Vec<String>,
+    #[serde(default)]
+    pub dietary_restrictions: Vec<String>,
+    pub result_count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IngredientFrequency {
+    pub name: String,
+    pub count: i64,
+    pub last_searched: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecentSearch {
+    pub ingredients: Vec<String>,
+    pub cooking_methods: Vec<String>,
+    pub dietary_restrictions: Vec<String>,
+    pub result_count: i64,
+    pub search_time: String,
+    /// How many stored searches collapsed into this entry.
+    pub occurrences: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IngredientSuggestion {
+    pub name: String,
+    pub score: f64,
+    pub count: i64,
+}
+
+struct SearchRow {
+    ingredients: Vec<String>,
+    cooking_methods: Vec<String>,
+    dietary_restrictions: Vec<String>,
+    result_count: i64,
+    search_time: String,
+    age_days: f64,
+}
+
+/// History rows written by the web layer hold either a JSON array or a comma list.
+fn stored_terms(raw: Option<&str>) -> Vec<String> {
+    let Some(raw) = raw.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                serde_json::Value::String(text) => Some(text.trim().to_string()),
+                serde_json::Value::Object(map) => map
+                    .get("name")
+                    .and_then(serde_json::Value::as_str)
+                    .map(|name| name.trim().to_string()),
+                _ => None,
+            })
+            .filter(|term| !term.is_empty())
+            .collect(),
+        _ => split_tags(raw),
+    }
+}
+
+fn cleaned_terms(terms: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    terms
+        .iter()
+        .map(|term| term.trim().to_string())
+        .filter(|term| !term.is_empty() && term.chars().count() <= 100)
+        .filter(|term| seen.insert(term.clone()))
+        .collect()
+}
+
+fn load_rows(conn: &Connection, session_id: &str) -> Result<Vec<SearchRow>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT ingredients, cooking_methods, dietary_restrictions, COALESCE(result_count, 0),
+                    search_time, MAX(julianday('now') - julianday(search_time), 0)
+             FROM search_history
+             WHERE session_id = ?1
+             ORDER BY search_time DESC, id DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| format!("Failed to load search history: {}", e))?;
+    stmt.query_map(params![session_id, ANALYTICS_WINDOW], |row| {
+        Ok(SearchRow {
+            ingredients: stored_terms(row.get::<_, Option<String>>(0)?.as_deref()),
+            cooking_methods: stored_terms(row.get::<_, Option<String>>(1)?.as_deref()),
+            dietary_restrictions: stored_terms(row.get::<_, Option<String>>(2)?.as_deref()),
+            result_count: row.get(3)?,
+            search_time: row.get(4)?,
+            age_days: row.get::<_, Option<f64>>(5)?.unwrap_or(0.0),
+        })
+    })
+    .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+    .map_err(|e| format!("Failed to load search history: {}", e))
+}
+
+pub(crate) fn record_search(
+    conn: &Connection,
+    session_id: &str,
+    search: &SearchRecordInput,
+) -> Result<i64, String> {
+    let ingredients = cleaned_terms(&search.ingredients);
+    if ingredients.is_empty() {
+        return Err("A search needs at least one ingredient".to_string());
+    }
+    if search.result_count < 0 {
+        return Err("Result count must not be negative".to_string());
+    }
+    let list = |terms: &[String]| serde_json::Value::from(cleaned_terms(terms)).to_string();
+    conn.execute(
+        "INSERT INTO search_history (session_id, ingredients, cooking_methods, dietary_restrictions, result_count)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            session_id,
+            serde_json::Value::from(ingredients).to_string(),
+            list(&search.cooking_methods),
+            list(&search.dietary_restrictions),
+            search.result_count,
+        ],
+    )
+    .map_err(|e| format!("Failed to save search: {}", e))?;
+    let search_id = conn.last_insert_rowid();
+    prune_history(conn)?;
+    Ok(search_id)
+}
+
+/// Counts ingredients under their canonical synonym, so "西红柿" and "番茄" add up,
+/// and reports each under the spelling the user typed most recently.
+pub(crate) fn frequent_ingredients(
+    conn: &Connection,
+    session_id: &str,
+    limit: usize,
+) -> Result<Vec<IngredientFrequency>, String> {
+    let mut counts: HashMap<String, IngredientFrequency> = HashMap::new();
+    for row in load_rows(conn, session_id)? {
+        for name in &row.ingredients {
+            counts
+                .entry(canonical_ingredient(name))
+                .and_modify(|entry| entry.count += 1)
+                .or_insert_with(|| IngredientFrequency {
+                    name: name.clone(),
+                    count: 1,
+                    last_searched: row.search_time.clone(),
+                });
+        }
+    }
+    let mut ranked: Vec<IngredientFrequency> = counts.into_values().collect();
+    ranked.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| b.last_searched.cmp(&a.last_searched))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    ranked.truncate(limit);
+    Ok(ranked)
+}
+
+/// Order-insensitive identity of a search, used to collapse repeats.
+fn search_key(row: &SearchRow) -> String {
+    let sorted = |terms: &[String], canonical: bool| {
+        let mut terms: Vec<String> = terms
+            .iter()
+            .map(|term| {
+                if canonical {
+                    canonical_ingredient(term)
+                } else {
+                    term.to_lowercase()
+                }
+            })
+            .collect();
+        terms.sort();
+        terms.dedup();
+        terms.join(",")
+    };
+    format!(
+        "{}|{}|{}",
+        sorted(&row.ingredients, true),
+        sorted(&row.cooking_methods, false),
+        sorted(&row.dietary_restrictions, false)
+    )
+}
+
+fn distinct_searches(rows: Vec<SearchRow>, limit: usize) -> Vec<RecentSearch> {
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut searches: Vec<RecentSearch> = Vec::new();
+    for row in rows {
+        let key = search_key(&row);
+        if let Some(&position) = positions.get(&key) {
+            searches[position].occurrences += 1;
+            continue;
+        }
+        if searches.len() == limit {
+            continue;
+        }
+        positions.insert(key, searches.len());
+        searches.push(RecentSearch {
+            ingredients: row.ingredients,
+            cooking_methods: row.cooking_methods,
+            dietary_restrictions: row.dietary_restrictions,
+            result_count: row.result_count,
+            search_time: row.search_time,
+            occurrences: 1,
+        });
+    }
+    searches
+}
+
+pub(crate) fn recent_searches(
+    conn: &Connection,
+    session_id: &str,
+    limit: usize,
+) -> Result<Vec<RecentSearch>, String> {
+    Ok(distinct_searches(load_rows(conn, session_id)?, limit))
+}
+
+/// Searches whose most recent run found nothing; a later successful run of the same
+/// search clears it from this list.
+pub(crate) fn zero_result_searches(
+    conn: &Connection,
+    session_id: &str,
+    limit: usize,
+) -> Result<Vec<RecentSearch>, String> {
+    Ok(distinct_searches(load_rows(conn, session_id)?, usize::MAX)
+        .into_iter()
+        .filter(|search| search.result_count == 0)
+        .take(limit)
+        .collect())
+}
+
+/// Ranks previously searched ingredients matching `prefix` by a recency-decayed
+/// frequency, then fills up with pantry items the user has not searched for yet.
+pub(crate) fn suggest_ingredients(
+    conn: &Connection,
+    session_id: &str,
+    prefix: &str,
+    limit: usize,
+) -> Result<Vec<IngredientSuggestion>, String> {
+    let prefix = prefix.trim().to_lowercase();
+    let matches = |name: &str| {
+        let lowered = name.to_lowercase();
+        prefix.is_empty()
+            || lowered.starts_with(&prefix)
+            || canonical_ingredient(name).starts_with(&prefix)
+    };
+
+    let mut scored: HashMap<String, IngredientSuggestion> = HashMap::new();
+    for row in load_rows(conn, session_id)? {
+        let weight = 0.5_f64.powf(row.age_days / SUGGESTION_HALF_LIFE_DAYS);
+        for name in row.ingredients.iter().filter(|name| matches(name)) {
+            let entry =
+                scored
+                    .entry(canonical_ingredient(name))
+                    .or_insert_with(|| IngredientSuggestion {
+                        name: name.clone(),
+                        score: 0.0,
+                        count: 0,
+                    });
+            entry.score += weight;
+            entry.count += 1;
+        }
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT name FROM ingredients_inventory WHERE session_id = ?1")
+        .map_err(|e| format!("Failed to load pantry items: {}", e))?;
+    let pantry = stmt
+        .query_map([session_id], |row| row.get::<_, String>(0))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load pantry items: {}", e))?;
+    for name in pantry.iter().filter(|name| matches(name)) {
+        scored
+            .entry(canonical_ingredient(name))
+            .or_insert_with(|| IngredientSuggestion {
+                name: name.trim().to_string(),
+                score: 0.0,
+                count: 0,
+            });
+    }
+
+    let mut ranked: Vec<IngredientSuggestion> = scored.into_values().collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.name.chars().count().cmp(&b.name.chars().count()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    ranked.truncate(limit);
+    Ok(ranked)
+}
+
+/// Retention in days; 0 keeps history forever.
+pub(crate) fn retention_days(conn: &Connection) -> Result<u32, String> {
+    let stored: Option<Option<String>> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [RETENTION_SETTING_KEY],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read search history retention: {}", e))?;
+    Ok(stored
+        .flatten()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .map_or(DEFAULT_RETENTION_DAYS, |days| days.min(MAX_RETENTION_DAYS)))
+}
+
+pub(crate) fn set_retention_days(conn: &Connection, days: u32) -> Result<usize, String> {
+    if days > MAX_RETENTION_DAYS {
+        return Err("Retention must be at most 3650 days".to_string());
+    }
+    conn.execute(
+        "INSERT INTO settings (key, value, category) VALUES (?1, ?2, 'search')
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+        params![RETENTION_SETTING_KEY, days.to_string()],
+    )
+    .map_err(|e| format!("Failed to save search history retention: {}", e))?;
+    prune_history(conn)
+}
+
+/// Deletes history older than the retention setting and returns how many rows went.
+pub(crate) fn prune_history(conn: &Connection) -> Result<usize, String> {
+    let days = retention_days(conn)?;
+    if days == 0 {
+        return Ok(0);
+    }
+    conn.execute(
+        "DELETE FROM search_history WHERE search_time < datetime('now', ?1)",
+        [format!("-{days} days")],
+    )
+    .map_err(|e| format!("Failed to prune search history: {}", e))
+}
+
+fn result_limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(10).clamp(1, MAX_RESULTS)
+}
+
+#[tauri::command]
+pub(crate) fn search_history_record(
+    search: SearchRecordInput,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<i64, String> {
+    let session_id = profiles.session_id()?;
+    let conn = db.open_connection()?;
+    record_search(&conn, &session_id, &search)
+}
+
+#[tauri::command]
+pub(crate) fn search_history_frequent_ingredients(
+    limit: Option<usize>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<IngredientFrequency>, String> {
+    let session_id = profiles.session_id()?;
+    let conn = db.open_connection()?;
+    frequent_ingredients(&conn, &session_id, result_limit(limit))
+}
+
+#[tauri::command]
+pub(crate) fn search_history_recent(
+    limit: Option<usize>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<RecentSearch>, String> {
+    let session_id = profiles.session_id()?;
+    let conn = db.open_connection()?;
+    recent_searches(&conn, &session_id, result_limit(limit))
+}
+
+#[tauri::command]
+pub(crate) fn search_history_zero_results(
+    limit: Option<usize>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<RecentSearch>, String> {
+    let session_id = profiles.session_id()?;
+    let conn = db.open_connection()?;
+    zero_result_searches(&conn, &session_id, result_limit(limit))
+}
+
+#[tauri::command]
+pub(crate) fn ingredient_suggest(
+    prefix: String,
+    limit: Option<usize>,
+    profiles: State<ProfileState>,
+    db: State<DatabaseState>,
+) -> Result<Vec<IngredientSuggestion>, String> {
+    let session_id = profiles.session_id()?;
+    if prefix.chars().count() > 100 {
+        return Err("Prefix is too long".to_string());
+    }
+    let conn = db.open_connection()?;
+    suggest_ingredients(&conn, &session_id, &prefix, result_limit(limit))
+}
+
+#[tauri::command]
+pub(crate) fn search_history_retention_get(db: State<DatabaseState>) -> Result<u32, String> {
+    let conn = db.open_connection()?;
+    retention_days(&conn)
+}
+
+/// Stores the retention period and prunes immediately; returns the rows removed.
+#[tauri::command]
+pub(crate) fn search_history_retention_set(
+    days: u32,
+    db: State<DatabaseState>,
+) -> Result<usize, String> {
+    let conn = db.open_connection()?;
+    set_retention_days(&conn, days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_schema;
+
+    fn search(ingredients: &[&str], result_count: i64) -> SearchRecordInput {
+        SearchRecordInput {
+            ingredients: ingredients.iter().map(|name| name.to_string()).collect(),
+            cooking_methods: Vec::new(),
+            dietary_restrictions: Vec::new(),
+            result_count,
+        }
+    }
+
+    fn backdate(conn: &Connection, search_id: i64, days: i64) {
+        conn.execute(
+            "UPDATE search_history SET search_time = datetime('now', ?1) WHERE id = ?2",
+            params![format!("-{days} days"), search_id],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn summarizes_frequent_recent_and_failed_searches() {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        let first = record_search(&conn, "s1", &search(&["西红柿", "鸡蛋"], 4)).unwrap();
+        backdate(&conn, first, 3);
+        let failed = record_search(&conn, "s1", &search(&["榴莲", "牛肉"], 0)).unwrap();
+        backdate(&conn, failed, 2);
+        let retried = record_search(&conn, "s1", &search(&["牛肉", "榴莲"], 0)).unwrap();
+        backdate(&conn, retried, 1);
+        record_search(&conn, "s1", &search(&["鸡蛋", "番茄"], 6)).unwrap();
+        conn.execute(
+            "INSERT INTO search_history (session_id, ingredients, result_count) VALUES ('s1', '番茄, 豆腐', 0)",
+            [],
+        )
+        .unwrap();
+        record_search(&conn, "s2", &search(&["鸡蛋"], 0)).unwrap();
+
+        let frequent = frequent_ingredients(&conn, "s1", 3).unwrap();
+        let summary: Vec<_> = frequent
+            .iter()
+            .map(|item| (item.name.as_str(), item.count))
+            .collect();
+        assert_eq!(summary, [("番茄", 3), ("鸡蛋", 2), ("榴莲", 2)]);
+
+        let recent = recent_searches(&conn, "s1", 10).unwrap();
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[0].ingredients, ["番茄", "豆腐"]);
+        assert_eq!(recent[1].ingredients, ["鸡蛋", "番茄"]);
+        assert_eq!(recent[1].occurrences, 2);
+        assert_eq!(recent[2].occurrences, 2);
+
+        let zero = zero_result_searches(&conn, "s1", 10).unwrap();
+        let zero: Vec<_> = zero.iter().map(|item| item.ingredients.clone()).collect();
+        assert_eq!(zero, [vec!["番茄", "豆腐"], vec!["牛肉", "榴莲"]]);
+        assert!(record_search(&conn, "s1", &search(&[" "], 1)).is_err());
+    }
+
+    #[test]
+    fn ranks_suggestions_and_prunes_old_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        for days in [200, 190, 180] {
+            let id = record_search(&conn, "s1", &search(&["牛肉"], 1)).unwrap();
+            backdate(&conn, id, days);
+        }
+        record_search(&conn, "s1", &search(&["牛奶"], 1)).unwrap();
+        conn.execute(
+            "INSERT INTO ingredients_inventory (session_id, name) VALUES ('s1', '牛油果')",
+            [],
+        )
+        .unwrap();
+
+        let suggestions = suggest_ingredients(&conn, "s1", "牛", 5).unwrap();
+        let names: Vec<_> = suggestions.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["牛奶", "牛肉", "牛油果"]);
+        assert_eq!(suggestions[1].count, 3);
+
+        assert_eq!(retention_days(&conn).unwrap(), DEFAULT_RETENTION_DAYS);
+        assert_eq!(set_retention_days(&conn, 185).unwrap(), 2);
+        assert_eq!(retention_days(&conn).unwrap(), 185);
+        assert_eq!(set_retention_days(&conn, 0).unwrap(), 0);
+        assert!(set_retention_days(&conn, 5_000).is_err());
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM search_history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 2);
+    }
+}
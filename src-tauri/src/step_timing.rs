@@ -0,0 +1,634 @@
+use crate::cooking::{
+    add_timer, CookingSession, CookingSessionSnapshot, CookingState, Now, TimerStatus,
+    MAX_TIMER_SECONDS,
+};
+use crate::recipe::{require_recipe, StoredRecipe};
+use crate::DatabaseState;
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::State;
+
+// Longest spellings first so "中小火" is not read as "小火".
+const HEAT_KEYWORDS: &[(&str, HeatLevel)] = &[
+    ("中大火", HeatLevel::MediumHigh),
+    ("中小火", HeatLevel::MediumLow),
+    ("大火", HeatLevel::High),
+    ("猛火", HeatLevel::High),
+    ("旺火", HeatLevel::High),
+    ("武火", HeatLevel::High),
+    ("中火", HeatLevel::Medium),
+    ("小火", HeatLevel::Low),
+    ("微火", HeatLevel::Low),
+    ("文火", HeatLevel::Low),
+    ("medium-high heat", HeatLevel::MediumHigh),
+    ("medium high heat", HeatLevel::MediumHigh),
+    ("medium-low heat", HeatLevel::MediumLow),
+    ("medium low heat", HeatLevel::MediumLow),
+    ("high heat", HeatLevel::High),
+    ("medium heat", HeatLevel::Medium),
+    ("low heat", HeatLevel::Low),
+    ("simmer", HeatLevel::Low),
+];
+
+// Unit spellings with their length in seconds, longest first. English units must
+// end at a word boundary; "分" alone is handled separately because "五分熟" is a
+// doneness, not a duration.
+const DURATION_UNITS: &[(&str, u64)] = &[
+    ("小时", 3_600),
+    ("钟头", 3_600),
+    ("分钟", 60),
+    ("秒钟", 1),
+    ("秒", 1),
+    ("天", 86_400),
+    ("hours", 3_600),
+    ("hour", 3_600),
+    ("hrs", 3_600),
+    ("hr", 3_600),
+    ("h", 3_600),
+    ("minutes", 60),
+    ("minute", 60),
+    ("mins", 60),
+    ("min", 60),
+    ("seconds", 1),
+    ("second", 1),
+    ("secs", 1),
+    ("sec", 1),
+];
+
+const RANGE_SEPARATORS: &[&str] = &["-", "~", "～", "–", "—", "到", "至", "to "];
+/// What may sit between the parts of a compound duration such as "1小时零5分钟".
+const COMPOUND_JOINERS: &[&str] = &["", "零", "又", "and"];
+const OVERNIGHT: &[&str] = &["过夜", "隔夜", "overnight"];
+/// Stored cooking times within this share of the parsed estimate count as consistent.
+const COOKING_TIME_TOLERANCE: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatLevel {
+    High,
+    MediumHigh,
+    Medium,
+    MediumLow,
+    Low,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepDuration {
+    pub text: String,
+    pub min_seconds: u64,
+    pub max_seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepTemperature {
+    pub text: String,
+    pub value: f64,
+    pub unit: TemperatureUnit,
+    pub celsius: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepTiming {
+    pub step_index: usize,
+    pub text: String,
+    pub durations: Vec<StepDuration>,
+    pub temperatures: Vec<StepTemperature>,
+    pub heat_levels: Vec<HeatLevel>,
+    pub min_seconds: u64,
+    pub max_seconds: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CookingTimeCheck {
+    Consistent,
+    /// The stored `cooking_time` is shorter than the steps add up to.
+    Understated,
+    /// The stored `cooking_time` is longer than the steps add up to.
+    Overstated,
+    /// Either no step mentions a duration or no `cooking_time` is stored.
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeTiming {
+    pub recipe_id: i64,
+    pub steps: Vec<StepTiming>,
+    pub total_min_minutes: u64,
+    pub total_max_minutes: u64,
+    pub stored_cooking_time: Option<i64>,
+    pub cooking_time_check: CookingTimeCheck,
+}
+
+fn chinese_digit(c: char) -> Option<u64> {
+    "零一二三四五六七八九"
+        .chars()
+        .position(|digit| digit == c)
+        .map(|value| value as u64)
+        .or((c == '两').then_some(2))
+}
+
+/// Reads "二十五", "十", "一百" and the like; "半" on its own is one half.
+fn chinese_value(numerals: &[char]) -> Option<f64> {
+    if numerals == ['半'] {
+        return Some(0.5);
+    }
+    let (mut total, mut current) = (0, 0);
+    for &c in numerals {
+        match c {
+            '十' => {
+                total += current.max(1) * 10;
+                current = 0;
+            }
+            '百' => {
+                total += current.max(1) * 100;
+                current = 0;
+            }
+            _ => current = chinese_digit(c)?,
+        }
+    }
+    let value = total + current;
+    (value > 0).then_some(value as f64)
+}
+
+/// Parses a number at `start`: Arabic digits with an optional decimal part, or a
+/// run of Chinese numerals. Adjacent Chinese digits such as "两三" read as a range.
+fn parse_number(chars: &[char], start: usize) -> Option<(f64, Option<f64>, usize)> {
+    let first = *chars.get(start)?;
+    if first.is_ascii_digit() {
+        let mut end = start;
+        while end < chars.len()
+            && (chars[end].is_ascii_digit()
+                || (chars[end] == '.' && chars.get(end + 1).is_some_and(char::is_ascii_digit)))
+        {
+            end += 1;
+        }
+        let value = chars[start..end].iter().collect::<String>().parse().ok()?;
+        return Some((value, None, end));
+    }
+    let is_numeral = |c: char| chinese_digit(c).is_some() || c == '十' || c == '百';
+    if first == '半' {
+        return Some((0.5, None, start + 1));
+    }
+    if !is_numeral(first) {
+        return None;
+    }
+    let mut end = start;
+    while end < chars.len() && is_numeral(chars[end]) {
+        end += 1;
+    }
+    let run = &chars[start..end];
+    if let [low, high] = run {
+        if let (Some(low), Some(high)) = (chinese_digit(*low), chinese_digit(*high)) {
+            if low > 0 && high == low + 1 {
+                return Some((low as f64, Some(high as f64), end));
+            }
+        }
+    }
+    Some((chinese_value(run)?, None, end))
+}
+
+fn skip_spaces(chars: &[char], mut index: usize) -> usize {
+    while index < chars.len() && chars[index].is_whitespace() {
+        index += 1;
+    }
+    index
+}
+
+fn lowercase_at(chars: &[char], index: usize, pattern: &str) -> bool {
+    let mut position = index;
+    for expected in pattern.chars() {
+        match chars.get(position) {
+            Some(c) if c.to_lowercase().eq(expected.to_lowercase()) => position += 1,
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn word_ends_at(chars: &[char], index: usize) -> bool {
+    chars.get(index).is_none_or(|c| !c.is_ascii_alphanumeric())
+}
+
+fn duration_unit(chars: &[char], index: usize) -> Option<(u64, usize)> {
+    for (unit, seconds) in DURATION_UNITS {
+        let length = unit.chars().count();
+        if lowercase_at(chars, index, unit)
+            && (!unit.is_ascii() || word_ends_at(chars, index + length))
+        {
+            return Some((*seconds, index + length));
+        }
+    }
+    // "分" counts only before a number, punctuation or the end of the text.
+    if chars.get(index) == Some(&'分') {
+        let next = chars.get(index + 1);
+        if next.is_none_or(|c| {
+            c.is_ascii_digit()
+                || c.is_ascii_punctuation()
+                || c.is_whitespace()
+                || "，。、；）".contains(*c)
+        }) || next.and_then(|c| chinese_digit(*c)).is_some()
+        {
+            return Some((60, index + 1));
+        }
+    }
+    None
+}
+
+fn temperature_unit(chars: &[char], index: usize, value: f64) -> Option<(TemperatureUnit, usize)> {
+    let patterns: &[(&str, Option<TemperatureUnit>)] = &[
+        ("°c", Some(TemperatureUnit::Celsius)),
+        ("℃", Some(TemperatureUnit::Celsius)),
+        ("°f", Some(TemperatureUnit::Fahrenheit)),
+        ("℉", Some(TemperatureUnit::Fahrenheit)),
+        ("degrees fahrenheit", Some(TemperatureUnit::Fahrenheit)),
+        ("degrees celsius", Some(TemperatureUnit::Celsius)),
+        ("degrees f", Some(TemperatureUnit::Fahrenheit)),
+        ("degrees c", Some(TemperatureUnit::Celsius)),
+        ("degrees", None),
+        ("°", None),
+        ("度", Some(TemperatureUnit::Celsius)),
+    ];
+    patterns.iter().find_map(|(pattern, unit)| {
+        let length = pattern.chars().count();
+        let end = index + length;
+        let matches = lowercase_at(chars, index, pattern)
+            && (!pattern.starts_with('d') || word_ends_at(chars, end));
+        // A bare "350 degrees" in an English recipe is almost always Fahrenheit.
+        let unit = unit.unwrap_or(if value >= 300.0 {
+            TemperatureUnit::Fahrenheit
+        } else {
+            TemperatureUnit::Celsius
+        });
+        matches.then_some((unit, end))
+    })
+}
+
+fn range_separator(chars: &[char], index: usize) -> Option<usize> {
+    RANGE_SEPARATORS.iter().find_map(|separator| {
+        lowercase_at(chars, index, separator).then(|| index + separator.chars().count())
+    })
+}
+
+fn heat_levels(text: &str) -> Vec<HeatLevel> {
+    let lowered = text.to_lowercase();
+    let mut levels = Vec::new();
+    let mut rest = lowered.as_str();
+    while !rest.is_empty() {
+        match HEAT_KEYWORDS
+            .iter()
+            .find(|(keyword, _)| rest.starts_with(keyword))
+        {
+            Some((keyword, level)) => {
+                if !levels.contains(level) {
+                    levels.push(*level);
+                }
+                rest = &rest[keyword.len()..];
+            }
+            None => {
+                let skip = rest.chars().next().map_or(1, char::len_utf8);
+                rest = &rest[skip..];
+            }
+        }
+    }
+    levels
+}
+
+/// Extracts every duration, temperature and heat level mentioned in one step.
+pub(crate) fn parse_step(step_index: usize, text: &str) -> StepTiming {
+    let chars: Vec<char> = text.chars().collect();
+    let mut durations = Vec::new();
+    let mut temperatures = Vec::new();
+    // Start, end and unit of the last duration, so "1小时30分钟" can join into one.
+    let mut previous: Option<(usize, usize, u64)> = None;
+    let mut index = 0;
+    while index < chars.len() {
+        if let Some(keyword) = OVERNIGHT
+            .iter()
+            .find(|keyword| lowercase_at(&chars, index, keyword))
+        {
+            durations.push(StepDuration {
+                text: keyword.to_string(),
+                min_seconds: 8 * 3_600,
+                max_seconds: 12 * 3_600,
+            });
+            index += keyword.chars().count();
+            previous = None;
+            continue;
+        }
+        let Some((value, mut high, mut end)) = parse_number(&chars, index) else {
+            index += 1;
+            continue;
+        };
+        if high.is_none() {
+            let after = skip_spaces(&chars, end);
+            if let Some(next) = range_separator(&chars, after) {
+                if let Some((upper, None, upper_end)) =
+                    parse_number(&chars, skip_spaces(&chars, next))
+                {
+                    if upper > value {
+                        high = Some(upper);
+                        end = upper_end;
+                    }
+                }
+            }
+        }
+        let mut unit_start = skip_spaces(&chars, end);
+        // "一个半小时" is one and a half hours.
+        let mut extra = 0.0;
+        if chars.get(unit_start) == Some(&'个') {
+            unit_start += 1;
+            if chars.get(unit_start) == Some(&'半') {
+                extra = 0.5;
+                unit_start += 1;
+            }
+        }
+
+        if let Some((seconds, unit_end)) = duration_unit(&chars, unit_start) {
+            let to_seconds = |amount: f64| ((amount + extra) * seconds as f64).round() as u64;
+            let (min_seconds, max_seconds) = (to_seconds(value), to_seconds(high.unwrap_or(value)));
+            let joined = previous.filter(|(_, previous_end, previous_unit)| {
+                let gap: String = chars[*previous_end..index].iter().collect();
+                *previous_unit > seconds
+                    && COMPOUND_JOINERS.contains(&gap.trim().to_lowercase().as_str())
+            });
+            match (joined, durations.last_mut()) {
+                (Some((start, _, _)), Some(last)) => {
+                    last.text = chars[start..unit_end].iter().collect();
+                    last.min_seconds += min_seconds;
+                    last.max_seconds += max_seconds;
+                    previous = Some((start, unit_end, seconds));
+                }
+                _ => {
+                    durations.push(StepDuration {
+                        text: chars[index..unit_end].iter().collect(),
+                        min_seconds,
+                        max_seconds,
+                    });
+                    previous = Some((index, unit_end, seconds));
+                }
+            }
+            index = unit_end;
+        } else if let Some((unit, unit_end)) =
+            temperature_unit(&chars, unit_start, value).filter(|_| extra == 0.0 && high.is_none())
+        {
+            let celsius = match unit {
+                TemperatureUnit::Celsius => value,
+                TemperatureUnit::Fahrenheit => ((value - 32.0) * 5.0 / 9.0).round(),
+            };
+            temperatures.push(StepTemperature {
+                text: chars[index..unit_end].iter().collect(),
+                value,
+                unit,
+                celsius,
+            });
+            index = unit_end;
+        } else {
+            index = end.max(index + 1);
+        }
+    }
+
+    StepTiming {
+        step_index,
+        text: text.to_string(),
+        min_seconds: durations.iter().map(|duration| duration.min_seconds).sum(),
+        max_seconds: durations.iter().map(|duration| duration.max_seconds).sum(),
+        durations,
+        temperatures,
+        heat_levels: heat_levels(text),
+    }
+}
+
+fn check_cooking_time(stored: Option<i64>, min_minutes: u64, max_minutes: u64) -> CookingTimeCheck {
+    let Some(stored) = stored.filter(|minutes| *minutes > 0) else {
+        return CookingTimeCheck::Unknown;
+    };
+    if max_minutes == 0 {
+        return CookingTimeCheck::Unknown;
+    }
+    let stored = stored as f64;
+    if stored < min_minutes as f64 * (1.0 - COOKING_TIME_TOLERANCE) {
+        CookingTimeCheck::Understated
+    } else if stored > max_minutes as f64 * (1.0 + COOKING_TIME_TOLERANCE) {
+        CookingTimeCheck::Overstated
+    } else {
+        CookingTimeCheck::Consistent
+    }
+}
+
+pub(crate) fn recipe_timing(recipe: &StoredRecipe) -> RecipeTiming {
+    let steps: Vec<StepTiming> = recipe
+        .step_list()
+        .iter()
+        .enumerate()
+        .map(|(index, text)| parse_step(index, text))
+        .collect();
+    let minutes = |seconds: u64| seconds.div_ceil(60);
+    let total_min_minutes = minutes(steps.iter().map(|step| step.min_seconds).sum());
+    let total_max_minutes = minutes(steps.iter().map(|step| step.max_seconds).sum());
+    RecipeTiming {
+        recipe_id: recipe.id,
+        cooking_time_check: check_cooking_time(
+            recipe.cooking_time,
+            total_min_minutes,
+            total_max_minutes,
+        ),
+        steps,
+        total_min_minutes,
+        total_max_minutes,
+        stored_cooking_time: recipe.cooking_time,
+    }
+}
+
+/// Starts one timer per duration mentioned in the step, using the short end of a
+/// range so the cook checks doneness early. Durations longer than a timer allows,
+/// such as "腌制2天", are left to the cook. Steps that already have a live timer
+/// are left alone, so navigating back and forth does not stack duplicates.
+pub(crate) fn seed_step_timers(
+    conn: &Connection,
+    session: &mut CookingSession,
+    step_index: usize,
+    now: Now,
+) -> Result<Vec<i64>, String> {
+    let text = session
+        .steps
+        .get(step_index)
+        .ok_or_else(|| "Step index is out of range".to_string())?
+        .clone();
+    let seeded = session.timers.iter().any(|timer| {
+        timer.step_index == Some(step_index) && timer.status != TimerStatus::Dismissed
+    });
+    if seeded {
+        return Ok(Vec::new());
+    }
+    parse_step(step_index, &text)
+        .durations
+        .iter()
+        .filter(|duration| (1..=MAX_TIMER_SECONDS).contains(&duration.min_seconds))
+        .map(|duration| {
+            let label = format!("第{}步 · {}", step_index + 1, duration.text);
+            add_timer(
+                conn,
+                session,
+                &label,
+                duration.min_seconds,
+                Some(step_index),
+                now,
+            )
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub(crate) fn recipe_step_timings(
+    recipe_id: i64,
+    db: State<DatabaseState>,
+) -> Result<RecipeTiming, String> {
+    let conn = db.open_connection()?;
+    Ok(recipe_timing(&require_recipe(&conn, recipe_id)?))
+}
+
+#[tauri::command]
+pub(crate) fn cooking_seed_step_timers(
+    step_index: Option<usize>,
+    cooking: State<CookingState>,
+    db: State<DatabaseState>,
+) -> Result<CookingSessionSnapshot, String> {
+    crate::cooking::update_session(&cooking, &db, |conn, session, now| {
+        let step_index = step_index.unwrap_or(session.step_index);
+        seed_step_timers(conn, session, step_index, now).map(|_| ())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(step: &StepTiming) -> Vec<(u64, u64)> {
+        step.durations
+            .iter()
+            .map(|duration| (duration.min_seconds, duration.max_seconds))
+            .collect()
+    }
+
+    #[test]
+    fn parses_chinese_and_english_steps() {
+        let step = parse_step(0, "小火焖煮20分钟，转中大火收汁2-3分钟");
+        assert_eq!(spans(&step), [(1_200, 1_200), (120, 180)]);
+        assert_eq!(step.durations[1].text, "2-3分钟");
+        assert_eq!(step.heat_levels, [HeatLevel::Low, HeatLevel::MediumHigh]);
+        assert_eq!((step.min_seconds, step.max_seconds), (1_320, 1_380));
+
+        assert_eq!(spans(&parse_step(0, "腌制 1-2 小时")), [(3_600, 7_200)]);
+        assert_eq!(spans(&parse_step(0, "蒸一个半小时")), [(5_400, 5_400)]);
+        assert_eq!(
+            spans(&parse_step(0, "再焖两三分钟，煮至五分熟")),
+            [(120, 180)]
+        );
+        let stew = parse_step(0, "炖1小时30分钟，焖5分钟");
+        assert_eq!(spans(&stew), [(5_400, 5_400), (300, 300)]);
+        assert_eq!(stew.durations[0].text, "1小时30分钟");
+        assert_eq!(spans(&parse_step(0, "炖1小时零5分钟")), [(3_900, 3_900)]);
+        assert_eq!(
+            spans(&parse_step(
+                0,
+                "Simmer 1 hour 30 minutes, or 1 hr and 15 mins"
+            )),
+            [(5_400, 5_400), (4_500, 4_500)]
+        );
+        assert_eq!(spans(&parse_step(0, "冷藏过夜")), [(28_800, 43_200)]);
+        assert!(parse_step(0, "十分美味，加一勺糖").durations.is_empty());
+
+        let oven = parse_step(1, "烤箱预热至200℃，烤15分钟");
+        assert_eq!(spans(&oven), [(900, 900)]);
+        assert_eq!(oven.temperatures[0].celsius, 200.0);
+
+        let english = parse_step(
+            2,
+            "Bake at 350 degrees for 20 to 25 minutes, then rest 30 secs.",
+        );
+        assert_eq!(spans(&english), [(1_200, 1_500), (30, 30)]);
+        assert_eq!(english.temperatures[0].unit, TemperatureUnit::Fahrenheit);
+        assert_eq!(english.temperatures[0].celsius, 177.0);
+        let simmer = parse_step(3, "Bring to a boil over high heat, then simmer for 1 hr.");
+        assert_eq!(simmer.heat_levels, [HeatLevel::High, HeatLevel::Low]);
+        assert_eq!(spans(&simmer), [(3_600, 3_600)]);
+        assert!(parse_step(4, "Add 2 heads of garlic").durations.is_empty());
+    }
+
+    #[test]
+    fn totals_steps_and_checks_cooking_time() {
+        let recipe = StoredRecipe {
+            id: 9,
+            title: "红烧肉".to_string(),
+            description: None,
+            ingredients: "[]".to_string(),
+            instructions: "[\"焯水5分钟\", \"炒糖色\", {\"description\": \"小火焖煮40-50分钟\"}]"
+                .to_string(),
+            cooking_time: Some(20),
+            difficulty: None,
+            servings: None,
+            category: None,
+            tags: None,
+            nutrition_info: None,
+            image_url: None,
+            cooking_methods: None,
+            ai_provider: None,
+            ai_model: None,
+        };
+        let timing = recipe_timing(&recipe);
+        assert_eq!(timing.steps.len(), 3);
+        assert_eq!(
+            (timing.total_min_minutes, timing.total_max_minutes),
+            (45, 55)
+        );
+        assert_eq!(timing.cooking_time_check, CookingTimeCheck::Understated);
+
+        assert_eq!(
+            check_cooking_time(Some(50), 45, 55),
+            CookingTimeCheck::Consistent
+        );
+        assert_eq!(
+            check_cooking_time(Some(120), 45, 55),
+            CookingTimeCheck::Overstated
+        );
+        assert_eq!(check_cooking_time(None, 45, 55), CookingTimeCheck::Unknown);
+        assert_eq!(
+            check_cooking_time(Some(30), 0, 0),
+            CookingTimeCheck::Unknown
+        );
+    }
+
+    #[test]
+    fn seeds_one_timer_per_span_and_skips_overlong_ones() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::initialize_schema(&conn).unwrap();
+        let recipe_id = crate::recipe::insert_recipe(
+            &conn,
+            &crate::recipe::RecipeDraft {
+                title: "卤牛肉".to_string(),
+                ingredients: vec!["牛腱 1斤".to_string()],
+                instructions: vec!["腌制2天，再炖1小时30分钟".to_string()],
+                ..crate::recipe::RecipeDraft::default()
+            },
+        )
+        .unwrap();
+        let recipe = require_recipe(&conn, recipe_id).unwrap();
+        let mut session = crate::cooking::start_session(&conn, &recipe).unwrap();
+
+        let seeded = seed_step_timers(&conn, &mut session, 0, Now::current()).unwrap();
+        assert_eq!(seeded.len(), 1);
+        assert_eq!(session.timers[0].label, "第1步 · 1小时30分钟");
+        let stored: i64 = conn
+            .query_row("SELECT COUNT(*) FROM cooking_timers", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, 1);
+    }
+}
@@ -0,0 +1,349 @@
+use crate::cooking::{
+    update_session, CookingSessionSnapshot, CookingState, CookingTimerSnapshot, TimerFinished,
+    TimerStatus,
+};
+use crate::DatabaseState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::menu::{IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_notification::NotificationExt;
+
+const TRAY_ID: &str = "chefmind-tray";
+pub(crate) const SNOOZE_MINUTES: u64 = 5;
+pub(crate) const STEP_FOCUS_EVENT: &str = "cooking-step-focus";
+
+type TimerSubmenus = HashMap<i64, Submenu<Wry>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TrayAction {
+    Snooze(i64),
+    Dismiss(i64),
+    JumpToStep(i64),
+    ShowWindow,
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepFocus {
+    pub session_id: i64,
+    pub recipe_id: i64,
+    pub step_index: usize,
+}
+
+/// Timer submenus currently in the tray. While the set of timers and their states
+/// stay the same only the labels are rewritten, so an open menu is not rebuilt
+/// under the cursor every second.
+#[derive(Default)]
+pub struct TrayState {
+    layout: Mutex<Vec<(i64, TimerStatus)>>,
+    submenus: Mutex<TimerSubmenus>,
+}
+
+pub(crate) fn format_remaining(remaining_ms: u64) -> String {
+    let seconds = remaining_ms.div_ceil(1_000);
+    let (hours, minutes, seconds) = (seconds / 3_600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+pub(crate) fn timer_label(timer: &CookingTimerSnapshot) -> String {
+    let state = match timer.status {
+        TimerStatus::Running => format_remaining(timer.remaining_ms),
+        TimerStatus::Paused | TimerStatus::Held => {
+            format!("已暂停 {}", format_remaining(timer.remaining_ms))
+        }
+        TimerStatus::Finished => "时间到".to_string(),
+        TimerStatus::Dismissed => "已关闭".to_string(),
+    };
+    format!("{} · {}", timer.label, state)
+}
+
+pub(crate) fn parse_menu_id(id: &str) -> Option<TrayAction> {
+    match id {
+        "show" => return Some(TrayAction::ShowWindow),
+        "quit" => return Some(TrayAction::Quit),
+        _ => {}
+    }
+    let (action, timer_id) = id.strip_prefix("timer:")?.split_once(':')?;
+    let timer_id = timer_id.parse().ok()?;
+    match action {
+        "snooze" => Some(TrayAction::Snooze(timer_id)),
+        "dismiss" => Some(TrayAction::Dismiss(timer_id)),
+        "step" => Some(TrayAction::JumpToStep(timer_id)),
+        _ => None,
+    }
+}
+
+fn timer_submenu(app: &AppHandle, timer: &CookingTimerSnapshot) -> tauri::Result<Submenu<Wry>> {
+    let id = timer.id;
+    let snooze = MenuItem::with_id(
+        app,
+        format!("timer:snooze:{id}"),
+        format!("{SNOOZE_MINUTES} 分钟后再提醒"),
+        timer.status == TimerStatus::Finished,
+        None::<&str>,
+    )?;
+    let dismiss = MenuItem::with_id(
+        app,
+        format!("timer:dismiss:{id}"),
+        "关闭计时器",
+        true,
+        None::<&str>,
+    )?;
+    let step_text = match timer.step_index {
+        Some(step_index) => format!("跳到第 {} 步", step_index + 1),
+        None => "打开烹饪指南".to_string(),
+    };
+    let step = MenuItem::with_id(
+        app,
+        format!("timer:step:{id}"),
+        step_text,
+        true,
+        None::<&str>,
+    )?;
+    Submenu::with_id_and_items(
+        app,
+        format!("timer:{id}"),
+        timer_label(timer),
+        true,
+        &[&snooze, &dismiss, &step],
+    )
+}
+
+fn build_menu(
+    app: &AppHandle,
+    timers: &[CookingTimerSnapshot],
+) -> tauri::Result<(Menu<Wry>, TimerSubmenus)> {
+    let mut submenus = HashMap::new();
+    for timer in timers {
+        submenus.insert(timer.id, timer_submenu(app, timer)?);
+    }
+    let empty = MenuItem::with_id(app, "no-timers", "没有进行中的计时器", false, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let show = MenuItem::with_id(app, "show", "显示 ChefMind", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+
+    let mut items: Vec<&dyn IsMenuItem<Wry>> = timers
+        .iter()
+        .filter_map(|timer| submenus.get(&timer.id))
+        .map(|submenu| submenu as &dyn IsMenuItem<Wry>)
+        .collect();
+    if items.is_empty() {
+        items.push(&empty);
+    }
+    items.extend([&separator as &dyn IsMenuItem<Wry>, &show, &quit]);
+    let menu = Menu::with_items(app, &items)?;
+    Ok((menu, submenus))
+}
+
+fn tooltip(timers: &[CookingTimerSnapshot]) -> String {
+    timers
+        .iter()
+        .filter(|timer| timer.status == TimerStatus::Running)
+        .min_by_key(|timer| timer.remaining_ms)
+        .map_or_else(
+            || "ChefMind".to_string(),
+            |timer| format!("ChefMind · {}", timer_label(timer)),
+        )
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let Some(action) = parse_menu_id(event.id().as_ref()) else {
+        return;
+    };
+    let (cooking, db) = (app.state::<CookingState>(), app.state::<DatabaseState>());
+    let result = match action {
+        TrayAction::ShowWindow => {
+            show_main_window(app);
+            Ok(())
+        }
+        TrayAction::Quit => {
+            app.exit(0);
+            Ok(())
+        }
+        TrayAction::Snooze(timer_id) => update_session(&cooking, &db, |_, session, now| {
+            session.snooze_timer(
+                timer_id,
+                Duration::from_secs(SNOOZE_MINUTES * 60),
+                now.instant,
+            )
+        })
+        .map(|_| ()),
+        TrayAction::Dismiss(timer_id) => update_session(&cooking, &db, |_, session, now| {
+            session.dismiss_timer(timer_id, now.instant)
+        })
+        .map(|_| ()),
+        TrayAction::JumpToStep(timer_id) => {
+            let mut focus = None;
+            update_session(&cooking, &db, |_, session, _| {
+                let step_index = session.focus_timer_step(timer_id)?;
+                focus = Some(StepFocus {
+                    session_id: session.id,
+                    recipe_id: session.recipe_id,
+                    step_index,
+                });
+                Ok(())
+            })
+            .and_then(|_| {
+                show_main_window(app);
+                match focus {
+                    Some(focus) => app
+                        .emit(STEP_FOCUS_EVENT, focus)
+                        .map_err(|e| format!("Failed to emit step focus: {}", e)),
+                    None => Ok(()),
+                }
+            })
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Tray action failed: {}", e);
+    }
+}
+
+/// Creates the tray icon; timers are filled in by `refresh` from the cooking ticker.
+pub(crate) fn build(app: &AppHandle) -> tauri::Result<()> {
+    let (menu, submenus) = build_menu(app, &[])?;
+    *app.state::<TrayState>()
+        .submenus
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = submenus;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("ChefMind")
+        .menu(&menu)
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Brings the tray in line with the cooking session; called once per tick.
+pub(crate) fn refresh(app: &AppHandle, session: Option<&CookingSessionSnapshot>) {
+    let (Some(tray), Some(state)) = (app.tray_by_id(TRAY_ID), app.try_state::<TrayState>()) else {
+        return;
+    };
+    let timers = session.map_or(&[][..], |session| session.timers.as_slice());
+    let layout: Vec<(i64, TimerStatus)> = timers
+        .iter()
+        .map(|timer| (timer.id, timer.status))
+        .collect();
+    let mut current = state.layout.lock().unwrap_or_else(|e| e.into_inner());
+    let mut submenus = state.submenus.lock().unwrap_or_else(|e| e.into_inner());
+
+    let result = if *current == layout {
+        timers
+            .iter()
+            .try_for_each(|timer| match submenus.get(&timer.id) {
+                Some(submenu) if timer.status == TimerStatus::Running => {
+                    submenu.set_text(timer_label(timer))
+                }
+                _ => Ok(()),
+            })
+    } else {
+        build_menu(app, timers).and_then(|(menu, built)| {
+            tray.set_menu(Some(menu))?;
+            *submenus = built;
+            *current = layout;
+            Ok(())
+        })
+    }
+    .and_then(|_| tray.set_tooltip(Some(tooltip(timers))));
+    if let Err(e) = result {
+        eprintln!("Failed to update tray menu: {}", e);
+    }
+}
+
+pub(crate) fn notify_finished(app: &AppHandle, event: &TimerFinished) {
+    let body = match event.timer.step_index {
+        Some(step_index) => format!("{} · 第 {} 步", event.recipe_title, step_index + 1),
+        None => event.recipe_title.clone(),
+    };
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(format!("{} 时间到", event.timer.label))
+        .body(body)
+        .show()
+    {
+        eprintln!("Failed to show timer notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timer(status: TimerStatus, remaining_ms: u64) -> CookingTimerSnapshot {
+        CookingTimerSnapshot {
+            id: 7,
+            step_index: Some(2),
+            label: "焖煮".to_string(),
+            duration_ms: 2_400_000,
+            remaining_ms,
+            status,
+        }
+    }
+
+    #[test]
+    fn formats_timer_labels() {
+        assert_eq!(format_remaining(0), "00:00");
+        assert_eq!(format_remaining(59_001), "01:00");
+        assert_eq!(format_remaining(3_723_000), "1:02:03");
+        assert_eq!(
+            timer_label(&timer(TimerStatus::Running, 754_200)),
+            "焖煮 · 12:35"
+        );
+        assert_eq!(
+            timer_label(&timer(TimerStatus::Held, 60_000)),
+            "焖煮 · 已暂停 01:00"
+        );
+        assert_eq!(
+            timer_label(&timer(TimerStatus::Finished, 0)),
+            "焖煮 · 时间到"
+        );
+        assert_eq!(
+            tooltip(&[
+                timer(TimerStatus::Paused, 1_000),
+                timer(TimerStatus::Running, 90_000)
+            ]),
+            "ChefMind · 焖煮 · 01:30"
+        );
+    }
+
+    #[test]
+    fn parses_menu_ids() {
+        assert_eq!(
+            parse_menu_id("timer:snooze:12"),
+            Some(TrayAction::Snooze(12))
+        );
+        assert_eq!(
+            parse_menu_id("timer:dismiss:3"),
+            Some(TrayAction::Dismiss(3))
+        );
+        assert_eq!(
+            parse_menu_id("timer:step:4"),
+            Some(TrayAction::JumpToStep(4))
+        );
+        assert_eq!(parse_menu_id("show"), Some(TrayAction::ShowWindow));
+        assert_eq!(parse_menu_id("quit"), Some(TrayAction::Quit));
+        assert_eq!(parse_menu_id("timer:7"), None);
+        assert_eq!(parse_menu_id("timer:step:x"), None);
+        assert_eq!(parse_menu_id("no-timers"), None);
+    }
+}
@@ -0,0 +1,335 @@
+use crate::dietary::{check_draft, rules_for_session, DietaryViolation};
+use crate::profiles::ProfileState;
+use crate::provider_chain::{attempt_order, complete_with_fallback, load_chain};
+use crate::provider_params::CompletionOptions;
+use crate::recipe::{insert_recipe, require_recipe, RecipeDraft, StoredRecipe};
+use crate::{request_completion, validate_provider_id, DatabaseState};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+const VARIATION_MAX_TOKENS: u32 = 4_096;
+const VARIATION_TEMPERATURE: f64 = 0.7;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeVariation {
+    /// `None` when the variation broke the profile's dietary rules and was not saved.
+    pub recipe_id: Option<i64>,
+    pub parent_recipe_id: i64,
+    pub recipe: RecipeDraft,
+    pub violations: Vec<DietaryViolation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeFamilyMember {
+    pub id: i64,
+    pub title: String,
+    pub parent_recipe_id: Option<i64>,
+    pub variation_request: Option<String>,
+    pub depth: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeFamily {
+    pub root_id: i64,
+    pub members: Vec<RecipeFamilyMember>,
+}
+
+fn stored_list(raw: Option<&str>) -> serde_json::Value {
+    raw.and_then(|text| serde_json::from_str(text).ok())
+        .unwrap_or_else(|| serde_json::Value::Array(Vec::new()))
+}
+
+pub(crate) fn variation_prompt(recipe: &StoredRecipe, change: &str) -> String {
+    let original = serde_json::json!({
+        "title": recipe.title,
+        "description": recipe.description,
+        "ingredients": stored_list(Some(&recipe.ingredients)),
+        "instructions": stored_list(Some(&recipe.instructions)),
+        "cooking_time": recipe.cooking_time,
+        "difficulty": recipe.difficulty,
+        "servings": recipe.servings,
+        "category": recipe.category,
+        "tags": stored_list(recipe.tags.as_deref()),
+        "nutrition_info": recipe
+            .nutrition_info
+            .as_deref()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(text).ok()),
+        "cooking_methods": stored_list(recipe.cooking_methods.as_deref()),
+    });
+    format!(
+        "请根据以下要求改编这道菜谱：{change}\n\n\
+         原菜谱（JSON）：\n{original}\n\n\
+         请只返回改编后的完整菜谱 JSON，字段与原菜谱相同，不要包含任何解释文字。"
+    )
+}
+
+/// Extracts the recipe JSON from a completion, tolerating Markdown code fences or
+/// a sentence of commentary around the object.
+pub(crate) fn parse_variation(content: &str) -> Result<RecipeDraft, String> {
+    let start = content.find('{');
+    let end = content.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => return Err("AI provider did not return a recipe".to_string()),
+    };
+    let draft: RecipeDraft = serde_json::from_str(json)
+        .map_err(|_| "AI provider returned an unreadable recipe".to_string())?;
+    draft.validate()?;
+    Ok(draft)
+}
+
+pub(crate) fn save_variation(
+    conn: &mut Connection,
+    parent_recipe_id: i64,
+    change: &str,
+    draft: &RecipeDraft,
+) -> Result<i64, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    require_recipe(&tx, parent_recipe_id)?;
+    let recipe_id =
+        insert_recipe(&tx, draft).map_err(|e| format!("Failed to save recipe: {}", e))?;
+    tx.execute(
+        "UPDATE recipes SET parent_recipe_id = ?1, variation_request = ?2 WHERE id = ?3",
+        params![parent_recipe_id, change, recipe_id],
+    )
+    .map_err(|e| format!("Failed to link recipe variation: {}", e))?;
+    tx.commit()
+        .map_err(|e| format!("Failed to save recipe: {}", e))?;
+    Ok(recipe_id)
+}
+
+/// Saves the variation unless it breaks the profile's allergy or diet rules, e.g. a
+/// "素食版" that still uses 猪油; then nothing is written and the violations are
+/// returned instead.
+pub(crate) fn save_checked_variation(
+    conn: &mut Connection,
+    session_id: &str,
+    parent_recipe_id: i64,
+    change: &str,
+    draft: &RecipeDraft,
+) -> Result<(Option<i64>, Vec<DietaryViolation>), String> {
+    let rules = rules_for_session(conn, session_id, &[], &[])?;
+    let violations = check_draft(&rules, draft);
+    if !violations.is_empty() {
+        return Ok((None, violations));
+    }
+    let recipe_id = save_variation(conn, parent_recipe_id, change, draft)?;
+    Ok((Some(recipe_id), violations))
+}
+
+/// Walks up to the oldest ancestor, then returns every descendant of it ordered by
+/// depth so the UI can render the whole tree from one call.
+pub(crate) fn load_family(conn: &Connection, recipe_id: i64) -> Result<RecipeFamily, String> {
+    require_recipe(conn, recipe_id)?;
+    let root_id: i64 = conn
+        .query_row(
+            "WITH RECURSIVE ancestors(id, parent_recipe_id, depth) AS (
+                 SELECT id, parent_recipe_id, 0 FROM recipes WHERE id = ?1
+                 UNION
+                 SELECT r.id, r.parent_recipe_id, a.depth + 1
+                 FROM recipes r JOIN ancestors a ON r.id = a.parent_recipe_id
+                 WHERE a.depth < 100
+             )
+             SELECT id FROM ancestors ORDER BY depth DESC LIMIT 1",
+            [recipe_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to load recipe family: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "WITH RECURSIVE family(id, depth) AS (
+                 SELECT ?1, 0
+                 UNION
+                 SELECT r.id, f.depth + 1
+                 FROM recipes r JOIN family f ON r.parent_recipe_id = f.id
+                 WHERE f.depth < 100
+             )
+             SELECT r.id, r.title, r.parent_recipe_id, r.variation_request, f.depth, r.created_at
+             FROM family f JOIN recipes r ON r.id = f.id
+             ORDER BY f.depth, r.id",
+        )
+        .map_err(|e| format!("Failed to load recipe family: {}", e))?;
+    let members = stmt
+        .query_map([root_id], |row| {
+            Ok(RecipeFamilyMember {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                parent_recipe_id: row.get(2)?,
+                variation_request: row.get(3)?,
+                depth: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to load recipe family: {}", e))?;
+    Ok(RecipeFamily { root_id, members })
+}
+
+/// Asks the provider to adapt a stored recipe (e.g. "更辣", "素食版", "空气炸锅")
+/// and saves the result as a child of it when it passes the dietary check.
+#[tauri::command]
+pub(crate) async fn ai_generate_variation(
+    provider_id: String,
+    recipe_id: i64,
+    change: String,
+    app: AppHandle,
+    db: State<'_, DatabaseState>,
+    profiles: State<'_, ProfileState>,
+) -> Result<RecipeVariation, String> {
+    let change = change.trim().to_string();
+    if change.is_empty() || change.chars().count() > 500 {
+        return Err("Variation request is invalid".to_string());
+    }
+    validate_provider_id(&provider_id)?;
+    let session_id = profiles.session_id()?;
+    let (parent, chain) = {
+        let conn = db.open_connection()?;
+        (require_recipe(&conn, recipe_id)?, load_chain(&conn)?)
+    };
+
+    let prompt = variation_prompt(&parent, &change);
+    let answer = complete_with_fallback(
+        &app,
+        &attempt_order(&provider_id, &chain),
+        |provider_id, credential| {
+            let prompt = &prompt;
+            async move {
+                request_completion(
+                    &provider_id,
+                    &credential,
+                    prompt,
+                    &CompletionOptions::new(VARIATION_MAX_TOKENS, VARIATION_TEMPERATURE),
+                )
+                .await
+            }
+        },
+    )
+    .await?;
+    // Record the profile that actually answered, which may be a fallback.
+    let mut recipe = parse_variation(&answer.value)?;
+    recipe.ai_provider = Some(answer.provider_id);
+    recipe.ai_model = Some(answer.model);
+
+    let mut conn = db.open_connection()?;
+    let (child_id, violations) =
+        save_checked_variation(&mut conn, &session_id, recipe_id, &change, &recipe)?;
+    Ok(RecipeVariation {
+        recipe_id: child_id,
+        parent_recipe_id: recipe_id,
+        recipe,
+        violations,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn recipe_family(
+    recipe_id: i64,
+    db: State<DatabaseState>,
+) -> Result<RecipeFamily, String> {
+    let conn = db.open_connection()?;
+    load_family(&conn, recipe_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::{save_health_profile, ActivityLevel, Gender, HealthProfile};
+    use crate::initialize_schema;
+
+    fn draft(title: &str) -> RecipeDraft {
+        RecipeDraft {
+            title: title.to_string(),
+            ingredients: vec!["鸡胸肉 300g".to_string()],
+            instructions: vec!["煎熟".to_string()],
+            ..RecipeDraft::default()
+        }
+    }
+
+    #[test]
+    fn parses_fenced_recipe_json_from_completion() {
+        let content = "好的，这是改编后的菜谱：\n```json\n{\"title\": \"麻辣鸡胸\", \
+            \"ingredients\": [{\"name\": \"鸡胸肉\", \"amount\": 300, \"unit\": \"g\"}, \"干辣椒 10个\"], \
+            \"instructions\": [\"腌制\", {\"description\": \"大火爆炒\"}], \"servings\": 2}\n```";
+        let recipe = parse_variation(content).expect("parse variation");
+        assert_eq!(recipe.title, "麻辣鸡胸");
+        assert_eq!(recipe.ingredients.len(), 2);
+        assert_eq!(recipe.servings, Some(2));
+
+        assert!(parse_variation("抱歉，我无法完成").is_err());
+        assert!(parse_variation("{\"title\": \"空菜谱\"}").is_err());
+    }
+
+    #[test]
+    fn walks_the_family_tree_from_any_member() {
+        let mut conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        let root = insert_recipe(&conn, &draft("香煎鸡胸")).expect("insert root");
+        let spicy = save_variation(&mut conn, root, "更辣", &draft("香辣鸡胸")).expect("spicy");
+        let air = save_variation(&mut conn, root, "空气炸锅", &draft("空气炸锅鸡胸")).expect("air");
+        let spicier =
+            save_variation(&mut conn, spicy, "再辣一点", &draft("特辣鸡胸")).expect("spicier");
+
+        let family = load_family(&conn, spicier).expect("load family");
+        assert_eq!(family.root_id, root);
+        let ids: Vec<_> = family.members.iter().map(|member| member.id).collect();
+        assert_eq!(ids, vec![root, spicy, air, spicier]);
+        assert_eq!(family.members[3].depth, 2);
+        assert_eq!(family.members[3].parent_recipe_id, Some(spicy));
+        assert_eq!(
+            family.members[3].variation_request.as_deref(),
+            Some("再辣一点")
+        );
+
+        conn.execute("DELETE FROM recipes WHERE id = ?1", [spicy])
+            .expect("delete middle recipe");
+        let orphan = load_family(&conn, spicier).expect("load orphaned family");
+        assert_eq!(orphan.root_id, spicier);
+        assert!(save_variation(&mut conn, 9_999, "不存在", &draft("孤儿")).is_err());
+    }
+
+    #[test]
+    fn refuses_variations_that_break_the_profile_diet() {
+        let mut conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        initialize_schema(&conn).expect("initialize schema");
+        save_health_profile(
+            &conn,
+            "s1",
+            HealthProfile {
+                name: "测试".to_string(),
+                age: 30,
+                gender: Gender::Female,
+                height_cm: 165.0,
+                weight_kg: 55.0,
+                activity_level: ActivityLevel::Moderate,
+                health_goals: Vec::new(),
+                medical_conditions: Vec::new(),
+                allergies: Vec::new(),
+                dietary_restrictions: vec!["素食".to_string()],
+            },
+        )
+        .expect("save health profile");
+        let root = insert_recipe(&conn, &draft("葱油拌面")).expect("insert root");
+
+        let mut lard = draft("素食葱油拌面");
+        lard.ingredients = vec!["面条 200g".to_string(), "猪油 1勺".to_string()];
+        let (recipe_id, violations) =
+            save_checked_variation(&mut conn, "s1", root, "素食版", &lard).expect("check");
+        assert_eq!(recipe_id, None);
+        assert_eq!(violations.len(), 1);
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM recipes", [], |row| row.get(0))
+            .expect("count recipes");
+        assert_eq!(count, 1);
+
+        lard.ingredients = vec!["面条 200g".to_string(), "葱油 1勺".to_string()];
+        let (recipe_id, violations) =
+            save_checked_variation(&mut conn, "s1", root, "素食版", &lard).expect("save");
+        assert!(recipe_id.is_some());
+        assert!(violations.is_empty());
+    }
+}
@@ -18,6 +18,8 @@ interface RecipeDocument {
   nutritionInfo: NutritionInfo
   imageUrl: string
   cookingMethods: string[]
+  aiProvider?: string
+  aiModel?: string
   createdAt: Date
   updatedAt: Date
   viewCount: number
@@ -51,6 +53,8 @@ class RecipeModel {
       nutrition_info: JSON.stringify(recipeData.nutritionInfo),
       image_url: recipeData.imageUrl || '',
       cooking_methods: JSON.stringify(recipeData.cookingMethods || ['炒']),
+      ai_provider: recipeData.aiProvider ?? null,
+      ai_model: recipeData.aiModel ?? null,
       view_count: recipeData.viewCount || 0,
       favorite_count: recipeData.favoriteCount || 0,
       rating_count: recipeData.ratingCount || 0,
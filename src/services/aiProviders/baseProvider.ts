@@ -13,6 +13,13 @@ export interface TextGenerationOptions {
   temperature?: number
 }
 
+export interface TextGenerationResult {
+  content: string
+  /** Keyring profile that answered; on desktop this may be a fallback in the chain. */
+  providerId?: string
+  model?: string
+}
+
 /**
  * AI提供者基础接口
  * 所有AI提供者都应实现此接口
@@ -23,6 +30,14 @@ export type BaseAIProvider = {
    */
   generateText?(prompt: string, options?: TextGenerationOptions): Promise<string>
 
+  /**
+   * Like generateText, but also reports which provider and model produced the answer.
+   */
+  generateTextWithSource?(
+    prompt: string,
+    options?: TextGenerationOptions
+  ): Promise<TextGenerationResult>
+
   /**
    * 生成食谱
    * @param params 食谱生成参数
@@ -34,7 +34,8 @@ class AIProviderFactory {
         'openai',
         savedConfig.apiKey || undefined,
         savedConfig.baseUrl,
-        savedConfig.model
+        savedConfig.model,
+        savedConfig.credentialId
       )
     }
 
@@ -53,11 +54,12 @@ class AIProviderFactory {
     providerName: AIProviderName | string,
     apiKey?: string,
     baseUrl?: string,
-    model?: string
+    model?: string,
+    credentialId = 'openai'
   ): BaseAIProvider {
     if (providerName === 'openai') {
       this.currentProviderName = 'openai'
-      this.currentProvider = new OpenAICompatibleProvider(apiKey, baseUrl, model, 'openai')
+      this.currentProvider = new OpenAICompatibleProvider(apiKey, baseUrl, model, credentialId)
       return this.currentProvider
     }
 
@@ -1,4 +1,4 @@
-import type { BaseAIProvider, TextGenerationOptions } from './baseProvider'
+import type { BaseAIProvider, TextGenerationOptions, TextGenerationResult } from './baseProvider'
 import type {
   Recipe,
   RecipeGenerationParams,
@@ -38,13 +38,28 @@ export class OpenAICompatibleProvider implements BaseAIProvider {
     prompt: string,
     options?: TextGenerationOptions
   ): Promise<string> {
+    return (await this.requestChat(prompt, options)).content
+  }
+
+  private async requestChat(
+    prompt: string,
+    options?: TextGenerationOptions
+  ): Promise<TextGenerationResult> {
     if (typeof window !== 'undefined' && window.__TAURI__?.invoke && !this.apiKey) {
-      return (await window.__TAURI__.invoke('ai_chat_completion', {
+      // The native side may answer from a fallback provider in the configured chain.
+      const completion = (await window.__TAURI__.invoke('ai_chat_completion', {
         providerId: this.providerId,
         prompt,
-        maxTokens: options?.maxTokens || 2000,
-        temperature: options?.temperature || 0.7,
-      })) as string
+        options: {
+          max_tokens: options?.maxTokens || 2000,
+          temperature: options?.temperature || 0.7,
+        },
+      })) as { content: string; provider_id: string; model: string }
+      return {
+        content: completion.content,
+        providerId: completion.provider_id,
+        model: completion.model,
+      }
     }
 
     if (!this.apiKey) {
@@ -87,7 +102,11 @@ export class OpenAICompatibleProvider implements BaseAIProvider {
         throw new Error('AI 兼容接口响应格式错误')
       }
 
-      return data.choices[0].message.content
+      return {
+        content: data.choices[0].message.content,
+        providerId: this.providerId,
+        model: data.model || this.model,
+      }
     } catch (error) {
       console.error('AI 兼容接口调用失败:', error)
       throw error
@@ -98,6 +117,13 @@ export class OpenAICompatibleProvider implements BaseAIProvider {
     return this.callChatCompletion(prompt, options)
   }
 
+  async generateTextWithSource(
+    prompt: string,
+    options?: TextGenerationOptions
+  ): Promise<TextGenerationResult> {
+    return this.requestChat(prompt, options)
+  }
+
   private parseJsonResponse<T>(response: string): T {
     try {
       const jsonMatch = response.match(/\{[\s\S]*\}/)
@@ -122,15 +148,15 @@ export class OpenAICompatibleProvider implements BaseAIProvider {
       // 构建通用提示词
       const prompt = PromptBuilder.buildRecipePrompt(standardParams)
 
-      const response = await this.callChatCompletion(prompt, {
+      const answer = await this.requestChat(prompt, {
         maxTokens: 2000,
         temperature: 0.7,
       })
 
-      const recipeResult = this.parseJsonResponse<Partial<Recipe>>(response)
+      const recipeResult = this.parseJsonResponse<Partial<Recipe>>(answer.content)
       const recipe = this.buildRecipeFromResult(recipeResult, standardParams)
 
-      return recipe
+      return { ...recipe, aiProvider: answer.providerId, aiModel: answer.model }
     } catch (error) {
       console.error('AI 兼容接口生成食谱失败:', error)
       return this.createFallbackRecipe(standardParams)
@@ -7,6 +7,7 @@ import { cookingMethods } from '../data/cookingMethods'
 import { cacheData, getCachedData } from '../utils/cacheUtils'
 import { generateRecipeCoverSvg } from '../utils/svgGenerator'
 import { Recipe as RecipeModel } from '../models/Recipe'
+import { saveGeneratedRecipe } from './generatedRecipeService'
 import type {
   Recipe as IRecipe,
   RecipeGenerationRequest,
@@ -102,15 +103,17 @@ function convertNutrition(rawNutrition?: RawRecipeData['nutrition']): Nutrition
 async function generateRecipe(request: RecipeGenerationRequest): Promise<IRecipe> {
   try {
     const userPrompt = buildUserPrompt(request)
-    const response = await aiService.generateText(userPrompt, {
+    const answer = await aiService.generateTextWithSource(userPrompt, {
       temperature: 0.7,
       maxTokens: 4096,
     })
 
-    const recipeData = parseAIJsonResponse<RawRecipeData>(response)
+    const recipeData = parseAIJsonResponse<RawRecipeData>(answer.content)
     const recipe = convertToRecipe(recipeData, request)
+    recipe.aiProvider = answer.providerId
+    recipe.aiModel = answer.model
 
-    // 保存到SQLite数据库
+    // 保存到数据库；桌面端会先按用户的过敏原与饮食限制校验
     try {
       const recipeData = {
         title: recipe.title || recipe.name,
@@ -131,15 +134,24 @@ async function generateRecipe(request: RecipeGenerationRequest): Promise<IRecipe
         },
         imageUrl: recipe.image || '',
         cookingMethods: recipe.cookingMethods || ['炒'],
+        aiProvider: recipe.aiProvider,
+        aiModel: recipe.aiModel,
         viewCount: 0,
         favoriteCount: 0,
         ratingCount: 0,
         averageRating: 0,
       }
 
-      const savedRecipe = await RecipeModel.create(recipeData)
-      // 更新recipe的id为数据库中的id
-      recipe.id = savedRecipe.id.toString()
+      const result = await saveGeneratedRecipe(recipeData, {
+        dietaryRestrictions: request.dietaryRestrictions,
+      })
+      if (result.recipeId === null) {
+        // 未通过校验的菜谱不入库，把原因带给界面
+        recipe.dietaryViolations = result.violations
+      } else {
+        // 更新recipe的id为数据库中的id
+        recipe.id = result.recipeId.toString()
+      }
     } catch (dbError) {
       console.error('保存菜谱到数据库失败:', dbError)
       // 数据库保存失败，仍然返回生成的菜谱
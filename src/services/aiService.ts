@@ -1,7 +1,7 @@
 // AI服务 - 智能烹饪助手核心服务
 import { ElMessage } from 'element-plus'
 import { AIProviderFactory } from './aiProviders'
-import type { BaseAIProvider } from './aiProviders/baseProvider'
+import type { BaseAIProvider, TextGenerationResult } from './aiProviders/baseProvider'
 import type {
   Recipe,
   IngredientValidationResult,
@@ -212,29 +212,41 @@ class AIService {
     prompt: string,
     options?: { maxTokens?: number; temperature?: number }
   ): Promise<string> {
+    return (await this.generateTextWithSource(prompt, options)).content
+  }
+
+  // 同上，并返回实际作答的供应商配置与模型，供保存菜谱时记录
+  async generateTextWithSource(
+    prompt: string,
+    options?: { maxTokens?: number; temperature?: number }
+  ): Promise<TextGenerationResult> {
     if (!this.isInitialized) {
       await this.init()
     }
 
     const cacheKey = this.getCacheKey('generateText', { prompt, options })
-    const cached = this.getFromCache<string>(cacheKey)
+    const cached = this.getFromCache<TextGenerationResult>(cacheKey)
     if (cached) {
       return cached
     }
 
     try {
-      const generateText = this.currentProvider.generateText
-      if (!generateText) {
-        throw new Error('当前 AI Provider 不支持通用文本生成')
-      }
-
-      const response = await generateText.call(this.currentProvider, prompt, {
+      const provider = this.currentProvider
+      const generationOptions = {
         maxTokens: options?.maxTokens || 2000,
         temperature: options?.temperature || 0.7,
-      })
+      }
+      let result: TextGenerationResult
+      if (provider.generateTextWithSource) {
+        result = await provider.generateTextWithSource(prompt, generationOptions)
+      } else if (provider.generateText) {
+        result = { content: await provider.generateText(prompt, generationOptions) }
+      } else {
+        throw new Error('当前 AI Provider 不支持通用文本生成')
+      }
 
-      this.setCache(cacheKey, response)
-      return response
+      this.setCache(cacheKey, result)
+      return result
     } catch (error) {
       console.error('AI文本生成失败:', error)
       throw new Error('AI文本生成服务暂时不可用')
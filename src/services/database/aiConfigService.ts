@@ -7,6 +7,8 @@ export interface AIProviderConfig {
   baseUrl?: string
   model?: string
   presetId?: string
+  /** Keyring profile holding the desktop key; one per preset so keys can be chained. */
+  credentialId?: string
   isConfigured: boolean
   storage: 'memory' | 'native-keyring'
   updatedAt: string
@@ -60,6 +62,8 @@ export class AIConfigService {
         baseUrl: parsed.baseUrl,
         model: parsed.model,
         presetId: parsed.presetId,
+        // Releases before per-preset profiles kept every key under the record's own name.
+        credentialId: parsed.credentialId || provider,
         isConfigured: true,
         storage: 'native-keyring',
         updatedAt: parsed.updatedAt || new Date().toISOString(),
@@ -69,7 +73,7 @@ export class AIConfigService {
       if (parsed.apiKey && window.__TAURI__?.invoke) {
         try {
           await window.__TAURI__.invoke('credential_store', {
-            providerId: provider,
+            providerId: config.credentialId,
             apiKey: parsed.apiKey,
             baseUrl: config.baseUrl,
             model: config.model,
@@ -96,6 +100,7 @@ export class AIConfigService {
       baseUrl: config.baseUrl,
       model: config.model,
       presetId: config.presetId,
+      credentialId: config.credentialId,
       isConfigured: true,
       storage: 'native-keyring' as const,
       updatedAt: config.updatedAt,
@@ -129,6 +134,7 @@ export class AIConfigService {
       baseUrl,
       model,
       presetId: config.presetId,
+      credentialId: normalizeProvider(config.credentialId || normalizedProvider),
       isConfigured: true,
       storage: isTauri() ? 'native-keyring' : 'memory',
       updatedAt: new Date().toISOString(),
@@ -136,7 +142,7 @@ export class AIConfigService {
 
     if (isTauri()) {
       await window.__TAURI__!.invoke!('credential_store', {
-        providerId: normalizedProvider,
+        providerId: nextConfig.credentialId,
         apiKey: secret,
         baseUrl: nextConfig.baseUrl,
         model: nextConfig.model,
@@ -178,7 +184,10 @@ export class AIConfigService {
     this.clearLegacyWebKeyStorage()
 
     if (isTauri()) {
-      await window.__TAURI__!.invoke!('credential_delete', { providerId: normalizedProvider })
+      const existing = await this.readDesktopConfig(normalizedProvider)
+      await window.__TAURI__!.invoke!('credential_delete', {
+        providerId: existing?.credentialId || normalizedProvider,
+      })
       await dataAccess.execute('DELETE FROM settings WHERE key = ?', [
         `ai_${normalizedProvider}_config`,
       ])
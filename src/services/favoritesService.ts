@@ -6,6 +6,7 @@
 
 import { Favorite } from '@/models/Favorite'
 import { Recipe } from '@/models/Recipe'
+import { DietaryViolationError, saveGeneratedRecipe } from '@/services/generatedRecipeService'
 
 interface FavoriteRecipe {
   id: string
@@ -54,6 +55,7 @@ class FavoritesService {
 
   /**
    * 添加收藏
+   * 新菜谱未通过饮食规则校验时抛出 DietaryViolationError
    */
   async addFavorite(sessionId: string, recipe: FavoriteRecipe): Promise<boolean> {
     try {
@@ -67,11 +69,14 @@ class FavoritesService {
 
       // 确保食谱数据保存到 recipes 表（对于AI生成的食谱特别重要）
       try {
-        let dbRecipe = await Recipe.findById(recipeId)
-
-        if (!dbRecipe) {
-          // 如果食谱不存在，创建它
-          dbRecipe = await Recipe.create({
+        const dbRecipe = await Recipe.findById(recipeId)
+        let newRecipeId: number
+
+        if (dbRecipe) {
+          newRecipeId = dbRecipe.id
+        } else {
+          // 如果食谱不存在，创建它；桌面端会先按用户的过敏原与饮食限制校验
+          const result = await saveGeneratedRecipe({
             title: recipe.title,
             description: recipe.description || '',
             ingredients: recipe.ingredients || [],
@@ -94,11 +99,12 @@ class FavoritesService {
             ratingCount: 0,
             averageRating: 0,
           })
+          if (result.recipeId === null) {
+            throw new DietaryViolationError(result.violations)
+          }
+          newRecipeId = result.recipeId
         }
 
-        // 使用新的recipeId（可能是刚创建的）
-        const newRecipeId = dbRecipe.id
-
         // 使用 Favorite 模型添加收藏
         await Favorite.addFavorite(sessionId, newRecipeId, recipe.title, recipe.image)
 
@@ -107,10 +113,12 @@ class FavoritesService {
 
         return true
       } catch (error) {
+        if (error instanceof DietaryViolationError) throw error
         console.error('保存食谱数据到 recipes 表失败:', error)
         return false
       }
     } catch (error) {
+      if (error instanceof DietaryViolationError) throw error
       console.error('添加收藏失败:', error)
       return false
     }
@@ -0,0 +1,100 @@
+/**
+ * AI 生成菜谱的保存入口
+ * 桌面端经由原生 `recipe_save_generated` 保存，保存前按当前用户的过敏原与饮食限制校验；
+ * Web 版没有原生规则引擎，直接写入本地存储
+ */
+
+import { Recipe as RecipeModel } from '@/models/Recipe'
+import type { DietaryViolation } from '@/types/recipe'
+
+export type GeneratedRecipeData = Parameters<typeof RecipeModel.create>[0]
+
+export interface GeneratedRecipeSaveResult {
+  // 被规则拦截时为 null，菜谱未写入数据库
+  recipeId: number | null
+  violations: DietaryViolation[]
+}
+
+const ALLERGEN_LABELS: Record<string, string> = {
+  peanut: '花生',
+  tree_nut: '坚果',
+  shellfish: '甲壳类海鲜',
+  fish: '鱼类',
+  gluten: '麸质',
+  dairy: '乳制品',
+  egg: '蛋类',
+  soy: '大豆',
+  sesame: '芝麻',
+}
+
+const DIET_LABELS: Record<string, string> = {
+  vegetarian: '素食',
+  vegan: '纯素',
+  halal: '清真',
+  low_sodium: '低钠',
+}
+
+export function describeViolation(violation: DietaryViolation): string {
+  switch (violation.kind) {
+    case 'allergen':
+      return `${violation.ingredient} 含有${ALLERGEN_LABELS[violation.class || ''] || violation.class}过敏原`
+    case 'custom_allergen':
+      return `${violation.ingredient} 含有过敏原「${violation.allergen}」`
+    case 'diet':
+      return `${violation.ingredient} 不符合${DIET_LABELS[violation.class || ''] || violation.class}饮食`
+    case 'sodium_limit':
+      return `每份钠含量 ${Math.round(violation.sodium_mg || 0)}mg，超过低钠上限 ${violation.limit_mg}mg`
+  }
+}
+
+export function describeViolations(violations: DietaryViolation[]): string {
+  return `菜谱未保存：${violations.map(describeViolation).join('；')}`
+}
+
+/** 菜谱未通过饮食规则校验时抛出，message 可直接展示给用户 */
+export class DietaryViolationError extends Error {
+  constructor(public readonly violations: DietaryViolation[]) {
+    super(describeViolations(violations))
+    this.name = 'DietaryViolationError'
+  }
+}
+
+/**
+ * 保存 AI 生成的菜谱
+ * @param recipeData 菜谱数据
+ * @param rules 在用户档案之外额外校验的过敏原与饮食限制
+ */
+export async function saveGeneratedRecipe(
+  recipeData: GeneratedRecipeData,
+  rules: { allergens?: string[]; dietaryRestrictions?: string[] } = {}
+): Promise<GeneratedRecipeSaveResult> {
+  const invoke = typeof window !== 'undefined' ? window.__TAURI__?.invoke : undefined
+  if (!invoke) {
+    const savedRecipe = await RecipeModel.create(recipeData)
+    return { recipeId: savedRecipe.id, violations: [] }
+  }
+
+  const result = (await invoke('recipe_save_generated', {
+    recipe: {
+      title: recipeData.title,
+      description: recipeData.description,
+      ingredients: recipeData.ingredients,
+      instructions: recipeData.instructions,
+      cooking_time: recipeData.cookingTime,
+      difficulty: String(recipeData.difficulty),
+      servings: recipeData.servings,
+      category: recipeData.category,
+      tags: recipeData.tags,
+      nutrition_info: recipeData.nutritionInfo,
+      image_url: recipeData.imageUrl || null,
+      cooking_methods: recipeData.cookingMethods,
+      // The profile that actually answered, which may be a fallback in the chain.
+      ai_provider: recipeData.aiProvider ?? null,
+      ai_model: recipeData.aiModel ?? null,
+    },
+    allergens: rules.allergens || [],
+    dietaryRestrictions: rules.dietaryRestrictions || [],
+  })) as { recipe_id: number | null; violations: DietaryViolation[] }
+
+  return { recipeId: result.recipe_id, violations: result.violations }
+}
@@ -11,6 +11,7 @@ const LOCAL_DATA_KEYS = new Set([
   'chefmind-theme',
   'chefmind-theme-auto',
   'chefMind_userProfile',
+  'chefMind_userMeals',
   'savedRecipes',
   'recipe-view-history',
   'currentCookingRecipe',
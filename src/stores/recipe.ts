@@ -2,8 +2,16 @@
 
 import { ref, computed } from 'vue'
 import { defineStore } from 'pinia'
-import type { Ingredient, CookingMethod, Recipe, RecipeGenerationRequest } from '@/types/recipe'
+import { ElMessage } from 'element-plus'
+import type {
+  Ingredient,
+  CookingMethod,
+  Recipe,
+  RecipeGenerationRequest,
+  DietaryViolation,
+} from '@/types/recipe'
 import aiRecipeService from '@/services/aiRecipeService'
+import { describeViolations, saveGeneratedRecipe } from '@/services/generatedRecipeService'
 import { Recipe as RecipeModel } from '@/models/Recipe'
 
 export const useRecipeStore = defineStore('recipe', () => {
@@ -25,6 +33,8 @@ export const useRecipeStore = defineStore('recipe', () => {
   const currentStep = ref(1)
   const isGenerating = ref(false)
   const savedRecipes = ref<Recipe[]>([])
+  // 最近一次保存被饮食规则拦截的原因
+  const saveViolations = ref<DietaryViolation[]>([])
 
   // 计算属性
   const canProceedToStep2 = computed(() => {
@@ -158,20 +168,32 @@ export const useRecipeStore = defineStore('recipe', () => {
           },
         imageUrl: recipe.image || '',
         cookingMethods: recipe.cookingMethods || ['炒'],
+        aiProvider: recipe.aiProvider,
+        aiModel: recipe.aiModel,
         viewCount: 0,
         favoriteCount: 0,
         ratingCount: 0,
         averageRating: recipe.rating || 0,
       }
 
-      const savedRecipe = await RecipeModel.create(recipeData)
+      // 桌面端保存前按当前用户的过敏原与饮食限制校验，拦截时不写入并提示原因
+      saveViolations.value = []
+      const result = await saveGeneratedRecipe(recipeData, {
+        allergens: recipe.allergies || [],
+        dietaryRestrictions: recipe.dietaryRestrictions || constraints.value.dietaryRestrictions,
+      })
+      if (result.recipeId === null) {
+        saveViolations.value = result.violations
+        ElMessage.warning({ message: describeViolations(result.violations), showClose: true })
+        return false
+      }
 
       // 更新本地状态
       const existingIndex = savedRecipes.value.findIndex(r => r.id === recipe.id)
       if (existingIndex === -1) {
         savedRecipes.value.push({
           ...recipe,
-          id: savedRecipe.id.toString(),
+          id: result.recipeId.toString(),
         })
       }
 
@@ -280,6 +302,7 @@ export const useRecipeStore = defineStore('recipe', () => {
     setStep,
     generateRecipes,
     saveRecipe,
+    saveViolations,
     removeRecipe,
     isRecipeSaved,
     resetSelection,
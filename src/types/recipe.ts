@@ -306,6 +306,16 @@ export interface RecipeStep {
   tips?: string[]
 }
 
+// 原生 `recipe_save_generated` 返回的违规项
+export interface DietaryViolation {
+  kind: 'allergen' | 'custom_allergen' | 'diet' | 'sodium_limit'
+  ingredient?: string
+  class?: string
+  allergen?: string
+  sodium_mg?: number
+  limit_mg?: number
+}
+
 // 食谱
 export interface Recipe {
   id: string
@@ -346,6 +356,9 @@ export interface Recipe {
   healthBenefits?: string[] // 健康益处
   autoCompletedIngredients?: string[] // 自动补充的食材列表
   aiGenerated?: boolean // AI生成标记
+  aiProvider?: string // 实际生成的供应商配置（可能是备用供应商）
+  aiModel?: string // 实际生成的模型
+  dietaryViolations?: DietaryViolation[] // 未通过饮食规则校验而未保存的原因
   tips?: string // 小贴士
 }
 